@@ -194,7 +194,10 @@ export class CustomAnalysisView extends React.Component<CustomAnalysisViewProps,
                         };
                     })}
                     rows={this.state.result.rows}
-                    extractColumn={(row, name) => row[columnNameToIndex.get(name)]}
+                    extractColumn={(row, name) => {
+                        const value = row[columnNameToIndex.get(name)];
+                        return value !== null && typeof value === "object" ? JSON.stringify(value) : value;
+                    }}
                     initialSortOrder={{
                         columnIndex: 0,
                         order: 1
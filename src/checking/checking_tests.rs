@@ -0,0 +1,176 @@
+use std::path::Path;
+
+use crate::checking::output::{to_junit, to_sarif};
+use crate::checking::rules::{run_checks, CheckBaseline, CheckKind, CheckRule, CheckRules, CheckViolation};
+use crate::checking::CheckingError;
+use crate::querying::engine::RepositoryQueryingConfig;
+use crate::querying::querying_tests::create_querying;
+
+#[test]
+fn test_check_rules() {
+    let rules = CheckRules::new(
+        r#"
+        # Recent hotspots
+        hotspot max_revisions=20 max_code_lines=500 days=90
+        coupling_violations
+        truck_factor min=2 modules=backend/* depth=1
+        "#
+    ).unwrap();
+
+    assert_eq!(3, rules.rules.len());
+    assert_eq!(3, rules.rules[0].line_number);
+    assert_eq!("hotspot-3", rules.rules[0].id());
+    assert_eq!(CheckKind::Hotspot { max_revisions: 20, max_code_lines: 500, days: Some(90) }, rules.rules[0].kind);
+    assert_eq!(CheckKind::CouplingViolations { days: None }, rules.rules[1].kind);
+    assert_eq!("truck_factor min=2 modules=backend/* depth=1", rules.rules[2].definition);
+    assert!(matches!(&rules.rules[2].kind, CheckKind::TruckFactor { min: 2, modules, depth: Some(1) } if modules.as_str() == "backend/*"));
+
+    let error_message = |definition: &str| {
+        match CheckRules::new(definition) {
+            Err(CheckingError::Rule(line_number, message)) => format!("{}: {}", line_number, message),
+            result => panic!("expected rule error but got {:?}", result)
+        }
+    };
+
+    assert_eq!("2: unknown check 'hotspots' (expected hotspot, coupling_violations or truck_factor)", error_message("coupling_violations\nhotspots max_revisions=1"));
+    assert_eq!("1: missing option 'max_revisions'", error_message("hotspot max_code_lines=1"));
+    assert_eq!("1: invalid value 'many' for 'min'", error_message("truck_factor min=many"));
+    assert_eq!("1: unknown option 'max' for 'coupling_violations'", error_message("coupling_violations max=1"));
+    assert_eq!("1: expected 'key=value' but got 'days'", error_message("coupling_violations days"));
+    assert_eq!("1: invalid value '36501' for 'days' (at most 36500 days)", error_message("coupling_violations days=36501"));
+    assert_eq!("1: invalid value '-1' for 'days'", error_message("hotspot max_revisions=1 days=-1"));
+
+    assert!(matches!(CheckRules::load(Path::new("test_data/sqlgrep")), Err(CheckingError::Read(_, _))));
+}
+
+#[tokio::test]
+async fn test_run_checks() {
+    let repository_querying = create_querying().await;
+
+    let rules = CheckRules::new(
+        r#"
+        hotspot max_revisions=60 max_code_lines=300
+        hotspot max_revisions=60 max_code_lines=300 days=1
+        coupling_violations
+        truck_factor min=1
+        truck_factor min=2 modules=exec*
+        "#
+    ).unwrap();
+
+    let results = run_checks(&repository_querying, &RepositoryQueryingConfig::default(), &rules).await.unwrap();
+    assert_eq!(5, results.len());
+
+    assert_eq!(
+        vec!["src/model.rs", "src/main.rs"],
+        results[0].violations.iter().map(|violation| violation.name.as_str()).collect::<Vec<_>>()
+    );
+    assert!(results[0].violations[0].is_file);
+    assert_eq!("src/model.rs has been changed 67 times (max 60) and has 932 lines of code (max 300)", results[0].violations[0].message);
+
+    assert!(results[1].violations.is_empty());
+
+    assert_eq!(11, results[2].violations.len());
+    assert_eq!(
+        "model and tests have been changed together in 19 revisions (forbidden by 'forbid model <-> tests')",
+        results[2].violations[0].message
+    );
+
+    assert!(results[3].violations.is_empty());
+
+    assert_eq!(
+        vec![
+            CheckViolation {
+                name: "execution".to_owned(),
+                is_file: false,
+                message: "execution has a truck factor of 1 (min 2) with 1 authors".to_owned(),
+                accepted: false
+            }
+        ],
+        results[4].violations
+    );
+
+    // A window starting before the representable dates is an error rather than an overflow
+    let rules = CheckRules {
+        rules: vec![
+            CheckRule {
+                line_number: 1,
+                definition: "coupling_violations".to_owned(),
+                kind: CheckKind::CouplingViolations { days: Some(u64::MAX) }
+            }
+        ]
+    };
+    let result = run_checks(&repository_querying, &RepositoryQueryingConfig::default(), &rules).await;
+    assert!(matches!(result, Err(CheckingError::DaysOutOfRange(u64::MAX))));
+}
+
+#[tokio::test]
+async fn test_check_output() {
+    let repository_querying = create_querying().await;
+
+    let rules = CheckRules::new("coupling_violations\nhotspot max_revisions=60 max_code_lines=900\ntruck_factor min=1").unwrap();
+    let results = run_checks(&repository_querying, &RepositoryQueryingConfig::default(), &rules).await.unwrap();
+
+    let junit = to_junit(&results);
+    assert!(junit.contains("<testsuites name=\"gitrends\" tests=\"3\" failures=\"2\">"));
+    assert!(junit.contains("<testcase classname=\"gitrends.coupling_violations\" name=\"coupling_violations (line 1)\">"));
+    assert!(junit.contains("<failure type=\"coupling_violations\" message=\"11 violation(s)\">"));
+    assert!(junit.contains("(forbidden by &apos;forbid model &lt;-&gt; tests&apos;)"));
+    assert!(junit.contains("<testcase classname=\"gitrends.truck_factor\" name=\"truck_factor min=1 (line 3)\"/>"));
+
+    let sarif = serde_json::from_str::<serde_json::Value>(&to_sarif(&results)).unwrap();
+    let run = &sarif["runs"][0];
+    assert_eq!("2.1.0", sarif["version"]);
+    assert_eq!(3, run["tool"]["driver"]["rules"].as_array().unwrap().len());
+    assert_eq!(12, run["results"].as_array().unwrap().len());
+
+    let module_result = &run["results"][0];
+    assert_eq!("coupling_violations-1", module_result["ruleId"]);
+    assert_eq!("model <-> tests", module_result["locations"][0]["logicalLocations"][0]["name"]);
+
+    let file_result = &run["results"][11];
+    assert_eq!("hotspot-2", file_result["ruleId"]);
+    assert_eq!(1, file_result["ruleIndex"]);
+    assert_eq!("src/model.rs", file_result["locations"][0]["physicalLocation"]["artifactLocation"]["uri"]);
+}
+
+#[tokio::test]
+async fn test_check_baseline() {
+    let repository_querying = create_querying().await;
+
+    let rules = CheckRules::new("coupling_violations\nhotspot max_revisions=60 max_code_lines=300\ncoupling_violations days=1").unwrap();
+    let mut results = run_checks(&repository_querying, &RepositoryQueryingConfig::default(), &rules).await.unwrap();
+    assert!(results[2].violations.is_empty());
+
+    let definition = CheckBaseline::create_definition(&results);
+    assert_eq!(13, definition.lines().count());
+    assert!(definition.starts_with("coupling_violations <root> <-> cli\n"));
+    assert!(definition.ends_with("hotspot src/main.rs\nhotspot src/model.rs\n"));
+
+    // A removed violation is ignored, while a violation not in the baseline is new
+    let baseline = CheckBaseline::new(
+        "# Accepted\ncoupling_violations model <-> tests\ncoupling_violations a <-> b\nhotspot src/model.rs"
+    ).unwrap();
+    baseline.accept(&mut results);
+
+    let new_violations = |index: usize| results[index].new_violations().map(|violation| violation.name.as_str()).collect::<Vec<_>>();
+    assert_eq!(10, new_violations(0).len());
+    assert!(!new_violations(0).contains(&"model <-> tests"));
+    assert_eq!(vec!["src/main.rs"], new_violations(1));
+
+    let junit = to_junit(&results);
+    assert!(junit.contains("<failure type=\"coupling_violations\" message=\"10 violation(s) (1 accepted by the baseline)\">"));
+    assert!(junit.contains("<failure type=\"hotspot\" message=\"1 violation(s) (1 accepted by the baseline)\">"));
+
+    let sarif = serde_json::from_str::<serde_json::Value>(&to_sarif(&results)).unwrap();
+    let sarif_results = sarif["runs"][0]["results"].as_array().unwrap();
+    assert_eq!(13, sarif_results.len());
+    assert_eq!(11, sarif_results.iter().filter(|result| result["baselineState"] == "new").count());
+    assert_eq!("note", sarif_results[0]["level"]);
+    assert_eq!("unchanged", sarif_results[0]["baselineState"]);
+
+    let result = CheckBaseline::new("hotspot src/model.rs\nhotspot");
+    assert_eq!(
+        "Invalid baseline entry at line 2: expected '<check> <name>' but got 'hotspot'",
+        result.err().unwrap().to_string()
+    );
+}
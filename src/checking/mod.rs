@@ -0,0 +1,31 @@
+use thiserror::Error;
+
+use crate::querying::QueryingError;
+
+pub mod rules;
+pub mod output;
+
+#[cfg(test)]
+pub mod checking_tests;
+
+type CheckingResult<T> = Result<T, CheckingError>;
+
+#[derive(Debug, Error)]
+pub enum CheckingError {
+    #[error("Failed to read the rules file '{0}': {1}")]
+    Read(String, std::io::Error),
+    #[error("Invalid rule at line {0}: {1}")]
+    Rule(usize, String),
+    #[error("Invalid baseline entry at line {0}: {1}")]
+    Baseline(usize, String),
+    #[error("The window of the last {0} days is out of range")]
+    DaysOutOfRange(u64),
+    #[error("Querying: {0}")]
+    Querying(QueryingError)
+}
+
+impl From<QueryingError> for CheckingError {
+    fn from(err: QueryingError) -> Self {
+        CheckingError::Querying(err)
+    }
+}
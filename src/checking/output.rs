@@ -0,0 +1,106 @@
+use std::fmt::Write;
+
+use serde_json::json;
+
+use crate::checking::rules::CheckResult;
+use crate::querying::graph_export::escape_xml;
+
+// Each rule is a test case, where the violations of the rule not accepted by the baseline are the failure
+pub fn to_junit(results: &[CheckResult]) -> String {
+    let num_failures = results.iter().filter(|result| result.new_violations().next().is_some()).count();
+
+    let mut output = String::new();
+    output += "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n";
+    writeln!(output, "<testsuites name=\"gitrends\" tests=\"{}\" failures=\"{}\">", results.len(), num_failures).unwrap();
+    writeln!(output, "  <testsuite name=\"gitrends checks\" tests=\"{}\" failures=\"{}\">", results.len(), num_failures).unwrap();
+
+    for result in results {
+        let rule = &result.rule;
+        write!(
+            output,
+            "    <testcase classname=\"gitrends.{}\" name=\"{} (line {})\"",
+            rule.kind.name(),
+            escape_xml(&rule.definition),
+            rule.line_number
+        ).unwrap();
+
+        let messages = result.new_violations().map(|violation| violation.message.as_str()).collect::<Vec<_>>();
+        if messages.is_empty() {
+            output += "/>\n";
+            continue;
+        }
+
+        let num_accepted = result.violations.len() - messages.len();
+        output += ">\n";
+        writeln!(
+            output,
+            "      <failure type=\"{}\" message=\"{} violation(s){}\">{}</failure>",
+            rule.kind.name(),
+            messages.len(),
+            if num_accepted > 0 { format!(" ({} accepted by the baseline)", num_accepted) } else { String::new() },
+            escape_xml(&messages.join("\n"))
+        ).unwrap();
+        output += "    </testcase>\n";
+    }
+
+    output += "  </testsuite>\n";
+    output += "</testsuites>\n";
+    output
+}
+
+// Violations of files have the file as location, while violations of modules have the module as a logical location.
+// Violations accepted by the baseline are notes with the baseline state 'unchanged'.
+pub fn to_sarif(results: &[CheckResult]) -> String {
+    let rules = results
+        .iter()
+        .map(|result| {
+            json!({
+                "id": result.rule.id(),
+                "name": result.rule.kind.name(),
+                "shortDescription": { "text": result.rule.definition }
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let mut sarif_results = Vec::new();
+    for (rule_index, result) in results.iter().enumerate() {
+        for violation in &result.violations {
+            let location = if violation.is_file {
+                json!({ "physicalLocation": { "artifactLocation": { "uri": violation.name } } })
+            } else {
+                json!({ "logicalLocations": [{ "name": violation.name, "kind": "module" }] })
+            };
+
+            sarif_results.push(
+                json!({
+                    "ruleId": result.rule.id(),
+                    "ruleIndex": rule_index,
+                    "level": if violation.accepted { "note" } else { "error" },
+                    "baselineState": if violation.accepted { "unchanged" } else { "new" },
+                    "message": { "text": violation.message },
+                    "locations": [location]
+                })
+            );
+        }
+    }
+
+    let sarif = json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [
+            {
+                "tool": {
+                    "driver": {
+                        "name": "gitrends",
+                        "version": env!("CARGO_PKG_VERSION"),
+                        "informationUri": env!("CARGO_PKG_HOMEPAGE"),
+                        "rules": rules
+                    }
+                },
+                "results": sarif_results
+            }
+        ]
+    });
+
+    serde_json::to_string_pretty(&sarif).unwrap()
+}
@@ -0,0 +1,353 @@
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::str::FromStr;
+
+use glob::Pattern;
+
+use crate::checking::{CheckingError, CheckingResult};
+use crate::querying::engine::{RepositoryQuerying, RepositoryQueryingConfig};
+
+pub const CHECKS_FILE: &str = "checks.txt";
+pub const CHECKS_BASELINE_FILE: &str = "checks_baseline.txt";
+
+// The largest window that a rule can consider, which keeps the start of the window within the representable dates
+const MAX_DAYS: u64 = 100 * 365;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CheckKind {
+    // Files changed more than the max revisions that also have more than the max lines of code
+    Hotspot {
+        max_revisions: u64,
+        max_code_lines: u64,
+        days: Option<u64>
+    },
+    // Module couplings forbidden by the architecture rules
+    CouplingViolations {
+        days: Option<u64>
+    },
+    // Modules (matching the pattern) with a truck factor below the minimum
+    TruckFactor {
+        min: u64,
+        modules: Pattern,
+        depth: Option<usize>
+    }
+}
+
+impl CheckKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CheckKind::Hotspot { .. } => "hotspot",
+            CheckKind::CouplingViolations { .. } => "coupling_violations",
+            CheckKind::TruckFactor { .. } => "truck_factor"
+        }
+    }
+
+    // The number of days of the window that the check considers (all commits if not set)
+    pub fn days(&self) -> Option<u64> {
+        match self {
+            CheckKind::Hotspot { days, .. } | CheckKind::CouplingViolations { days } => *days,
+            CheckKind::TruckFactor { .. } => None
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CheckRule {
+    pub line_number: usize,
+    pub definition: String,
+    pub kind: CheckKind
+}
+
+impl CheckRule {
+    pub fn id(&self) -> String {
+        format!("{}-{}", self.kind.name(), self.line_number)
+    }
+}
+
+#[derive(Debug)]
+pub struct CheckRules {
+    pub rules: Vec<CheckRule>
+}
+
+impl CheckRules {
+    // One rule per line, such as 'hotspot max_revisions=20 max_code_lines=500 days=90'
+    pub fn new(definition: &str) -> CheckingResult<CheckRules> {
+        let mut rules = Vec::new();
+
+        for (line_index, line) in definition.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let line_number = line_index + 1;
+            let mut parts = line.split_whitespace();
+            let kind_name = parts.next().unwrap_or("");
+
+            let mut options = HashMap::new();
+            for part in parts {
+                let (key, value) = part.split_once('=')
+                    .ok_or_else(|| CheckingError::Rule(line_number, format!("expected 'key=value' but got '{}'", part)))?;
+                options.insert(key, value);
+            }
+
+            let mut options = RuleOptions { line_number, options };
+            let kind = match kind_name {
+                "hotspot" => {
+                    CheckKind::Hotspot {
+                        max_revisions: options.required("max_revisions")?,
+                        max_code_lines: options.optional("max_code_lines")?.unwrap_or(0),
+                        days: options.days()?
+                    }
+                }
+                "coupling_violations" => {
+                    CheckKind::CouplingViolations {
+                        days: options.days()?
+                    }
+                }
+                "truck_factor" => {
+                    let modules = options.options.remove("modules").unwrap_or("*");
+                    CheckKind::TruckFactor {
+                        min: options.required("min")?,
+                        modules: Pattern::new(modules).map_err(|err| CheckingError::Rule(line_number, format!("pattern: {}", err)))?,
+                        depth: options.optional("depth")?
+                    }
+                }
+                _ => {
+                    return Err(CheckingError::Rule(
+                        line_number,
+                        format!("unknown check '{}' (expected hotspot, coupling_violations or truck_factor)", kind_name)
+                    ));
+                }
+            };
+
+            if let Some(key) = options.options.keys().next() {
+                return Err(CheckingError::Rule(line_number, format!("unknown option '{}' for '{}'", key, kind_name)));
+            }
+
+            rules.push(CheckRule { line_number, definition: line.to_owned(), kind });
+        }
+
+        Ok(CheckRules { rules })
+    }
+
+    pub fn load(data_directory: &Path) -> CheckingResult<CheckRules> {
+        let definition = std::fs::read_to_string(data_directory.join(CHECKS_FILE))
+            .map_err(|err| CheckingError::Read(CHECKS_FILE.to_owned(), err))?;
+        CheckRules::new(&definition)
+    }
+}
+
+struct RuleOptions<'a> {
+    line_number: usize,
+    options: HashMap<&'a str, &'a str>
+}
+
+impl RuleOptions<'_> {
+    fn optional<T: FromStr>(&mut self, key: &str) -> CheckingResult<Option<T>> {
+        match self.options.remove(key) {
+            Some(value) => {
+                value.parse::<T>()
+                    .map(Some)
+                    .map_err(|_| CheckingError::Rule(self.line_number, format!("invalid value '{}' for '{}'", value, key)))
+            }
+            None => Ok(None)
+        }
+    }
+
+    fn required<T: FromStr>(&mut self, key: &str) -> CheckingResult<T> {
+        self.optional(key)?.ok_or_else(|| CheckingError::Rule(self.line_number, format!("missing option '{}'", key)))
+    }
+
+    fn days(&mut self) -> CheckingResult<Option<u64>> {
+        match self.optional::<u64>("days")? {
+            Some(days) if days > MAX_DAYS => {
+                Err(CheckingError::Rule(self.line_number, format!("invalid value '{}' for 'days' (at most {} days)", days, MAX_DAYS)))
+            }
+            days => Ok(days)
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct CheckResult {
+    pub rule: CheckRule,
+    pub violations: Vec<CheckViolation>
+}
+
+impl CheckResult {
+    // The violations not accepted by the baseline
+    pub fn new_violations(&self) -> impl Iterator<Item=&CheckViolation> {
+        self.violations.iter().filter(|violation| !violation.accepted)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CheckViolation {
+    // A file or a module (pair)
+    pub name: String,
+    pub is_file: bool,
+    pub message: String,
+    // If the violation is in the baseline
+    pub accepted: bool
+}
+
+// The violations accepted when the baseline was recorded, one per line on the form '<check> <name>' (such as
+// 'hotspot src/model.rs'), where only violations not in the baseline fail the checks
+#[derive(Debug, Default)]
+pub struct CheckBaseline {
+    violations: HashSet<(String, String)>
+}
+
+impl CheckBaseline {
+    pub fn new(definition: &str) -> CheckingResult<CheckBaseline> {
+        let mut violations = HashSet::new();
+
+        for (line_index, line) in definition.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (kind_name, name) = line.split_once(' ')
+                .ok_or_else(|| CheckingError::Baseline(line_index + 1, format!("expected '<check> <name>' but got '{}'", line)))?;
+            violations.insert((kind_name.to_owned(), name.trim().to_owned()));
+        }
+
+        Ok(CheckBaseline { violations })
+    }
+
+    // The baseline is empty if there is no baseline file
+    pub fn load(data_directory: &Path) -> CheckingResult<CheckBaseline> {
+        match std::fs::read_to_string(data_directory.join(CHECKS_BASELINE_FILE)) {
+            Ok(definition) => CheckBaseline::new(&definition),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(CheckBaseline::default()),
+            Err(err) => Err(CheckingError::Read(CHECKS_BASELINE_FILE.to_owned(), err))
+        }
+    }
+
+    // The definition of a baseline accepting all violations of the results
+    pub fn create_definition(results: &[CheckResult]) -> String {
+        let mut lines = results
+            .iter()
+            .flat_map(|result| {
+                result.violations.iter().map(|violation| format!("{} {}", result.rule.kind.name(), violation.name))
+            })
+            .collect::<Vec<_>>();
+        lines.sort();
+        lines.dedup();
+
+        lines.iter().map(|line| format!("{}\n", line)).collect()
+    }
+
+    pub fn accept(&self, results: &mut [CheckResult]) {
+        for result in results {
+            for violation in &mut result.violations {
+                violation.accepted = self.violations.contains(&(result.rule.kind.name().to_owned(), violation.name.clone()));
+            }
+        }
+    }
+}
+
+// Rules with 'days' only consider the commits within that many days of the last commit
+pub async fn run_checks(
+    repository_querying: &RepositoryQuerying,
+    querying_config: &RepositoryQueryingConfig,
+    rules: &CheckRules
+) -> CheckingResult<Vec<CheckResult>> {
+    let last_date = repository_querying.summary().await?.last_commit.map(|commit| commit.date).unwrap_or(0);
+
+    // Rules with the same number of days share the querying of that window
+    let mut windowed_queryings = HashMap::new();
+    for days in rules.rules.iter().filter_map(|rule| rule.kind.days()) {
+        if let Entry::Vacant(entry) = windowed_queryings.entry(days) {
+            let min_date = i64::try_from(days)
+                .ok()
+                .and_then(|days| days.checked_mul(24 * 60 * 60))
+                .and_then(|seconds| last_date.checked_sub(seconds))
+                .ok_or(CheckingError::DaysOutOfRange(days))?;
+            let min_date = Some(min_date).max(querying_config.min_date);
+            entry.insert(repository_querying.with_scope(None, min_date, None).await?);
+        }
+    }
+
+    let windowed_querying = |days: &Option<u64>| days.map_or(repository_querying, |days| &windowed_queryings[&days]);
+
+    let mut results = Vec::new();
+    for rule in &rules.rules {
+        let mut violations = Vec::new();
+
+        match &rule.kind {
+            CheckKind::Hotspot { max_revisions, max_code_lines, days } => {
+                let hotspots = windowed_querying(days).file_hotspots(None).await?;
+
+                for hotspot in hotspots {
+                    if hotspot.num_revisions > *max_revisions && hotspot.num_code_lines > *max_code_lines {
+                        violations.push(
+                            CheckViolation {
+                                message: format!(
+                                    "{} has been changed {} times{} (max {}) and has {} lines of code (max {})",
+                                    hotspot.name,
+                                    hotspot.num_revisions,
+                                    days.map(|days| format!(" in the last {} days", days)).unwrap_or_default(),
+                                    max_revisions,
+                                    hotspot.num_code_lines,
+                                    max_code_lines
+                                ),
+                                name: hotspot.name,
+                                is_file: true,
+                                accepted: false
+                            }
+                        );
+                    }
+                }
+            }
+            CheckKind::CouplingViolations { days } => {
+                let coupling_violations = windowed_querying(days).coupling_violations().await?;
+
+                for coupling_violation in coupling_violations {
+                    violations.push(
+                        CheckViolation {
+                            name: format!("{} <-> {}", coupling_violation.left_name, coupling_violation.right_name),
+                            is_file: false,
+                            accepted: false,
+                            message: format!(
+                                "{} and {} have been changed together in {} revisions{} (forbidden by '{}')",
+                                coupling_violation.left_name,
+                                coupling_violation.right_name,
+                                coupling_violation.coupled_revisions,
+                                days.map(|days| format!(" in the last {} days", days)).unwrap_or_default(),
+                                coupling_violation.rule
+                            )
+                        }
+                    );
+                }
+            }
+            CheckKind::TruckFactor { min, modules, depth } => {
+                for entry in repository_querying.modules_truck_factor(*depth).await? {
+                    if modules.matches(&entry.name) && entry.truck_factor < *min {
+                        violations.push(
+                            CheckViolation {
+                                message: format!(
+                                    "{} has a truck factor of {} (min {}) with {} authors",
+                                    entry.name,
+                                    entry.truck_factor,
+                                    min,
+                                    entry.num_authors
+                                ),
+                                name: entry.name,
+                                is_file: false,
+                                accepted: false
+                            }
+                        );
+                    }
+                }
+            }
+        }
+
+        results.push(CheckResult { rule: rule.clone(), violations });
+    }
+
+    Ok(results)
+}
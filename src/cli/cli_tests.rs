@@ -0,0 +1,140 @@
+use crate::checking::rules::{CHECKS_BASELINE_FILE, CHECKS_FILE};
+use crate::cli::commands::{check, export, open_repository_querying, query, CheckOutputFormat, ExportOptions, QueryOutputFormat, EXPORT_ANALYSES};
+use crate::cli::CliError;
+use crate::querying::engine::{CustomAnalysisLimits, RepositoryQuerying, RepositoryQueryingConfig};
+use crate::querying::querying_helpers::QueryParameters;
+use crate::querying::querying_tests::{create_querying, create_test_data_directory};
+use crate::querying::QueryingError;
+use crate::web::app::WebAppConfig;
+
+#[tokio::test]
+async fn test_query() {
+    let repository_querying = create_querying().await;
+    let sql = "SELECT revision, date FROM git_log ORDER BY date LIMIT 2";
+
+    let output = run_query(&repository_querying, sql, &CustomAnalysisLimits::default(), QueryOutputFormat::Table).await.unwrap();
+    assert_eq!(
+        "+----------+------------+\n\
+         | revision | date       |\n\
+         +----------+------------+\n\
+         | 6d5225d  | 1603992639 |\n\
+         | 4fb85e4  | 1604060887 |\n\
+         +----------+------------+\n",
+        output
+    );
+
+    let output = run_query(&repository_querying, sql, &CustomAnalysisLimits::default(), QueryOutputFormat::Csv).await.unwrap();
+    assert_eq!("revision,date\n6d5225d,1603992639\n4fb85e4,1604060887\n", output);
+
+    let output = run_query(&repository_querying, sql, &CustomAnalysisLimits::default(), QueryOutputFormat::Json).await.unwrap();
+    assert_eq!("[{\"revision\":\"6d5225d\",\"date\":1603992639},{\"revision\":\"4fb85e4\",\"date\":1604060887}]\n", output);
+
+    let output = run_query(&repository_querying, "SELECT 1 AS x WHERE false", &CustomAnalysisLimits::default(), QueryOutputFormat::Json).await.unwrap();
+    assert_eq!("[]\n", output);
+}
+
+#[tokio::test]
+async fn test_query_limits() {
+    let repository_querying = create_querying().await;
+    let limits = CustomAnalysisLimits { max_rows: Some(10), ..Default::default() };
+
+    let result = run_query(&repository_querying, "SELECT * FROM git_log", &limits, QueryOutputFormat::Table).await;
+    assert!(matches!(result, Err(CliError::Querying(QueryingError::QueryMaxRowsExceeded(10)))));
+
+    // Streamed formats are truncated at the max rows
+    let output = run_query(&repository_querying, "SELECT revision FROM git_log", &limits, QueryOutputFormat::Csv).await.unwrap();
+    assert_eq!(11, output.lines().count());
+
+    let limits = CustomAnalysisLimits { max_rows: Some(2), ..Default::default() };
+    let output = run_query(&repository_querying, "SELECT revision FROM git_log ORDER BY date LIMIT 2", &limits, QueryOutputFormat::Table).await.unwrap();
+    assert_eq!(6, output.lines().count());
+
+    let result = run_query(&repository_querying, "DROP VIEW git_log", &limits, QueryOutputFormat::Table).await;
+    assert!(matches!(result, Err(CliError::Querying(QueryingError::QueryNotReadOnly(_)))));
+}
+
+#[tokio::test]
+async fn test_export() {
+    let repository_querying = create_querying().await;
+
+    let output = run_export(&repository_querying, "file-hotspots", &ExportOptions { count: Some(3), depth: None }, "csv").await.unwrap();
+    let lines = output.lines().collect::<Vec<_>>();
+    assert_eq!(4, lines.len());
+    assert!(lines[0].starts_with("name,num_revisions,num_authors"));
+    assert!(lines[1].starts_with("src/model.rs,67,1"));
+
+    let output = run_export(&repository_querying, "module-hotspots", &ExportOptions::default(), "json").await.unwrap();
+    let modules = serde_json::from_str::<serde_json::Value>(&output).unwrap();
+    assert_eq!(13, modules.as_array().unwrap().len());
+
+    for analysis in EXPORT_ANALYSES {
+        assert!(run_export(&repository_querying, analysis, &ExportOptions::default(), "jsonl").await.is_ok(), "{}", analysis);
+    }
+
+    let result = run_export(&repository_querying, "hotspots", &ExportOptions::default(), "csv").await;
+    assert!(matches!(result, Err(CliError::UnknownAnalysis(name, _)) if name == "hotspots"));
+
+    let result = run_export(&repository_querying, "file-hotspots", &ExportOptions::default(), "xml").await;
+    assert!(matches!(result, Err(CliError::UnknownFormat(name, _)) if name == "xml"));
+}
+
+#[tokio::test]
+async fn test_open_repository_querying() {
+    let config: WebAppConfig = serde_yaml::from_str("source_dir: test_data/none\ndata_dir: test_data/sqlgrep").unwrap();
+    let repository_querying = open_repository_querying(&config).await.unwrap();
+    assert_eq!(286, repository_querying.summary().await.unwrap().num_revisions);
+
+    let config: WebAppConfig = serde_yaml::from_str("source_dir: test_data/none\ndata_dir: test_data/none").unwrap();
+    let result = open_repository_querying(&config).await;
+    assert!(matches!(result, Err(CliError::NotIndexed(_))));
+}
+
+#[tokio::test]
+async fn test_check_baseline() {
+    let test_directory = create_test_data_directory();
+    let data_directory = test_directory.path();
+    std::fs::write(data_directory.join(CHECKS_FILE), "hotspot max_revisions=60 max_code_lines=300").unwrap();
+
+    let repository_querying = RepositoryQuerying::new(data_directory, RepositoryQueryingConfig::default()).await.unwrap();
+    let run_check = |update_baseline: bool| {
+        let repository_querying = &repository_querying;
+        async move {
+            let mut output = Vec::new();
+            check(repository_querying, &RepositoryQueryingConfig::default(), CheckOutputFormat::Junit, update_baseline, &mut output).await
+        }
+    };
+
+    assert!(matches!(run_check(false).await, Err(CliError::ChecksFailed(2))));
+
+    run_check(true).await.unwrap();
+    assert_eq!(
+        "hotspot src/main.rs\nhotspot src/model.rs\n",
+        std::fs::read_to_string(data_directory.join(CHECKS_BASELINE_FILE)).unwrap()
+    );
+    run_check(false).await.unwrap();
+
+    std::fs::write(data_directory.join(CHECKS_BASELINE_FILE), "hotspot src/model.rs\n").unwrap();
+    assert!(matches!(run_check(false).await, Err(CliError::ChecksFailed(1))));
+}
+
+async fn run_query(
+    repository_querying: &RepositoryQuerying,
+    sql: &str,
+    limits: &CustomAnalysisLimits,
+    format: QueryOutputFormat
+) -> Result<String, CliError> {
+    let mut output = Vec::new();
+    query(repository_querying, sql, &QueryParameters::default(), limits, format, &mut output).await?;
+    Ok(String::from_utf8(output).unwrap())
+}
+
+async fn run_export(
+    repository_querying: &RepositoryQuerying,
+    analysis: &str,
+    options: &ExportOptions,
+    format: &str
+) -> Result<String, CliError> {
+    let mut output = Vec::new();
+    export(repository_querying, analysis, options, format, &mut output).await?;
+    Ok(String::from_utf8(output).unwrap())
+}
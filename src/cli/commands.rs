@@ -0,0 +1,274 @@
+use std::io::Write;
+use std::path::Path;
+
+use clap::ValueEnum;
+use futures::StreamExt;
+use log::info;
+
+use datafusion::arrow::json::ArrayWriter;
+use datafusion::arrow::util::pretty::pretty_format_batches;
+
+use crate::checking::output::{to_junit, to_sarif};
+use crate::checking::rules::{run_checks, CheckBaseline, CheckRules, CHECKS_BASELINE_FILE};
+use crate::cli::{CliError, CliResult};
+use crate::indexing::{indexer, GIT_FILE_ENTRIES_PATH, GIT_LOG_PATH};
+use crate::querying::engine::{CustomAnalysisLimits, RepositoryQuerying, RepositoryQueryingConfig};
+use crate::querying::export::{rows_to_record_batches, ExportFormat, ExportRow, RecordBatchWriter};
+use crate::querying::querying_helpers::QueryParameters;
+use crate::querying::QueryingError;
+use crate::reporting::report::{create_report_data, write_report, ReportConfig, REPORT_FILE};
+use crate::web::app::{PersistentWebAppState, WebAppConfig};
+
+pub const EXPORT_ANALYSES: &[&str] = &[
+    "git-log",
+    "files",
+    "file-hotspots",
+    "file-change-coupling",
+    "file-sum-of-couplings",
+    "file-main-developer",
+    "file-code-age",
+    "modules",
+    "module-hotspots",
+    "module-change-coupling",
+    "module-coupling-violations",
+    "module-sum-of-couplings",
+    "module-main-developer",
+    "module-commit-spread",
+    "module-truck-factor"
+];
+
+const EXPORT_FORMATS: &str = "json, csv, jsonl, parquet";
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum QueryOutputFormat {
+    Table,
+    Csv,
+    Json
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum)]
+pub enum CheckOutputFormat {
+    Junit,
+    Sarif
+}
+
+#[derive(Debug, Default)]
+pub struct ExportOptions {
+    // The number of entries of the analyses that are limited by default (such as hotspots)
+    pub count: Option<usize>,
+    pub depth: Option<usize>
+}
+
+// Indexes the repository if not already indexed, or always if forced
+pub fn index(config: &WebAppConfig, force: bool) -> CliResult<()> {
+    if force {
+        indexer::index_repository(&config.source_dir, &config.data_dir)?;
+    } else {
+        indexer::try_index_repository(&config.source_dir, &config.data_dir)?;
+    }
+
+    Ok(())
+}
+
+// Opens the existing index of the data directory, using the persisted state of the web app (such as the valid date range)
+pub async fn open_repository_querying(config: &WebAppConfig) -> CliResult<RepositoryQuerying> {
+    if !(config.data_dir.join(GIT_LOG_PATH).exists() && config.data_dir.join(GIT_FILE_ENTRIES_PATH).exists()) {
+        return Err(CliError::NotIndexed(config.data_dir.clone()));
+    }
+
+    Ok(RepositoryQuerying::new(&config.data_dir, load_querying_config(config)).await?)
+}
+
+pub fn load_querying_config(config: &WebAppConfig) -> RepositoryQueryingConfig {
+    PersistentWebAppState::load_from_file(&config.data_dir.join("state.json"))
+        .unwrap_or_default()
+        .querying_config()
+        .clone()
+}
+
+// Runs a custom analysis, where the table format keeps the result in memory and therefore fails if there are more than
+// the max rows (while the other formats are truncated)
+pub async fn query<W: Write + Send>(
+    repository_querying: &RepositoryQuerying,
+    sql: &str,
+    parameters: &QueryParameters,
+    limits: &CustomAnalysisLimits,
+    format: QueryOutputFormat,
+    mut output: W
+) -> CliResult<()> {
+    let run_query = async {
+        // The table format fetches one extra row to report that the max rows were exceeded rather than truncating
+        let stream_limits = match format {
+            QueryOutputFormat::Table => CustomAnalysisLimits { max_rows: limits.max_rows.map(|max_rows| max_rows + 1), ..limits.clone() },
+            _ => limits.clone()
+        };
+
+        let mut batches = repository_querying.custom_analysis_stream(sql, parameters, &stream_limits).await?;
+        let schema = batches.schema();
+
+        match format {
+            QueryOutputFormat::Table => {
+                let mut result = Vec::new();
+                let mut num_rows = 0;
+                while let Some(batch) = batches.next().await {
+                    let batch = batch?;
+                    num_rows += batch.num_rows();
+                    if let Some(max_rows) = limits.max_rows.filter(|max_rows| num_rows > *max_rows) {
+                        return Err(QueryingError::QueryMaxRowsExceeded(max_rows).into());
+                    }
+
+                    result.push(batch);
+                }
+
+                writeln!(output, "{}", pretty_format_batches(&result)?)?;
+            }
+            QueryOutputFormat::Csv => {
+                let mut writer = RecordBatchWriter::new(ExportFormat::Csv, schema, &mut output)?;
+                while let Some(batch) = batches.next().await {
+                    writer.write(&batch?)?;
+                }
+                writer.finish()?;
+            }
+            QueryOutputFormat::Json => {
+                let mut writer = ArrayWriter::new(&mut output);
+                while let Some(batch) = batches.next().await {
+                    writer.write(&batch?)?;
+                }
+                writer.finish()?;
+                writeln!(output)?;
+            }
+        }
+
+        Ok::<_, CliError>(())
+    };
+
+    match limits.timeout {
+        Some(timeout) => {
+            tokio::time::timeout(timeout, run_query).await
+                .map_err(|_| QueryingError::QueryTimeout(timeout.as_secs_f64()))??;
+        }
+        None => run_query.await?
+    }
+
+    output.flush()?;
+    Ok(())
+}
+
+// Exports an analysis by the same name as the download of the web app, such as 'file-hotspots'
+pub async fn export<W: Write + Send>(
+    repository_querying: &RepositoryQuerying,
+    analysis: &str,
+    options: &ExportOptions,
+    format: &str,
+    output: W
+) -> CliResult<()> {
+    let format = match format {
+        "json" => None,
+        _ => Some(
+            ExportFormat::from_name(format)
+                .ok_or_else(|| CliError::UnknownFormat(format.to_owned(), EXPORT_FORMATS.to_owned()))?
+        )
+    };
+
+    let count = options.count.or(Some(100));
+    let depth = options.depth;
+    match analysis {
+        "git-log" => write_rows(repository_querying.log().await?, format, output),
+        "files" => write_rows(repository_querying.files().await?, format, output),
+        "file-hotspots" => write_rows(repository_querying.file_hotspots(count).await?, format, output),
+        "file-change-coupling" => write_rows(repository_querying.file_change_couplings(count).await?, format, output),
+        "file-sum-of-couplings" => write_rows(repository_querying.file_sum_of_couplings(count).await?, format, output),
+        "file-main-developer" => write_rows(repository_querying.files_main_developer().await?, format, output),
+        "file-code-age" => write_rows(repository_querying.file_code_age(count).await?, format, output),
+        "modules" => write_rows(repository_querying.modules().await?, format, output),
+        "module-hotspots" => write_rows(repository_querying.module_hotspots(count, depth).await?, format, output),
+        "module-change-coupling" => write_rows(repository_querying.module_change_couplings(count, depth).await?, format, output),
+        "module-coupling-violations" => write_rows(repository_querying.coupling_violations().await?, format, output),
+        "module-sum-of-couplings" => write_rows(repository_querying.module_sum_of_couplings(count).await?, format, output),
+        "module-main-developer" => write_rows(repository_querying.modules_main_developer(depth).await?, format, output),
+        "module-commit-spread" => write_rows(repository_querying.commit_spread(depth).await?, format, output),
+        "module-truck-factor" => write_rows(repository_querying.modules_truck_factor(depth).await?, format, output),
+        _ => Err(CliError::UnknownAnalysis(analysis.to_owned(), EXPORT_ANALYSES.join(", ")))
+    }
+}
+
+pub async fn report(repository_querying: &RepositoryQuerying, config: &WebAppConfig, output_directory: &Path) -> CliResult<()> {
+    let report_data = create_report_data(repository_querying, &ReportConfig::from_web_app_config(config)).await?;
+    write_report(&report_data, output_directory)?;
+    info!("Report written to {}.", output_directory.join(REPORT_FILE).display());
+    Ok(())
+}
+
+// Evaluates the rules of the data directory, where the result is written before failing due to violations not in the
+// baseline. Updating the baseline accepts all current violations.
+pub async fn check<W: Write>(
+    repository_querying: &RepositoryQuerying,
+    querying_config: &RepositoryQueryingConfig,
+    format: CheckOutputFormat,
+    update_baseline: bool,
+    mut output: W
+) -> CliResult<()> {
+    let data_directory = &repository_querying.data_directory;
+    let rules = CheckRules::load(data_directory)?;
+    let mut results = run_checks(repository_querying, querying_config, &rules).await?;
+
+    let baseline = if update_baseline {
+        let definition = CheckBaseline::create_definition(&results);
+        std::fs::write(data_directory.join(CHECKS_BASELINE_FILE), &definition)?;
+        info!("Updated the baseline with {} violation(s).", definition.lines().count());
+        CheckBaseline::new(&definition)?
+    } else {
+        CheckBaseline::load(data_directory)?
+    };
+    baseline.accept(&mut results);
+
+    let result_output = match format {
+        CheckOutputFormat::Junit => to_junit(&results),
+        CheckOutputFormat::Sarif => to_sarif(&results)
+    };
+    output.write_all(result_output.as_bytes())?;
+    output.flush()?;
+
+    for result in &results {
+        for violation in &result.violations {
+            info!(
+                "{}: {}{}",
+                result.rule.id(),
+                violation.message,
+                if violation.accepted { " (accepted by the baseline)" } else { "" }
+            );
+        }
+    }
+
+    let num_violations = results.iter().map(|result| result.new_violations().count()).sum::<usize>();
+    if num_violations > 0 {
+        return Err(CliError::ChecksFailed(num_violations));
+    }
+
+    info!("All {} checks passed.", results.len());
+    Ok(())
+}
+
+fn write_rows<T: ExportRow, W: Write + Send>(
+    rows: Vec<T>,
+    format: Option<ExportFormat>,
+    mut output: W
+) -> CliResult<()> {
+    match format {
+        Some(format) => {
+            let (schema, batches) = rows_to_record_batches(rows, format)?;
+            let mut writer = RecordBatchWriter::new(format, schema, &mut output)?;
+            for batch in batches {
+                writer.write(&batch?)?;
+            }
+            writer.finish()?;
+        }
+        None => {
+            serde_json::to_writer_pretty(&mut output, &rows).map_err(|err| QueryingError::Export(err.to_string()))?;
+            writeln!(output)?;
+        }
+    }
+
+    output.flush()?;
+    Ok(())
+}
@@ -0,0 +1,79 @@
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+use crate::checking::CheckingError;
+use crate::indexing::indexer::IndexError;
+use crate::querying::QueryingError;
+use crate::reporting::ReportingError;
+
+pub mod commands;
+
+#[cfg(test)]
+pub mod cli_tests;
+
+type CliResult<T> = Result<T, CliError>;
+
+#[derive(Debug, Error)]
+pub enum CliError {
+    #[error("I/O: {0}")]
+    IO(std::io::Error),
+    #[error("Indexing: {0}")]
+    Indexing(IndexError),
+    #[error("Querying: {0}")]
+    Querying(QueryingError),
+    #[error("Reporting: {0}")]
+    Reporting(ReportingError),
+    #[error("Checks: {0}")]
+    Checking(CheckingError),
+    #[error("The checks failed with {0} violation(s)")]
+    ChecksFailed(usize),
+    #[error("The data directory '{0}' has not been indexed (run the 'index' command first)")]
+    NotIndexed(PathBuf),
+    #[error("Unknown analysis '{0}' (expected one of: {1})")]
+    UnknownAnalysis(String, String),
+    #[error("Unknown format '{0}' (expected one of: {1})")]
+    UnknownFormat(String, String)
+}
+
+impl From<std::io::Error> for CliError {
+    fn from(err: std::io::Error) -> Self {
+        CliError::IO(err)
+    }
+}
+
+impl From<IndexError> for CliError {
+    fn from(err: IndexError) -> Self {
+        CliError::Indexing(err)
+    }
+}
+
+impl From<QueryingError> for CliError {
+    fn from(err: QueryingError) -> Self {
+        CliError::Querying(err)
+    }
+}
+
+impl From<ReportingError> for CliError {
+    fn from(err: ReportingError) -> Self {
+        CliError::Reporting(err)
+    }
+}
+
+impl From<CheckingError> for CliError {
+    fn from(err: CheckingError) -> Self {
+        CliError::Checking(err)
+    }
+}
+
+impl From<datafusion::error::DataFusionError> for CliError {
+    fn from(err: datafusion::error::DataFusionError) -> Self {
+        CliError::Querying(err.into())
+    }
+}
+
+impl From<datafusion::arrow::error::ArrowError> for CliError {
+    fn from(err: datafusion::arrow::error::ArrowError) -> Self {
+        CliError::Querying(err.into())
+    }
+}
@@ -25,6 +25,7 @@ pub struct GitLogEntry {
     pub revision: String,
     pub date: i64,
     pub author: String,
+    pub author_email: String,
     pub commit_message: String
 }
 
@@ -94,7 +95,7 @@ pub fn index_repository(repository: &Path, output_directory: &Path) -> Result<()
 
     let mut ignore_commits = HashSet::<Oid>::new();
     let mut indexed_files = HashSet::new();
-    while let Some(commit_id) = rev_walk.next() {
+    for commit_id in rev_walk {
         let commit_id = commit_id?;
         let commit = repository.find_commit(commit_id)?;
 
@@ -162,6 +163,7 @@ fn add_log_entry(git_log_writer: &mut SerializedFileWriter<File>, commit: &Commi
             revision: short_commit_hash.clone(),
             date: commit_time.timestamp(),
             author: commit.author().name().unwrap_or("unknown").to_string(),
+            author_email: commit.author().email().unwrap_or("").to_string(),
             commit_message: commit.message().unwrap_or("unknown").to_string(),
         }
     ].as_slice().write_to_row_group(&mut row_group)?;
@@ -181,7 +183,7 @@ fn index_commit(
     let commit_time = commit.time().to_date_time().unwrap();
     let commit_tree = commit.tree();
 
-    let parent_tree = parent.as_ref().map(|c| c.tree().ok()).flatten();
+    let parent_tree = parent.as_ref().and_then(|c| c.tree().ok());
 
     let diff = repository.diff_tree_to_tree(
         parent_tree.as_ref(),
@@ -201,7 +203,7 @@ fn index_commit(
         None,
         Some(
             &mut |diff, _, line| {
-                if let Some(file_path) = diff.new_file().path().map(|x| x.to_str()).flatten() {
+                if let Some(file_path) = diff.new_file().path().and_then(|x| x.to_str()) {
                     let is_added = line.old_lineno().is_none();
 
                     let add_entry = added_lines.entry(file_path.to_owned()).or_insert(0);
@@ -237,13 +239,12 @@ fn index_commit(
             delta.status()
         );
 
-        let file_entry = file_entry.map(|entry| entry.to_object(&repository).ok()).flatten();
+        let file_entry = file_entry.and_then(|entry| entry.to_object(repository).ok());
         let content = file_entry.as_ref()
-            .map(|entry| entry.as_blob()).flatten()
-            .map(|blob| std::str::from_utf8(blob.content()).ok())
-            .flatten();
+            .and_then(|entry| entry.as_blob())
+            .and_then(|blob| std::str::from_utf8(blob.content()).ok());
 
-        let file_extension = file_path.extension().map(|x| x.to_str()).flatten().unwrap_or("unknown");
+        let file_extension = file_path.extension().and_then(|x| x.to_str()).unwrap_or("unknown");
         let source_code_stats = content.map(|content| calculate_source_code_stats(file_extension, content));
 
         debug!("\t\t{:?}", source_code_stats);
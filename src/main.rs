@@ -1,9 +1,17 @@
-use clap::Parser;
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
 
 mod web;
 mod querying;
 mod indexing;
+mod reporting;
+mod cli;
+mod checking;
 
+use cli::commands::{CheckOutputFormat, ExportOptions, QueryOutputFormat};
+use cli::CliError;
+use querying::querying_helpers::QueryParameters;
 use web::app::WebAppConfig;
 
 #[derive(Parser, Debug)]
@@ -11,9 +19,70 @@ use web::app::WebAppConfig;
 struct Args {
     /// The config file
     #[arg()]
-    config: String
+    config: String,
+
+    /// The command to run (serve if not given)
+    #[command(subcommand)]
+    command: Option<Command>
 }
 
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Indexes the repository (if not already indexed)
+    Index {
+        /// Reindexes the repository even if already indexed
+        #[arg(long)]
+        force: bool
+    },
+    /// Indexes the repository (if not already indexed) and starts the web app
+    Serve,
+    /// Runs a read-only SQL query against the index and prints the result
+    Query {
+        /// The SQL query
+        sql: String,
+        /// The output format
+        #[arg(long, value_enum, default_value_t=QueryOutputFormat::Table)]
+        format: QueryOutputFormat,
+        /// The query parameters as JSON, either an array (positional) or an object (named)
+        #[arg(long)]
+        parameters: Option<String>
+    },
+    /// Exports an analysis (such as 'file-hotspots')
+    Export {
+        /// The analysis
+        analysis: String,
+        /// The output format (json, csv, jsonl or parquet)
+        #[arg(long, default_value="csv")]
+        format: String,
+        /// The number of entries (for analyses limited by default)
+        #[arg(long)]
+        count: Option<usize>,
+        /// The module depth (for module analyses)
+        #[arg(long)]
+        depth: Option<usize>,
+        /// The output file (stdout if not given)
+        #[arg(long, short)]
+        output: Option<PathBuf>
+    },
+    /// Writes a static HTML report of the standard analyses
+    Report {
+        /// The output directory
+        output_directory: PathBuf
+    },
+    /// Evaluates the rules of 'checks.txt' in the data directory and fails if any rule is violated (except for the
+    /// violations in 'checks_baseline.txt')
+    Check {
+        /// The output format
+        #[arg(long, value_enum, default_value_t=CheckOutputFormat::Junit)]
+        format: CheckOutputFormat,
+        /// The output file (stdout if not given)
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+        /// Records the current violations as the baseline
+        #[arg(long)]
+        update_baseline: bool
+    }
+}
 
 #[tokio::main]
 async fn main() {
@@ -21,13 +90,88 @@ async fn main() {
     let config: WebAppConfig = serde_yaml::from_str(
         &std::fs::read_to_string(args.config).unwrap()
     ).unwrap();
-    setup_logger().unwrap();
 
-    web::app::main(config).await;
+    let command = args.command.unwrap_or(Command::Serve);
+
+    // The result of queries and exports is written to stdout, so the log is written to stderr
+    let log_to_stderr = matches!(
+        command,
+        Command::Query { .. } | Command::Export { .. } | Command::Report { .. } | Command::Check { .. }
+    );
+    setup_logger(log_to_stderr).unwrap();
+
+    if let Err(err) = run(command, config).await {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
 }
 
-fn setup_logger() -> Result<(), fern::InitError> {
-    fern::Dispatch::new()
+async fn run(command: Command, config: WebAppConfig) -> Result<(), CliError> {
+    match command {
+        Command::Index { force } => {
+            cli::commands::index(&config, force)?;
+        }
+        Command::Serve => {
+            web::app::main(config).await;
+        }
+        Command::Query { sql, format, parameters } => {
+            let parameters = match parameters {
+                Some(parameters) => {
+                    let parameters = serde_json::from_str(&parameters)
+                        .map_err(|err| querying::QueryingError::InvalidQueryParameter("parameters".to_owned(), err.to_string()))?;
+                    QueryParameters::from_json(&parameters)?
+                }
+                None => QueryParameters::default()
+            };
+
+            let repository_querying = cli::commands::open_repository_querying(&config).await?;
+            cli::commands::query(
+                &repository_querying,
+                &sql,
+                &parameters,
+                &config.custom_analysis_limits(),
+                format,
+                std::io::BufWriter::new(std::io::stdout())
+            ).await?;
+        }
+        Command::Export { analysis, format, count, depth, output } => {
+            let repository_querying = cli::commands::open_repository_querying(&config).await?;
+            let options = ExportOptions { count, depth };
+            match output {
+                Some(output) => {
+                    let output = std::io::BufWriter::new(std::fs::File::create(output)?);
+                    cli::commands::export(&repository_querying, &analysis, &options, &format, output).await?;
+                }
+                None => {
+                    let output = std::io::BufWriter::new(std::io::stdout());
+                    cli::commands::export(&repository_querying, &analysis, &options, &format, output).await?;
+                }
+            }
+        }
+        Command::Report { output_directory } => {
+            let repository_querying = cli::commands::open_repository_querying(&config).await?;
+            cli::commands::report(&repository_querying, &config, &output_directory).await?;
+        }
+        Command::Check { format, output, update_baseline } => {
+            let repository_querying = cli::commands::open_repository_querying(&config).await?;
+            let querying_config = cli::commands::load_querying_config(&config);
+            match output {
+                Some(output) => {
+                    let output = std::io::BufWriter::new(std::fs::File::create(output)?);
+                    cli::commands::check(&repository_querying, &querying_config, format, update_baseline, output).await?;
+                }
+                None => {
+                    cli::commands::check(&repository_querying, &querying_config, format, update_baseline, std::io::stdout()).await?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn setup_logger(log_to_stderr: bool) -> Result<(), fern::InitError> {
+    let dispatch = fern::Dispatch::new()
         .format(|out, message, record| {
             out.finish(format_args!(
                 "[{} {} {}] {}",
@@ -37,8 +181,13 @@ fn setup_logger() -> Result<(), fern::InitError> {
                 message
             ))
         })
-        .level(log::LevelFilter::Info)
-        .chain(std::io::stdout())
-        .apply()?;
+        .level(log::LevelFilter::Info);
+
+    if log_to_stderr {
+        dispatch.chain(std::io::stderr()).apply()?;
+    } else {
+        dispatch.chain(std::io::stdout()).apply()?;
+    }
+
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,142 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::querying::model::{AuthorAlias, AuthorAliasSuggestion};
+
+pub struct AuthorIdentity {
+    pub name: String,
+    pub email: String,
+    pub num_revisions: u64
+}
+
+// Groups the authors that share an e-mail or have names only differing in case, diacritics or ordering, where the
+// author with the most revisions is suggested as the name of the group
+pub fn suggest_author_aliases(identities: &[AuthorIdentity]) -> Vec<AuthorAliasSuggestion> {
+    let mut authors = BTreeMap::<&str, (BTreeSet<&str>, u64)>::new();
+    for identity in identities {
+        let author = authors.entry(identity.name.as_str()).or_default();
+        if !identity.email.is_empty() {
+            author.0.insert(identity.email.as_str());
+        }
+        author.1 += identity.num_revisions;
+    }
+
+    let names = authors.keys().cloned().collect::<Vec<_>>();
+    let mut groups = (0..names.len()).collect::<Vec<_>>();
+
+    let mut keys = HashMap::new();
+    for (name_index, name) in names.iter().enumerate() {
+        let fuzzy_name = fuzzy_author_name(name);
+        let emails = authors[name].0.iter().map(|email| email.to_lowercase());
+
+        for key in emails.chain(Some(fuzzy_name).filter(|name| !name.is_empty())) {
+            match keys.get(&key) {
+                Some(other_index) => union_groups(&mut groups, name_index, *other_index),
+                None => {
+                    keys.insert(key, name_index);
+                }
+            }
+        }
+    }
+
+    let mut grouped_names = BTreeMap::new();
+    for (name_index, name) in names.iter().enumerate() {
+        grouped_names.entry(find_group(&mut groups, name_index)).or_insert_with(Vec::new).push(*name);
+    }
+
+    let mut suggestions = grouped_names
+        .into_values()
+        .filter(|names| names.len() >= 2)
+        .map(|mut names| {
+            names.sort_by_key(|name| (std::cmp::Reverse(authors[name].1), *name));
+
+            AuthorAliasSuggestion {
+                name: names[0].to_owned(),
+                num_revisions: names.iter().map(|name| authors[name].1).sum(),
+                aliases: names[1..]
+                    .iter()
+                    .map(|name| {
+                        AuthorAlias {
+                            name: name.to_string(),
+                            emails: authors[name].0.iter().map(|email| email.to_string()).collect(),
+                            num_revisions: authors[name].1
+                        }
+                    })
+                    .collect()
+            }
+        })
+        .collect::<Vec<_>>();
+
+    suggestions.sort_by_key(|suggestion| (std::cmp::Reverse(suggestion.num_revisions), suggestion.name.clone()));
+    suggestions
+}
+
+// Lowercases the name, removes diacritics and punctuation and sorts the parts of the name
+pub fn fuzzy_author_name(name: &str) -> String {
+    let mut folded_name = String::new();
+    for c in name.to_lowercase().chars() {
+        match fold_diacritic(c) {
+            Some(folded) => folded_name += folded,
+            None if c.is_alphanumeric() => folded_name.push(c),
+            None => folded_name.push(' ')
+        }
+    }
+
+    let mut parts = folded_name.split_whitespace().collect::<Vec<_>>();
+    parts.sort();
+    parts.join(" ")
+}
+
+fn fold_diacritic(c: char) -> Option<&'static str> {
+    let folded = match c {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' => "a",
+        'æ' => "ae",
+        'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => "c",
+        'ď' | 'đ' | 'ð' => "d",
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => "e",
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => "g",
+        'ĥ' | 'ħ' => "h",
+        'ì' | 'í' | 'î' | 'ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => "i",
+        'ĵ' => "j",
+        'ķ' => "k",
+        'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => "l",
+        'ñ' | 'ń' | 'ņ' | 'ň' => "n",
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => "o",
+        'œ' => "oe",
+        'ŕ' | 'ŗ' | 'ř' => "r",
+        'ś' | 'ŝ' | 'ş' | 'š' => "s",
+        'ß' => "ss",
+        'ţ' | 'ť' | 'ŧ' => "t",
+        'þ' => "th",
+        'ù' | 'ú' | 'û' | 'ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => "u",
+        'ŵ' => "w",
+        'ý' | 'ÿ' | 'ŷ' => "y",
+        'ź' | 'ż' | 'ž' => "z",
+        _ => return None
+    };
+
+    Some(folded)
+}
+
+fn find_group(groups: &mut [usize], index: usize) -> usize {
+    let mut root = index;
+    while groups[root] != root {
+        root = groups[root];
+    }
+
+    let mut current = index;
+    while groups[current] != root {
+        let next = groups[current];
+        groups[current] = root;
+        current = next;
+    }
+
+    root
+}
+
+fn union_groups(groups: &mut [usize], left: usize, right: usize) {
+    let left = find_group(groups, left);
+    let right = find_group(groups, right);
+    if left != right {
+        groups[left.max(right)] = left.min(right);
+    }
+}
@@ -1,18 +1,31 @@
+use std::any::Any;
+use std::collections::BTreeMap;
 use std::path::Path;
-use std::sync::Arc;
+use std::sync::{Arc, LazyLock};
 
-use datafusion::arrow::array::{as_primitive_array, ArrayRef, BooleanArray, Float64Array, StringViewArray};
-use datafusion::arrow::datatypes::{DataType, Int64Type};
+use chrono::{DateTime, Datelike};
+use chrono_tz::Tz;
+use regex::Regex;
+
+use datafusion::arrow::array::{as_primitive_array, Array, ArrayRef, BooleanArray, Float64Array, Int64Array, ListBuilder, StringBuilder, StringViewArray};
+use datafusion::arrow::datatypes::{DataType, Field, Int64Type};
 use datafusion::common::cast::as_string_array;
-use datafusion::logical_expr::{create_udf, ColumnarValue, Volatility};
+use datafusion::common::exec_err;
+use datafusion::logical_expr::{create_udf, ColumnarValue, ScalarFunctionArgs, ScalarUDF, ScalarUDFImpl, Signature, TypeSignature, Volatility};
 use datafusion::prelude::*;
 
-use crate::querying::data_transformers::{AuthorNormalizer, IgnoreFile, ModuleDefinitions};
-use crate::querying::{QueryingResult};
+use crate::querying::data_transformers::{default_module_name, truncate_module_name_at_depth, AuthorExclusions, AuthorNormalizer, IgnoreFile, ModuleDefinitions, RuleFileError};
+use crate::querying::engine::{self, RepositoryQueryingConfig, DEFAULT_MODULE_VIEW};
+use crate::querying::{QueryingError, QueryingResult};
+
+// The module definitions of each view, where an invalid definition of another view than the default only gives an error
+// when the view is used
+pub type ModuleViews = BTreeMap<String, Result<ModuleDefinitions, Arc<RuleFileError>>>;
 
-pub fn add(data_directory: &Path, ctx: &SessionContext) -> QueryingResult<()> {
+// Adds the functions except 'extract_module_name', which depends on the module view (see 'add_extract_module_name')
+pub fn add(data_directory: &Path, config: &RepositoryQueryingConfig, ctx: &SessionContext) -> QueryingResult<Arc<ModuleViews>> {
     let ignore_file = match std::fs::read_to_string(data_directory.join("ignore.txt")) {
-        Ok(definition) => IgnoreFile::new(&definition),
+        Ok(definition) => IgnoreFile::new(&definition)?,
         _ => IgnoreFile::empty()
     };
 
@@ -39,37 +52,34 @@ pub fn add(data_directory: &Path, ctx: &SessionContext) -> QueryingResult<()> {
     );
     ctx.register_udf(is_ignored.clone());
 
-    let module_definitions = match std::fs::read_to_string(data_directory.join("modules.txt")) {
-        Ok(definition) => ModuleDefinitions::new(&definition)?,
-        _ => ModuleDefinitions::empty()
-    };
+    let mut module_views = ModuleViews::new();
+    for view in engine::module_views(data_directory) {
+        let file = engine::module_definition_file_name(&view)?;
+        let module_definitions = match std::fs::read_to_string(data_directory.join(&file)) {
+            Ok(definition) if view == DEFAULT_MODULE_VIEW => Ok(ModuleDefinitions::new(&file, &definition)?),
+            Ok(definition) => ModuleDefinitions::new(&file, &definition).map_err(Arc::new),
+            _ => Ok(ModuleDefinitions::empty())
+        };
 
-    let extract_module_name = create_udf(
-        "extract_module_name",
-        vec![DataType::Utf8],
+        module_views.insert(view, module_definitions);
+    }
+
+    let truncate_module_name = create_udf(
+        "truncate_module_name",
+        vec![DataType::Utf8, DataType::Int64],
         DataType::Utf8View,
         Volatility::Immutable,
         Arc::new(move |args: &[ColumnarValue]| {
             let args = ColumnarValue::values_to_arrays(args)?;
-            let file_name = as_string_array(&args[0]).expect("cast failed");
-
-            let array = file_name
-                .iter()
-                .map(|file_name| {
-                    file_name.map(|file_name| {
-                        if let Some(module_name) = module_definitions.get_module(file_name) {
-                            return module_name.to_owned();
-                        }
+            let module_name = as_string_array(&args[0]).expect("cast failed");
+            let depth = as_primitive_array::<Int64Type>(&args[1]);
 
-                        if let Some(parent) = Path::new(file_name).parent() {
-                            let parent = parent.to_str().unwrap().to_owned();
-                            if !parent.is_empty() {
-                                parent
-                            } else {
-                                "<root>".to_owned()
-                            }
-                        } else {
-                            file_name.to_owned()
+            let array = module_name.iter().zip(depth.iter())
+                .map(|(module_name, depth)| {
+                    module_name.map(|module_name| {
+                        match depth {
+                            Some(depth) => truncate_module_name_at_depth(module_name, depth.max(1) as usize),
+                            None => module_name.to_owned()
                         }
                     })
                 })
@@ -78,35 +88,39 @@ pub fn add(data_directory: &Path, ctx: &SessionContext) -> QueryingResult<()> {
             Ok(ColumnarValue::from(Arc::new(array) as ArrayRef))
         })
     );
-    ctx.register_udf(extract_module_name.clone());
+    ctx.register_udf(truncate_module_name.clone());
 
     let author_normalizer = match std::fs::read_to_string(data_directory.join("authors.txt")) {
         Ok(definition) => AuthorNormalizer::new(&definition)?,
         _ => AuthorNormalizer::empty()
     };
 
-    let normalize_author = create_udf(
-        "normalize_author",
-        vec![DataType::Utf8],
-        DataType::Utf8View,
+    ctx.register_udf(ScalarUDF::new_from_impl(NormalizeAuthor::new(author_normalizer)));
+
+    let author_exclusions = AuthorExclusions::new(&config.excluded_authors)?;
+
+    let is_excluded_author = create_udf(
+        "is_excluded_author",
+        vec![DataType::Utf8, DataType::Utf8],
+        DataType::Boolean,
         Volatility::Immutable,
         Arc::new(move |args: &[ColumnarValue]| {
             let args = ColumnarValue::values_to_arrays(args)?;
             let author = as_string_array(&args[0]).expect("cast failed");
+            let author_email = as_string_array(&args[1]).expect("cast failed");
 
-            let array = author
-                .iter()
-                .map(|file_name| {
-                    file_name.map(|author| {
-                        author_normalizer.normalize(author).unwrap_or(author).to_owned()
+            let array = author.iter().zip(author_email.iter())
+                .map(|(author, author_email)| {
+                    author.map(|author| {
+                        author_exclusions.is_excluded(author, author_email)
                     })
                 })
-                .collect::<StringViewArray>();
+                .collect::<BooleanArray>();
 
             Ok(ColumnarValue::from(Arc::new(array) as ArrayRef))
         })
     );
-    ctx.register_udf(normalize_author.clone());
+    ctx.register_udf(is_excluded_author.clone());
 
     let ratio = create_udf(
         "ratio",
@@ -138,5 +152,552 @@ pub fn add(data_directory: &Path, ctx: &SessionContext) -> QueryingResult<()> {
     );
     ctx.register_udf(ratio.clone());
 
+    add_path_functions(ctx);
+    add_commit_message_functions(ctx);
+
+    let timezone = match &config.timezone {
+        Some(timezone) => parse_timezone(timezone).ok_or_else(|| QueryingError::UnknownTimezone(timezone.clone()))?,
+        None => Tz::UTC
+    };
+
+    ctx.register_udf(ScalarUDF::new_from_impl(TimeBucket::new(timezone)));
+
+    Ok(Arc::new(module_views))
+}
+
+// The module view is used by 'extract_module_name' when no view is given as the second argument
+pub fn add_extract_module_name(ctx: &SessionContext, module_views: &Arc<ModuleViews>, module_view: Option<&str>) -> QueryingResult<()> {
+    let module_view = module_view.unwrap_or(DEFAULT_MODULE_VIEW);
+    match module_views.get(module_view) {
+        Some(Ok(_)) => {}
+        Some(Err(err)) => return Err(QueryingError::ModuleView(module_view.to_owned(), err.clone())),
+        None => return Err(QueryingError::UnknownModuleView(module_view.to_owned()))
+    }
+
+    ctx.register_udf(ScalarUDF::new_from_impl(ExtractModuleName::new(module_views.clone(), module_view.to_owned())));
     Ok(())
-}
\ No newline at end of file
+}
+
+fn add_path_functions(ctx: &SessionContext) {
+    ctx.register_udf(create_string_udf("file_extension", |file_name| Some(file_extension(file_name).to_owned())));
+    ctx.register_udf(create_string_udf("base_name", |file_name| Some(base_name(file_name).to_owned())));
+    ctx.register_udf(create_string_udf("file_language", |file_name| file_language(file_name).map(|language| language.to_owned())));
+
+    let path_depth = create_udf(
+        "path_depth",
+        vec![DataType::Utf8],
+        DataType::Int64,
+        Volatility::Immutable,
+        Arc::new(move |args: &[ColumnarValue]| {
+            let args = ColumnarValue::values_to_arrays(args)?;
+            let file_name = as_string_array(&args[0]).expect("cast failed");
+
+            let array = file_name
+                .iter()
+                .map(|file_name| file_name.map(path_depth))
+                .collect::<Int64Array>();
+
+            Ok(ColumnarValue::from(Arc::new(array) as ArrayRef))
+        })
+    );
+    ctx.register_udf(path_depth.clone());
+
+    let directory_at_depth = create_udf(
+        "directory_at_depth",
+        vec![DataType::Utf8, DataType::Int64],
+        DataType::Utf8View,
+        Volatility::Immutable,
+        Arc::new(move |args: &[ColumnarValue]| {
+            let args = ColumnarValue::values_to_arrays(args)?;
+            let file_name = as_string_array(&args[0]).expect("cast failed");
+            let depth = as_primitive_array::<Int64Type>(&args[1]);
+
+            let array = file_name.iter().zip(depth.iter())
+                .map(|(file_name, depth)| {
+                    match (file_name, depth) {
+                        (Some(file_name), Some(depth)) => Some(directory_at_depth(file_name, depth.max(1) as usize)),
+                        _ => None
+                    }
+                })
+                .collect::<StringViewArray>();
+
+            Ok(ColumnarValue::from(Arc::new(array) as ArrayRef))
+        })
+    );
+    ctx.register_udf(directory_at_depth.clone());
+
+    let glob_match = create_udf(
+        "glob_match",
+        vec![DataType::Utf8, DataType::Utf8],
+        DataType::Boolean,
+        Volatility::Immutable,
+        Arc::new(move |args: &[ColumnarValue]| {
+            let args = ColumnarValue::values_to_arrays(args)?;
+            let file_name = as_string_array(&args[0]).expect("cast failed");
+            let pattern = as_string_array(&args[1]).expect("cast failed");
+
+            let match_options = glob::MatchOptions {
+                require_literal_separator: true,
+                ..Default::default()
+            };
+
+            // The pattern is usually the same for all rows
+            let mut compiled_pattern: Option<(&str, glob::Pattern)> = None;
+
+            let mut array = Vec::with_capacity(file_name.len());
+            for (file_name, pattern) in file_name.iter().zip(pattern.iter()) {
+                let (Some(file_name), Some(pattern)) = (file_name, pattern) else {
+                    array.push(None);
+                    continue;
+                };
+
+                if compiled_pattern.as_ref().map(|(current, _)| *current != pattern).unwrap_or(true) {
+                    match glob::Pattern::new(pattern) {
+                        Ok(glob_pattern) => compiled_pattern = Some((pattern, glob_pattern)),
+                        Err(err) => return exec_err!("Invalid glob pattern '{}': {}", pattern, err)
+                    }
+                }
+
+                let (_, glob_pattern) = compiled_pattern.as_ref().unwrap();
+                array.push(Some(glob_pattern.matches_with(file_name, match_options)));
+            }
+
+            Ok(ColumnarValue::from(Arc::new(array.into_iter().collect::<BooleanArray>()) as ArrayRef))
+        })
+    );
+    ctx.register_udf(glob_match.clone());
+}
+
+fn add_commit_message_functions(ctx: &SessionContext) {
+    ctx.register_udf(create_string_udf("commit_first_line", |message| Some(commit_first_line(message).to_owned())));
+    ctx.register_udf(create_string_udf("conventional_commit_type", conventional_commit_type));
+
+    let issue_keys = create_udf(
+        "issue_keys",
+        vec![DataType::Utf8],
+        DataType::List(Arc::new(Field::new_list_field(DataType::Utf8, true))),
+        Volatility::Immutable,
+        Arc::new(move |args: &[ColumnarValue]| {
+            let args = ColumnarValue::values_to_arrays(args)?;
+            let message = as_string_array(&args[0]).expect("cast failed");
+
+            let mut builder = ListBuilder::new(StringBuilder::new());
+            for message in message.iter() {
+                match message {
+                    Some(message) => {
+                        for issue_key in issue_keys(message) {
+                            builder.values().append_value(issue_key);
+                        }
+
+                        builder.append(true);
+                    }
+                    None => builder.append(false)
+                }
+            }
+
+            Ok(ColumnarValue::from(Arc::new(builder.finish()) as ArrayRef))
+        })
+    );
+    ctx.register_udf(issue_keys.clone());
+}
+
+// Creates a function mapping a string to another string
+fn create_string_udf(name: &str, function: impl Fn(&str) -> Option<String> + Send + Sync + 'static) -> ScalarUDF {
+    create_udf(
+        name,
+        vec![DataType::Utf8],
+        DataType::Utf8View,
+        Volatility::Immutable,
+        Arc::new(move |args: &[ColumnarValue]| {
+            let args = ColumnarValue::values_to_arrays(args)?;
+            let value = as_string_array(&args[0]).expect("cast failed");
+
+            let array = value
+                .iter()
+                .map(|value| value.and_then(&function))
+                .collect::<StringViewArray>();
+
+            Ok(ColumnarValue::from(Arc::new(array) as ArrayRef))
+        })
+    )
+}
+
+// Extracts the module name of a file, either using the active view or the view given as the second argument
+struct ExtractModuleName {
+    signature: Signature,
+    module_views: Arc<ModuleViews>,
+    active_view: String
+}
+
+impl ExtractModuleName {
+    fn new(module_views: Arc<ModuleViews>, active_view: String) -> ExtractModuleName {
+        ExtractModuleName {
+            signature: Signature::one_of(
+                vec![
+                    TypeSignature::Exact(vec![DataType::Utf8]),
+                    TypeSignature::Exact(vec![DataType::Utf8, DataType::Utf8])
+                ],
+                Volatility::Immutable
+            ),
+            module_views,
+            active_view
+        }
+    }
+}
+
+impl std::fmt::Debug for ExtractModuleName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExtractModuleName")
+            .field("views", &self.module_views.keys().collect::<Vec<_>>())
+            .field("active_view", &self.active_view)
+            .finish()
+    }
+}
+
+impl ScalarUDFImpl for ExtractModuleName {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "extract_module_name"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> datafusion::common::Result<DataType> {
+        Ok(DataType::Utf8View)
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> datafusion::common::Result<ColumnarValue> {
+        let args = ColumnarValue::values_to_arrays(&args.args)?;
+        let file_name = as_string_array(&args[0]).expect("cast failed");
+        let view = args.get(1).map(|view| as_string_array(view).expect("cast failed"));
+
+        let mut array = Vec::with_capacity(file_name.len());
+        for (row_index, file_name) in file_name.iter().enumerate() {
+            let view = match &view {
+                Some(view) if view.is_valid(row_index) => view.value(row_index),
+                Some(_) => {
+                    array.push(None);
+                    continue;
+                }
+                None => &self.active_view
+            };
+
+            let module_definitions = match self.module_views.get(view) {
+                Some(Ok(module_definitions)) => module_definitions,
+                Some(Err(err)) => return exec_err!("Module view '{}': {}", view, err),
+                None => return exec_err!("Unknown module view '{}'", view)
+            };
+
+            array.push(
+                file_name.map(|file_name| {
+                    if let Some(module_name) = module_definitions.get_module(file_name) {
+                        return module_name;
+                    }
+
+                    default_module_name(file_name)
+                })
+            );
+        }
+
+        Ok(ColumnarValue::from(Arc::new(array.into_iter().collect::<StringViewArray>()) as ArrayRef))
+    }
+}
+
+// Normalizes the author name, optionally using the e-mail given as the second argument
+struct NormalizeAuthor {
+    signature: Signature,
+    author_normalizer: AuthorNormalizer
+}
+
+impl NormalizeAuthor {
+    fn new(author_normalizer: AuthorNormalizer) -> NormalizeAuthor {
+        NormalizeAuthor {
+            signature: Signature::one_of(
+                vec![
+                    TypeSignature::Exact(vec![DataType::Utf8]),
+                    TypeSignature::Exact(vec![DataType::Utf8, DataType::Utf8])
+                ],
+                Volatility::Immutable
+            ),
+            author_normalizer
+        }
+    }
+}
+
+impl std::fmt::Debug for NormalizeAuthor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NormalizeAuthor").finish()
+    }
+}
+
+impl ScalarUDFImpl for NormalizeAuthor {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "normalize_author"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> datafusion::common::Result<DataType> {
+        Ok(DataType::Utf8View)
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> datafusion::common::Result<ColumnarValue> {
+        let args = ColumnarValue::values_to_arrays(&args.args)?;
+        let author = as_string_array(&args[0]).expect("cast failed");
+        let author_email = args.get(1).map(|author_email| as_string_array(author_email).expect("cast failed"));
+
+        let array = author
+            .iter()
+            .enumerate()
+            .map(|(row_index, author)| {
+                author.map(|author| {
+                    let author_email = author_email
+                        .filter(|author_email| author_email.is_valid(row_index))
+                        .map(|author_email| author_email.value(row_index));
+
+                    self.author_normalizer.normalize(author, author_email).unwrap_or(author).to_owned()
+                })
+            })
+            .collect::<StringViewArray>();
+
+        Ok(ColumnarValue::from(Arc::new(array) as ArrayRef))
+    }
+}
+
+// Buckets a UNIX timestamp into hours, days, weeks, months, quarters or years in the configured timezone (or the
+// timezone given as the third argument)
+struct TimeBucket {
+    signature: Signature,
+    timezone: Tz
+}
+
+impl TimeBucket {
+    fn new(timezone: Tz) -> TimeBucket {
+        TimeBucket {
+            signature: Signature::one_of(
+                vec![
+                    TypeSignature::Exact(vec![DataType::Int64, DataType::Utf8]),
+                    TypeSignature::Exact(vec![DataType::Int64, DataType::Utf8, DataType::Utf8])
+                ],
+                Volatility::Immutable
+            ),
+            timezone
+        }
+    }
+}
+
+impl std::fmt::Debug for TimeBucket {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TimeBucket")
+            .field("timezone", &self.timezone)
+            .finish()
+    }
+}
+
+impl ScalarUDFImpl for TimeBucket {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn name(&self) -> &str {
+        "time_bucket"
+    }
+
+    fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    fn return_type(&self, _arg_types: &[DataType]) -> datafusion::common::Result<DataType> {
+        Ok(DataType::Utf8View)
+    }
+
+    fn invoke_with_args(&self, args: ScalarFunctionArgs) -> datafusion::common::Result<ColumnarValue> {
+        let args = ColumnarValue::values_to_arrays(&args.args)?;
+        let timestamp = as_primitive_array::<Int64Type>(&args[0]);
+        let unit = as_string_array(&args[1]).expect("cast failed");
+        let timezone = args.get(2).map(|timezone| as_string_array(timezone).expect("cast failed"));
+
+        let mut array = Vec::with_capacity(timestamp.len());
+        for (row_index, (timestamp, unit)) in timestamp.iter().zip(unit.iter()).enumerate() {
+            let (Some(timestamp), Some(unit)) = (timestamp, unit) else {
+                array.push(None);
+                continue;
+            };
+
+            let timezone = match &timezone {
+                Some(timezone) if timezone.is_valid(row_index) => {
+                    let timezone = timezone.value(row_index);
+                    match parse_timezone(timezone) {
+                        Some(timezone) => timezone,
+                        None => return exec_err!("Unknown timezone '{}'", timezone)
+                    }
+                }
+                Some(_) => {
+                    array.push(None);
+                    continue;
+                }
+                None => self.timezone
+            };
+
+            match time_bucket(timestamp, unit, &timezone) {
+                Some(bucket) => array.push(Some(bucket)),
+                None => return exec_err!("Unknown time bucket '{}' (expected hour, day, week, month, quarter or year)", unit)
+            }
+        }
+
+        Ok(ColumnarValue::from(Arc::new(array.into_iter().collect::<StringViewArray>()) as ArrayRef))
+    }
+}
+
+pub fn base_name(file_name: &str) -> &str {
+    file_name.rsplit('/').next().unwrap_or(file_name)
+}
+
+// The extension without the dot, where files starting with a dot (such as '.gitignore') have no extension
+pub fn file_extension(file_name: &str) -> &str {
+    base_name(file_name)
+        .rsplit_once('.')
+        .filter(|(stem, _)| !stem.is_empty())
+        .map(|(_, extension)| extension)
+        .unwrap_or("")
+}
+
+// The number of directories the file is in
+pub fn path_depth(file_name: &str) -> i64 {
+    file_name.matches('/').count() as i64
+}
+
+// The directory of the file truncated at the given depth, where files in the root are in '<root>'
+pub fn directory_at_depth(file_name: &str, depth: usize) -> String {
+    let directories = file_name.split('/').collect::<Vec<_>>();
+    let directories = &directories[..directories.len() - 1];
+    if directories.is_empty() {
+        return "<root>".to_owned();
+    }
+
+    directories[..depth.min(directories.len())].join("/")
+}
+
+pub fn file_language(file_name: &str) -> Option<&'static str> {
+    let language = match base_name(file_name) {
+        "Makefile" | "makefile" | "GNUmakefile" => "Makefile",
+        "Dockerfile" => "Dockerfile",
+        "CMakeLists.txt" => "CMake",
+        "Cargo.lock" => "TOML",
+        _ => {
+            match file_extension(file_name).to_lowercase().as_str() {
+                "rs" => "Rust",
+                "c" | "h" => "C",
+                "cpp" | "cc" | "cxx" | "hpp" | "hh" | "hxx" => "C++",
+                "cs" => "C#",
+                "go" => "Go",
+                "java" => "Java",
+                "kt" | "kts" => "Kotlin",
+                "scala" => "Scala",
+                "swift" => "Swift",
+                "m" | "mm" => "Objective-C",
+                "py" | "pyi" => "Python",
+                "rb" => "Ruby",
+                "php" => "PHP",
+                "pl" | "pm" => "Perl",
+                "lua" => "Lua",
+                "r" => "R",
+                "dart" => "Dart",
+                "ex" | "exs" => "Elixir",
+                "erl" | "hrl" => "Erlang",
+                "hs" => "Haskell",
+                "ml" | "mli" => "OCaml",
+                "fs" | "fsx" => "F#",
+                "clj" | "cljs" => "Clojure",
+                "zig" => "Zig",
+                "js" | "mjs" | "cjs" | "jsx" => "JavaScript",
+                "ts" | "tsx" => "TypeScript",
+                "vue" => "Vue",
+                "html" | "htm" => "HTML",
+                "css" => "CSS",
+                "scss" | "sass" => "Sass",
+                "sh" | "bash" | "zsh" => "Shell",
+                "ps1" => "PowerShell",
+                "sql" => "SQL",
+                "json" => "JSON",
+                "yaml" | "yml" => "YAML",
+                "toml" => "TOML",
+                "xml" => "XML",
+                "md" | "markdown" => "Markdown",
+                "proto" => "Protocol Buffers",
+                "cmake" => "CMake",
+                _ => return None
+            }
+        }
+    };
+
+    Some(language)
+}
+
+// The first non-empty line of the commit message
+pub fn commit_first_line(message: &str) -> &str {
+    message.lines().map(|line| line.trim()).find(|line| !line.is_empty()).unwrap_or("")
+}
+
+// The type of a conventional commit message (such as 'feat' for 'feat(parser): add support for joins')
+pub fn conventional_commit_type(message: &str) -> Option<String> {
+    static CONVENTIONAL_COMMIT: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r"^([A-Za-z]+)(\([^)]*\))?!?:\s").unwrap()
+    });
+
+    CONVENTIONAL_COMMIT
+        .captures(commit_first_line(message))
+        .map(|captures| captures[1].to_lowercase())
+}
+
+// Issue keys such as 'PROJ-123' or '#123', in order of appearance
+pub fn issue_keys(message: &str) -> Vec<String> {
+    static ISSUE_KEY: LazyLock<Regex> = LazyLock::new(|| {
+        Regex::new(r"\b[A-Z][A-Z0-9_]+-[0-9]+\b|#[0-9]+\b").unwrap()
+    });
+
+    let mut issue_keys = Vec::<String>::new();
+    for issue_key in ISSUE_KEY.find_iter(message) {
+        if !issue_keys.iter().any(|existing| existing == issue_key.as_str()) {
+            issue_keys.push(issue_key.as_str().to_owned());
+        }
+    }
+
+    issue_keys
+}
+
+pub fn parse_timezone(timezone: &str) -> Option<Tz> {
+    timezone.parse::<Tz>().ok()
+}
+
+// Hours are given as '2025-01-31T13:00', days as '2025-01-31', weeks as ISO weeks ('2025-W05'), months as '2025-01',
+// quarters as '2025-Q1' and years as '2025'
+pub fn time_bucket(timestamp: i64, unit: &str, timezone: &Tz) -> Option<String> {
+    let date_time = DateTime::from_timestamp(timestamp, 0)?.with_timezone(timezone);
+
+    let bucket = match unit {
+        "hour" => date_time.format("%Y-%m-%dT%H:00").to_string(),
+        "day" => date_time.format("%Y-%m-%d").to_string(),
+        "week" => {
+            let week = date_time.iso_week();
+            format!("{}-W{:02}", week.year(), week.week())
+        }
+        "month" => date_time.format("%Y-%m").to_string(),
+        "quarter" => format!("{}-Q{}", date_time.year(), (date_time.month() - 1) / 3 + 1),
+        "year" => date_time.format("%Y").to_string(),
+        _ => return None
+    };
+
+    Some(bucket)
+}
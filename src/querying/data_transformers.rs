@@ -1,23 +1,86 @@
-use glob::{Pattern, PatternError};
+use glob::Pattern;
 use regex::Regex;
 use thiserror::Error;
 
 pub struct ModuleDefinitions {
-    modules: Vec<(Pattern, String)>
+    modules: Vec<ModuleRule>
+}
+
+pub struct ModuleRule {
+    pub line_number: usize,
+    pub definition: String,
+    pattern: ModulePattern,
+    module_name: String
+}
+
+enum ModulePattern {
+    Glob(Pattern),
+    Regex(Regex)
+}
+
+impl ModuleRule {
+    pub fn get_module(&self, file_name: &str) -> Option<String> {
+        match &self.pattern {
+            ModulePattern::Glob(pattern) => {
+                if pattern.matches(file_name) {
+                    return Some(self.module_name.clone());
+                }
+            }
+            ModulePattern::Regex(pattern) => {
+                if let Some(captures) = pattern.captures(file_name) {
+                    let mut expanded_module_name = String::new();
+                    captures.expand(&self.module_name, &mut expanded_module_name);
+                    return Some(expanded_module_name);
+                }
+            }
+        }
+
+        None
+    }
 }
 
 impl ModuleDefinitions {
-    pub fn new(definition: &str) -> Result<ModuleDefinitions, ModuleDefinitionError> {
+    // Rules are either globs or regexes prefixed by 'regex:', where the module name of a regex can reference its capture
+    // groups (such as $1). The file name (such as 'modules.txt') is only used for errors.
+    pub fn new(file: &str, definition: &str) -> Result<ModuleDefinitions, RuleFileError> {
         let mut modules = Vec::new();
 
         let rename_pattern = Regex::new("(.*)=>(.*)").unwrap();
-        for line in definition.lines() {
-            if let Some(line_capture) = rename_pattern.captures(&line) {
-                modules.push((
-                    Pattern::new(&line_capture[1].trim()).map_err(|err| ModuleDefinitionError::Pattern(err))?,
-                    line_capture[2].trim().to_owned()
-                ));
+        for (line_index, line) in definition.lines().enumerate() {
+            let line_number = line_index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
             }
+
+            let invalid_line = || RuleFileError::new(
+                file,
+                line_number,
+                format!("invalid definition '{}' (expected '<pattern> => <module>')", line)
+            );
+
+            let line_capture = rename_pattern.captures(line).ok_or_else(invalid_line)?;
+
+            let pattern = line_capture[1].trim();
+            let module_name = line_capture[2].trim().to_owned();
+            if pattern.is_empty() || module_name.is_empty() {
+                return Err(invalid_line());
+            }
+
+            let pattern = if let Some(pattern) = pattern.strip_prefix("regex:") {
+                let pattern = pattern.trim();
+                ModulePattern::Regex(
+                    create_full_match_regex(pattern)
+                        .map_err(|err| RuleFileError::new(file, line_number, format!("invalid regex '{}': {}", pattern, err)))?
+                )
+            } else {
+                ModulePattern::Glob(
+                    Pattern::new(pattern)
+                        .map_err(|err| RuleFileError::new(file, line_number, format!("invalid pattern '{}': {}", pattern, err)))?
+                )
+            };
+
+            modules.push(ModuleRule { line_number, definition: line.to_owned(), pattern, module_name });
         }
 
         Ok(ModuleDefinitions { modules })
@@ -27,33 +90,94 @@ impl ModuleDefinitions {
         ModuleDefinitions { modules: Vec::new() }
     }
 
-    pub fn get_module(&self, file_name: &str) -> Option<&str> {
-        for (pattern, module_name) in &self.modules {
-            if pattern.matches(file_name) {
-                return Some(module_name);
-            }
-        }
+    pub fn get_module(&self, file_name: &str) -> Option<String> {
+        self.modules.iter().find_map(|module| module.get_module(file_name))
+    }
 
-        None
+    pub fn rules(&self) -> &[ModuleRule] {
+        &self.modules
     }
 }
 
+fn create_full_match_regex(pattern: &str) -> Result<Regex, regex::Error> {
+    Regex::new(&format!("^(?:{})$", pattern))
+}
+
+pub fn default_module_name(file_name: &str) -> String {
+    if let Some(parent) = std::path::Path::new(file_name).parent() {
+        let parent = parent.to_str().unwrap().to_owned();
+        if !parent.is_empty() {
+            parent
+        } else {
+            "<root>".to_owned()
+        }
+    } else {
+        file_name.to_owned()
+    }
+}
+
+pub fn truncate_module_name_at_depth(module_name: &str, depth: usize) -> String {
+    module_name.split('/').take(depth).collect::<Vec<_>>().join("/")
+}
+
 pub struct AuthorNormalizer {
-    authors: Vec<(String, String)>
+    authors: Vec<(AuthorMatcher, String)>
+}
+
+enum AuthorMatcher {
+    Name(String),
+    NamePattern(Regex),
+    Email(String),
+    EmailPattern(Regex)
 }
 
 impl AuthorNormalizer {
-    pub fn new(definition: &str) -> Result<AuthorNormalizer, ModuleDefinitionError> {
+    // Sources are either exact names, name regexes on the form /pattern/, e-mails on the form <email> (case-insensitive)
+    // or e-mail regexes on the form </pattern/>
+    pub fn new(definition: &str) -> Result<AuthorNormalizer, RuleFileError> {
+        const FILE: &str = "authors.txt";
         let mut authors = Vec::new();
 
         let rename_pattern = Regex::new("(.*)=>(.*)").unwrap();
-        for line in definition.lines() {
-            if let Some(line_capture) = rename_pattern.captures(&line) {
-                authors.push((
-                    line_capture[1].trim().to_owned(),
-                    line_capture[2].trim().to_owned()
-                ));
+        for (line_index, line) in definition.lines().enumerate() {
+            let line_number = line_index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid_line = || RuleFileError::new(
+                FILE,
+                line_number,
+                format!("invalid alias '{}' (expected '<source> => <name>')", line)
+            );
+
+            let line_capture = rename_pattern.captures(line).ok_or_else(invalid_line)?;
+
+            let source = line_capture[1].trim();
+            let target = line_capture[2].trim().to_owned();
+            if source.is_empty() || target.is_empty() {
+                return Err(invalid_line());
             }
+
+            let create_regex = |pattern: &str| {
+                Regex::new(pattern)
+                    .map_err(|err| RuleFileError::new(FILE, line_number, format!("invalid pattern '{}': {}", pattern, err)))
+            };
+
+            let matcher = if let Some(email) = source.strip_prefix('<').and_then(|source| source.strip_suffix('>')) {
+                match strip_pattern_delimiters(email) {
+                    Some(pattern) => AuthorMatcher::EmailPattern(create_regex(pattern)?),
+                    None => AuthorMatcher::Email(email.to_lowercase())
+                }
+            } else {
+                match strip_pattern_delimiters(source) {
+                    Some(pattern) => AuthorMatcher::NamePattern(create_regex(pattern)?),
+                    None => AuthorMatcher::Name(source.to_owned())
+                }
+            };
+
+            authors.push((matcher, target));
         }
 
         Ok(AuthorNormalizer { authors })
@@ -63,51 +187,350 @@ impl AuthorNormalizer {
         AuthorNormalizer { authors: Vec::new() }
     }
 
-    pub fn normalize(&self, author: &str) -> Option<&str> {
-        for (source, target) in &self.authors {
-            if source == author {
+    pub fn normalize(&self, author: &str, email: Option<&str>) -> Option<&str> {
+        let email = email.filter(|email| !email.is_empty());
+
+        for (matcher, target) in &self.authors {
+            let is_match = match matcher {
+                AuthorMatcher::Name(name) => name == author,
+                AuthorMatcher::NamePattern(pattern) => pattern.is_match(author),
+                AuthorMatcher::Email(value) => email.map(|email| value == &email.to_lowercase()).unwrap_or(false),
+                AuthorMatcher::EmailPattern(pattern) => email.map(|email| pattern.is_match(email)).unwrap_or(false)
+            };
+
+            if is_match {
                 return Some(target);
             }
         }
 
         None
     }
+
+    // Creates the definition line mapping the alias to the name, where aliases that would be parsed as patterns are
+    // written as exact regexes
+    pub fn create_alias_definition(alias: &str, name: &str) -> String {
+        if alias.starts_with('/') || alias.starts_with('<') || alias.starts_with('#') || alias.contains("=>") {
+            format!("/^{}$/ => {}", regex::escape(alias), name)
+        } else {
+            format!("{} => {}", alias, name)
+        }
+    }
+}
+
+fn strip_pattern_delimiters(value: &str) -> Option<&str> {
+    if value.len() >= 2 && value.starts_with('/') && value.ends_with('/') {
+        Some(&value[1..(value.len() - 1)])
+    } else {
+        None
+    }
+}
+
+enum AuthorExclusion {
+    Exact(String),
+    Pattern(Regex)
+}
+
+pub struct AuthorExclusions {
+    exclusions: Vec<AuthorExclusion>
+}
+
+impl AuthorExclusions {
+    // Exclusions are either exact (case-insensitive) names/e-mails or regexes on the form /pattern/
+    pub fn new(exclusions: &[String]) -> Result<AuthorExclusions, AuthorExclusionError> {
+        let mut author_exclusions = Vec::new();
+
+        for exclusion in exclusions {
+            let exclusion = exclusion.trim();
+            if exclusion.is_empty() {
+                continue;
+            }
+
+            if let Some(pattern) = strip_pattern_delimiters(exclusion) {
+                author_exclusions.push(AuthorExclusion::Pattern(
+                    Regex::new(pattern).map_err(|err| AuthorExclusionError { exclusion: exclusion.to_owned(), err })?
+                ));
+            } else {
+                author_exclusions.push(AuthorExclusion::Exact(exclusion.to_lowercase()));
+            }
+        }
+
+        Ok(AuthorExclusions { exclusions: author_exclusions })
+    }
+
+    pub fn is_excluded(&self, name: &str, email: Option<&str>) -> bool {
+        let email = email.filter(|email| !email.is_empty());
+
+        self.exclusions.iter().any(|exclusion| {
+            match exclusion {
+                AuthorExclusion::Exact(value) => {
+                    value == &name.to_lowercase() || email.map(|email| value == &email.to_lowercase()).unwrap_or(false)
+                }
+                AuthorExclusion::Pattern(pattern) => {
+                    pattern.is_match(name) || email.map(|email| pattern.is_match(email)).unwrap_or(false)
+                }
+            }
+        })
+    }
+}
+
+#[derive(Debug, Error)]
+#[error("Invalid pattern '{exclusion}': {err}")]
+pub struct AuthorExclusionError {
+    pub exclusion: String,
+    pub err: regex::Error
 }
 
+// An invalid line in one of the rule files of the data directory (such as 'architecture_rules.txt')
 #[derive(Debug, Error)]
-pub enum ModuleDefinitionError {
-    #[error("Pattern: {0}")]
-    Pattern(PatternError),
+#[error("{file}, line {line_number}: {message}")]
+pub struct RuleFileError {
+    pub file: String,
+    pub line_number: usize,
+    pub message: String
+}
+
+impl RuleFileError {
+    fn new(file: &str, line_number: usize, message: String) -> RuleFileError {
+        RuleFileError { file: file.to_owned(), line_number, message }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CouplingRuleKind {
+    Allow,
+    Forbid
+}
+
+pub struct ArchitectureRule {
+    pub kind: CouplingRuleKind,
+    pub left: Pattern,
+    pub right: Pattern,
+    pub definition: String
+}
+
+impl ArchitectureRule {
+    pub fn matches(&self, left_module_name: &str, right_module_name: &str) -> bool {
+        (self.left.matches(left_module_name) && self.right.matches(right_module_name))
+        || (self.left.matches(right_module_name) && self.right.matches(left_module_name))
+    }
+}
+
+pub struct ArchitectureRules {
+    rules: Vec<ArchitectureRule>
+}
+
+impl ArchitectureRules {
+    pub fn new(definition: &str) -> Result<ArchitectureRules, RuleFileError> {
+        const FILE: &str = "architecture_rules.txt";
+        let mut rules = Vec::new();
+
+        let rule_pattern = Regex::new("^(allow|forbid)\\s+(.+)<->(.+)$").unwrap();
+        for (line_index, line) in definition.lines().enumerate() {
+            let line_number = line_index + 1;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let line_capture = rule_pattern.captures(line)
+                .ok_or_else(|| {
+                    RuleFileError::new(
+                        FILE,
+                        line_number,
+                        format!("invalid rule '{}' (expected 'allow <module> <-> <module>' or 'forbid <module> <-> <module>')", line)
+                    )
+                })?;
+
+            let create_pattern = |pattern: &str| {
+                Pattern::new(pattern.trim())
+                    .map_err(|err| RuleFileError::new(FILE, line_number, format!("invalid pattern '{}': {}", pattern.trim(), err)))
+            };
+
+            rules.push(
+                ArchitectureRule {
+                    kind: if &line_capture[1] == "allow" { CouplingRuleKind::Allow } else { CouplingRuleKind::Forbid },
+                    left: create_pattern(&line_capture[2])?,
+                    right: create_pattern(&line_capture[3])?,
+                    definition: line.to_owned()
+                }
+            );
+        }
+
+        Ok(ArchitectureRules { rules })
+    }
+
+    pub fn empty() -> ArchitectureRules {
+        ArchitectureRules { rules: Vec::new() }
+    }
+
+    // The first matching rule decides if the coupling is allowed, couplings not matching any rule are allowed
+    pub fn forbidding_rule(&self, left_module_name: &str, right_module_name: &str) -> Option<&ArchitectureRule> {
+        self.rules
+            .iter()
+            .find(|rule| rule.matches(left_module_name, right_module_name))
+            .filter(|rule| rule.kind == CouplingRuleKind::Forbid)
+    }
 }
 
 pub struct IgnoreFile {
-    patterns: Vec<Pattern>
+    patterns: Vec<IgnorePattern>
+}
+
+struct IgnorePattern {
+    regex: Regex,
+    negated: bool,
+    directory_only: bool
 }
 
 impl IgnoreFile {
-    pub fn new(definition: &str) -> IgnoreFile {
+    // Uses the semantics of '.gitignore': comments, negation ('!'), directory patterns (trailing '/'), anchoring
+    // (a '/' at the start or in the middle) and '**'
+    pub fn new(definition: &str) -> Result<IgnoreFile, RuleFileError> {
+        const FILE: &str = "ignore.txt";
         let mut patterns = Vec::new();
 
-        for line in definition.lines() {
-            if let Ok(pattern) = Pattern::new(line) {
-                patterns.push(pattern);
+        for (line_index, line) in definition.lines().enumerate() {
+            let line_number = line_index + 1;
+
+            // Trailing spaces are ignored unless escaped
+            let line = line.trim_start();
+            let line = if line.trim_end().ends_with('\\') && line.len() > line.trim_end().len() {
+                &line[..line.trim_end().len() + 1]
+            } else {
+                line.trim_end()
+            };
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
             }
+
+            let (negated, pattern) = match line.strip_prefix('!') {
+                Some(pattern) => (true, pattern),
+                None => (false, line)
+            };
+
+            let (directory_only, pattern) = match pattern.strip_suffix('/') {
+                Some(pattern) => (true, pattern),
+                None => (false, pattern)
+            };
+
+            let anchored = pattern.contains('/');
+            let pattern = pattern.strip_prefix('/').unwrap_or(pattern);
+            if pattern.is_empty() {
+                return Err(RuleFileError::new(FILE, line_number, format!("empty pattern '{}'", line)));
+            }
+
+            let pattern = gitignore_pattern_to_regex(pattern)
+                .ok_or_else(|| RuleFileError::new(FILE, line_number, format!("invalid pattern '{}'", line)))?;
+
+            let pattern = if anchored {
+                format!("^{}$", pattern)
+            } else {
+                format!("^(?:.*/)?{}$", pattern)
+            };
+
+            patterns.push(
+                IgnorePattern {
+                    regex: Regex::new(&pattern)
+                        .map_err(|err| RuleFileError::new(FILE, line_number, format!("invalid pattern '{}': {}", line, err)))?,
+                    negated,
+                    directory_only
+                }
+            );
         }
 
-        IgnoreFile { patterns }
+        Ok(IgnoreFile { patterns })
     }
 
     pub fn empty() -> IgnoreFile {
         IgnoreFile { patterns: Vec::new() }
     }
 
+    // As in git, a file can't be re-included if one of its parent folders is ignored
     pub fn is_ignored(&self, file_name: &str) -> bool {
-        for pattern in &self.patterns {
-            if pattern.matches(file_name) {
+        let parts = file_name.split('/').collect::<Vec<_>>();
+        for length in 1..parts.len() {
+            if self.matches(&parts[..length].join("/"), true) {
                 return true;
             }
         }
 
-        false
+        self.matches(file_name, false)
     }
+
+    // The last matching pattern decides if the path is ignored
+    fn matches(&self, path: &str, is_directory: bool) -> bool {
+        self.patterns
+            .iter()
+            .rev()
+            .find(|pattern| (is_directory || !pattern.directory_only) && pattern.regex.is_match(path))
+            .map(|pattern| !pattern.negated)
+            .unwrap_or(false)
+    }
+}
+
+// Translates a gitignore pattern (without negation, trailing and leading '/') into a regex, where '*' and '?' don't
+// match '/' while '**' matches any number of folders
+fn gitignore_pattern_to_regex(pattern: &str) -> Option<String> {
+    let chars = pattern.chars().collect::<Vec<_>>();
+    let mut regex = String::new();
+
+    let mut index = 0;
+    while index < chars.len() {
+        let at_segment_start = index == 0 || chars[index - 1] == '/';
+        let is_double_star = chars[index] == '*'
+            && chars.get(index + 1) == Some(&'*')
+            && matches!(chars.get(index + 2), None | Some('/'));
+
+        match chars[index] {
+            '*' if at_segment_start && is_double_star => {
+                if index + 2 >= chars.len() {
+                    regex += ".*";
+                } else {
+                    regex += "(?:.*/)?";
+                }
+
+                index += 3;
+                continue;
+            }
+            '*' => regex += "[^/]*",
+            '?' => regex += "[^/]",
+            '[' => {
+                let mut class_end = index + 1;
+                if matches!(chars.get(class_end), Some('!') | Some('^')) {
+                    class_end += 1;
+                }
+                if chars.get(class_end) == Some(&']') {
+                    class_end += 1;
+                }
+                while class_end < chars.len() && chars[class_end] != ']' {
+                    class_end += 1;
+                }
+                if class_end >= chars.len() {
+                    return None;
+                }
+
+                regex += "[";
+                for (class_index, c) in chars[index + 1..class_end].iter().enumerate() {
+                    match c {
+                        '!' | '^' if class_index == 0 => regex += "^",
+                        '-' => regex += "-",
+                        c => regex += &regex::escape(&c.to_string())
+                    }
+                }
+                regex += "]";
+
+                index = class_end;
+            }
+            '\\' => {
+                index += 1;
+                regex += &regex::escape(&chars.get(index)?.to_string());
+            }
+            c => regex += &regex::escape(&c.to_string())
+        }
+
+        index += 1;
+    }
+
+    Some(regex)
 }
\ No newline at end of file
@@ -1,44 +1,133 @@
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::future::Future;
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 
-use log::warn;
+use futures::StreamExt;
+use regex::Regex;
 
 use serde::{Deserialize, Serialize};
 
-use datafusion::arrow::array::{Array, AsArray};
+use datafusion::arrow::array::AsArray;
 use datafusion::arrow::datatypes::{Int64Type, UInt64Type};
-use datafusion::common::ScalarValue;
+use datafusion::common::{DataFusionError, ScalarValue};
+use datafusion::execution::context::SQLOptions;
+use datafusion::execution::runtime_env::RuntimeEnvBuilder;
+use datafusion::datasource::TableType;
+use datafusion::physical_plan::display::DisplayableExecutionPlan;
+use datafusion::physical_plan::{displayable, execute_stream, SendableRecordBatchStream};
 use datafusion::prelude::*;
 
 use crate::indexing::{GIT_FILE_ENTRIES_PATH, GIT_LOG_PATH};
 use crate::indexing::indexer::GitLogEntry;
-use crate::querying::{custom_functions, QueryingResult};
+use crate::querying::{custom_functions, user_views, QueryingError, QueryingResult};
+use crate::querying::custom_functions::ModuleViews;
 use crate::querying::model_data_extraction::{collect_rows, collect_rows_into, yield_rows, FromRow};
-use crate::querying::model::{ChangeCouplingEntry, CommitSpreadEntry, CustomAnalysis, CustomValue, FileEntry, FileHistoryEntry, HotspotEntry, MainDeveloperEntry, Module, RepositorySummary, SumOfCouplingEntry};
-use crate::querying::querying_helpers::add_optional_limit;
-
-#[derive(Clone, Deserialize, Serialize)]
+use crate::querying::frequent_itemsets::closed_frequent_itemsets;
+use crate::querying::data_transformers::{default_module_name, ArchitectureRules, ModuleDefinitions, ModuleRule};
+use crate::querying::author_aliases::{suggest_author_aliases, AuthorIdentity};
+use crate::querying::module_suggestion::{create_module_definition, louvain_communities, name_modules};
+use crate::querying::model::{graph_attributes, AuthorAliasSuggestion, ChangeCouplingEntry, CoChangeClusterEntry, CodeAgeEntry, ColumnSchema, CommitSpreadEntry, CouplingViolationEntry, CustomAnalysis, CustomValue, FileEntry, FileHistoryEntry, FunctionSchema, Graph, GraphEdge, GraphNode, HotspotEntry, MainDeveloperEntry, Module, ModuleAssignmentEntry, ModuleDefinitionComparison, ModuleDefinitionSuggestion, ModuleDefinitionValidation, ModuleRuleEntry, QueryPlan, QueryPlanOperator, QuerySchema, RepositorySummary, ShadowedFileEntry, ShadowedRuleEntry, SuggestedModule, SumOfCouplingEntry, TableSchema, TruckFactorEntry, UnmatchedFileEntry};
+use crate::querying::querying_helpers::{add_optional_limit, bind_parameters, QueryParameters};
+use crate::querying::user_views::ViewFile;
+
+// Excluded revisions are matched by prefix, so they must be at least as long as an abbreviated git hash
+pub const MIN_EXCLUDED_REVISION_LENGTH: usize = 7;
+
+#[derive(Clone, Default, Deserialize, Serialize)]
 pub struct RepositoryQueryingConfig {
     pub min_date: Option<i64>,
-    pub max_date: Option<i64>
+    pub max_date: Option<i64>,
+
+    #[serde(default)]
+    pub coupling_max_files_per_commit: Option<u64>,
+    #[serde(default)]
+    pub coupling_excluded_revisions: Vec<String>,
+    #[serde(default)]
+    pub coupling_excluded_commit_message_patterns: Vec<String>,
+
+    #[serde(default)]
+    pub excluded_authors: Vec<String>,
+
+    // The timezone used when bucketing dates, such as 'Europe/Stockholm' (UTC if not set)
+    #[serde(default)]
+    pub timezone: Option<String>,
+
+    // The module view used by the module analyses (the default view if not set)
+    #[serde(skip)]
+    pub module_view: Option<String>
 }
 
-impl Default for RepositoryQueryingConfig {
-    fn default() -> Self {
-        RepositoryQueryingConfig {
-            min_date: None,
-            max_date: None
-        }
+#[derive(Clone, Default)]
+pub struct CustomAnalysisLimits {
+    pub timeout: Option<Duration>,
+    pub memory_limit: Option<usize>,
+    pub max_rows: Option<usize>
+}
+
+pub const DEFAULT_MODULE_VIEW: &str = "default";
+
+// The default view is defined by 'modules.txt' while other views are defined by 'modules.<view>.txt'
+pub fn module_definition_file_name(view: &str) -> QueryingResult<String> {
+    if view == DEFAULT_MODULE_VIEW {
+        return Ok("modules.txt".to_owned());
+    }
+
+    if view.is_empty() || !view.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(QueryingError::InvalidModuleView(view.to_owned()));
     }
+
+    Ok(format!("modules.{}.txt", view))
+}
+
+pub fn module_definition_path(data_directory: &Path, view: &str) -> QueryingResult<PathBuf> {
+    Ok(data_directory.join(module_definition_file_name(view)?))
+}
+
+// The available module views, starting with the default view
+pub fn module_views(data_directory: &Path) -> Vec<String> {
+    let mut views = std::fs::read_dir(data_directory)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| {
+                    let file_name = entry.file_name().to_str()?.to_owned();
+                    let view = file_name.strip_prefix("modules.")?.strip_suffix(".txt")?.to_owned();
+                    module_definition_file_name(&view).ok().map(|_| view)
+                })
+                .filter(|view| view != DEFAULT_MODULE_VIEW)
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    views.sort();
+    views.insert(0, DEFAULT_MODULE_VIEW.to_owned());
+    views
 }
 
 pub struct RepositoryQuerying {
     pub data_directory: PathBuf,
-    pub ctx: SessionContext
+    pub ctx: SessionContext,
+    config: RepositoryQueryingConfig,
+    // Shared with the queryings derived from this querying
+    module_views: Arc<ModuleViews>,
+    view_files: Arc<Vec<ViewFile>>,
+    architecture_rules: Arc<ArchitectureRules>
 }
 
 impl RepositoryQuerying {
     pub async fn new(data_directory: &Path, config: RepositoryQueryingConfig) -> QueryingResult<RepositoryQuerying> {
+        let view_files = user_views::load_view_files(data_directory)?;
+        RepositoryQuerying::with_view_files(data_directory, config, &view_files).await
+    }
+
+    // Uses the given user-defined views instead of the ones in the data directory (such as to validate changed views)
+    pub async fn with_view_files(
+        data_directory: &Path,
+        config: RepositoryQueryingConfig,
+        view_files: &[ViewFile]
+    ) -> QueryingResult<RepositoryQuerying> {
         let ctx = SessionContext::new();
 
         ctx.register_parquet(
@@ -53,21 +142,90 @@ impl RepositoryQuerying {
             ParquetReadOptions::default()
         ).await?;
 
-        custom_functions::add(data_directory, &ctx)?;
+        let module_views = custom_functions::add(data_directory, &config, &ctx)?;
 
-        ctx.sql(
-            r#"
-            CREATE VIEW git_log AS
-            SELECT
-                revision,
-                date,
-                normalize_author(author) AS author,
-                commit_message
-            FROM raw_git_log
-            "#
+        let architecture_rules = match std::fs::read_to_string(data_directory.join("architecture_rules.txt")) {
+            Ok(definition) => ArchitectureRules::new(&definition)?,
+            _ => ArchitectureRules::empty()
+        };
+
+        let repository_querying = RepositoryQuerying {
+            data_directory: data_directory.to_owned(),
+            ctx,
+            config,
+            module_views,
+            view_files: Arc::new(view_files.to_vec()),
+            architecture_rules: Arc::new(architecture_rules)
+        };
+
+        repository_querying.create_views().await?;
+        Ok(repository_querying)
+    }
+
+    // Derives a querying using another module view, where the given dates override the configured date range. The
+    // tables, functions and definitions are shared with this querying so that only the views are created
+    pub async fn with_scope(
+        &self,
+        module_view: Option<String>,
+        min_date: Option<i64>,
+        max_date: Option<i64>
+    ) -> QueryingResult<RepositoryQuerying> {
+        let ctx = SessionContext::new();
+        for table_name in ["raw_git_log", "all_git_file_entries"] {
+            ctx.register_table(table_name, self.ctx.table_provider(table_name).await?)?;
+        }
+
+        for function in self.ctx.state().scalar_functions().values() {
+            ctx.register_udf(function.as_ref().clone());
+        }
+
+        let repository_querying = RepositoryQuerying {
+            data_directory: self.data_directory.clone(),
+            ctx,
+            config: RepositoryQueryingConfig {
+                min_date: min_date.or(self.config.min_date),
+                max_date: max_date.or(self.config.max_date),
+                module_view,
+                ..self.config.clone()
+            },
+            module_views: self.module_views.clone(),
+            view_files: self.view_files.clone(),
+            architecture_rules: self.architecture_rules.clone()
+        };
+
+        repository_querying.create_views().await?;
+        Ok(repository_querying)
+    }
+
+    async fn create_views(&self) -> QueryingResult<()> {
+        custom_functions::add_extract_module_name(&self.ctx, &self.module_views, self.config.module_view.as_deref())?;
+
+        // Indexes created before the author e-mail was added to the log lack the column
+        let author_email_column = if self.ctx.table("raw_git_log").await?.schema().has_column_with_unqualified_name("author_email") {
+            "author_email"
+        } else {
+            "arrow_cast('', 'Utf8View')"
+        };
+
+        self.ctx.sql(
+            &format!(
+                r#"
+                CREATE VIEW git_log AS
+                SELECT
+                    revision,
+                    date,
+                    normalize_author(author, {0}) AS author,
+                    {0} AS author_email,
+                    commit_message
+                FROM raw_git_log
+                WHERE
+                    NOT is_excluded_author(author, {0}) AND NOT is_excluded_author(normalize_author(author, {0}), {0})
+                "#,
+                author_email_column
+            )
         ).await?;
 
-        ctx.sql(
+        self.ctx.sql(
             &format!(
                 r#"
                 CREATE VIEW git_file_entries AS
@@ -75,14 +233,15 @@ impl RepositoryQuerying {
                     *
                 FROM all_git_file_entries
                 WHERE
-                    exists_at_head AND NOT is_ignored(file_name) AND date >= {} AND date <= {}
+                    exists_at_head AND NOT is_ignored(file_name) AND date >= {} AND date <= {} {}
                 "#,
-                config.min_date.unwrap_or(0),
-                config.max_date.unwrap_or(i64::MAX)
+                self.config.min_date.unwrap_or(0),
+                self.config.max_date.unwrap_or(i64::MAX),
+                if !self.config.excluded_authors.is_empty() { "AND revision IN (SELECT revision FROM git_log)" } else { "" }
             )
         ).await?;
 
-        ctx.sql(
+        self.ctx.sql(
             r#"
             CREATE VIEW git_module_entries AS
             SELECT
@@ -102,7 +261,28 @@ impl RepositoryQuerying {
         "#
         ).await?;
 
-        ctx.sql(
+        self.ctx.sql(&create_coupling_file_entries_view(&self.config)?).await?;
+
+        self.ctx.sql(
+            r#"
+            CREATE VIEW coupling_module_entries AS
+            SELECT
+                revision,
+                module_name,
+                date,
+                COUNT(*) AS num_changed_files
+            FROM (
+                SELECT
+                    revision,
+                    extract_module_name(file_name) AS module_name,
+                    date
+                FROM coupling_file_entries
+            )
+            GROUP BY revision, module_name, date
+        "#
+        ).await?;
+
+        self.ctx.sql(
             r#"
             CREATE VIEW num_module_revisions AS
             SELECT
@@ -115,7 +295,7 @@ impl RepositoryQuerying {
             "#
         ).await?;
 
-        ctx.sql(
+        self.ctx.sql(
             r#"
             CREATE VIEW latest_revision_file_entries AS
             SELECT
@@ -133,7 +313,7 @@ impl RepositoryQuerying {
             "#
         ).await?;
 
-        ctx.sql(
+        self.ctx.sql(
             r#"
             CREATE VIEW latest_revision_module_entries AS
             SELECT
@@ -150,7 +330,7 @@ impl RepositoryQuerying {
         "#
         ).await?;
 
-        ctx.sql(
+        self.ctx.sql(
             r#"
             CREATE VIEW file_hotspots AS
             SELECT
@@ -172,7 +352,7 @@ impl RepositoryQuerying {
             "#
         ).await?;
 
-        ctx.sql(
+        self.ctx.sql(
             r#"
             CREATE VIEW module_hotspots AS
             SELECT
@@ -193,35 +373,35 @@ impl RepositoryQuerying {
             "#
         ).await?;
 
-        ctx.sql(
+        self.ctx.sql(
             r#"
             CREATE VIEW file_coupled_revisions AS
             SELECT
                  left_entries.revision AS revision,
                  left_entries.file_name AS left_file_name,
                  right_entries.file_name AS right_file_name
-            FROM git_file_entries left_entries, git_file_entries right_entries
+            FROM coupling_file_entries left_entries, coupling_file_entries right_entries
             WHERE
                 left_entries.revision = right_entries.revision
                 AND left_entries.file_name != right_entries.file_name
             "#
         ).await?;
 
-        ctx.sql(
+        self.ctx.sql(
             r#"
             CREATE VIEW module_coupled_revisions AS
             SELECT
                  left_entries.revision AS revision,
                  left_entries.module_name AS left_module_name,
                  right_entries.module_name AS right_module_name
-            FROM git_module_entries left_entries, git_module_entries right_entries
+            FROM coupling_module_entries left_entries, coupling_module_entries right_entries
             WHERE
                 left_entries.revision = right_entries.revision
                 AND left_entries.module_name != right_entries.module_name
             "#
         ).await?;
 
-        ctx.sql(
+        self.ctx.sql(
             r#"
             CREATE VIEW file_sum_of_couplings AS
             SELECT
@@ -232,7 +412,7 @@ impl RepositoryQuerying {
             "#
         ).await?;
 
-        ctx.sql(
+        self.ctx.sql(
             r#"
             CREATE VIEW module_sum_of_couplings AS
             SELECT
@@ -243,7 +423,7 @@ impl RepositoryQuerying {
             "#
         ).await?;
 
-        ctx.sql(
+        self.ctx.sql(
             r#"
             CREATE VIEW file_developers AS
             SELECT
@@ -257,7 +437,7 @@ impl RepositoryQuerying {
             "#
         ).await?;
 
-        ctx.sql(
+        self.ctx.sql(
             r#"
             CREATE VIEW module_developers AS
             SELECT
@@ -269,15 +449,17 @@ impl RepositoryQuerying {
             "#
         ).await?;
 
-        Ok(RepositoryQuerying { data_directory: data_directory.to_owned(), ctx })
+        user_views::create_views(&self.ctx, &self.view_files).await?;
+
+        Ok(())
     }
 
     pub async fn summary(&self) -> QueryingResult<RepositorySummary> {
         let mut result = RepositorySummary {
             data_directory: self.data_directory
                 .components()
-                .last()
-                .map(|x| x.as_os_str().to_str()).flatten()
+                .next_back()
+                .and_then(|x| x.as_os_str().to_str())
                 .unwrap_or("N/A").to_owned(),
 
             num_revisions: 0,
@@ -310,11 +492,13 @@ impl RepositoryQuerying {
                 FIRST_VALUE(revision ORDER BY date) AS first_revision,
                 FIRST_VALUE(date ORDER BY date) AS first_date,
                 FIRST_VALUE(author ORDER BY date) AS first_author,
+                FIRST_VALUE(author_email ORDER BY date) AS first_author_email,
                 FIRST_VALUE(commit_message ORDER BY date) AS first_commit_message,
 
                 LAST_VALUE(revision ORDER BY date) AS last_revision,
                 LAST_VALUE(date ORDER BY date) AS last_date,
                 LAST_VALUE(author ORDER BY date) AS last_author,
+                LAST_VALUE(author_email ORDER BY date) AS last_author_email,
                 LAST_VALUE(commit_message ORDER BY date) AS lastcommit_message
             FROM git_log
             "#
@@ -322,10 +506,10 @@ impl RepositoryQuerying {
 
         yield_rows(
             result_df.collect().await?,
-            8,
+            2 * GitLogEntry::NUM_COLUMNS,
             |columns, row_index| {
                 result.first_commit = Some(GitLogEntry::from_row(columns, row_index, 0));
-                result.last_commit = Some(GitLogEntry::from_row(columns, row_index, 4));
+                result.last_commit = Some(GitLogEntry::from_row(columns, row_index, GitLogEntry::NUM_COLUMNS));
             }
         );
 
@@ -412,7 +596,7 @@ impl RepositoryQuerying {
         let result_df = self.ctx.sql(
             r#"
             SELECT
-                revision, date, author, commit_message
+                revision, date, author, author_email, commit_message
             FROM git_log
             ORDER BY date;
             "#
@@ -510,14 +694,65 @@ impl RepositoryQuerying {
         collect_rows::<HotspotEntry>(result_df).await
     }
 
-    pub async fn module_hotspots(&self, count: Option<usize>) -> QueryingResult<Vec<HotspotEntry>> {
+    // Without a depth, the module_hotspots view is used as is
+    pub async fn module_hotspots(&self, count: Option<usize>, depth: Option<usize>) -> QueryingResult<Vec<HotspotEntry>> {
+        let Some(depth) = depth else {
+            let result_df = self.ctx.sql(
+                r#"
+                SELECT
+                    *
+                FROM module_hotspots
+                ORDER BY num_revisions DESC
+                "#
+            ).await?;
+
+            let result_df = add_optional_limit(result_df, count)?;
+            return collect_rows::<HotspotEntry>(result_df).await;
+        };
+
         let result_df = self.ctx.sql(
-            r#"
-            SELECT
-                *
-            FROM module_hotspots
-            ORDER BY num_revisions DESC;
-            "#
+            &format!(
+                r#"
+                SELECT
+                    module_entries.module_name,
+
+                    module_revisions.num_revisions,
+                    module_revisions.num_authors,
+
+                    num_code_lines,
+                    num_comment_lines,
+                    num_blank_lines,
+
+                    total_indent_levels,
+                    avg_indent_levels
+                FROM (
+                    SELECT
+                        {0} AS module_name,
+
+                        SUM(num_code_lines) AS num_code_lines,
+                        SUM(num_comment_lines) AS num_comment_lines,
+                        SUM(num_blank_lines) AS num_blank_lines,
+
+                        SUM(total_indent_levels) AS total_indent_levels,
+                        SUM(total_indent_levels)::double / SUM(num_code_lines)::double AS avg_indent_levels
+                    FROM latest_revision_module_entries
+                    GROUP BY {0}
+                ) module_entries
+                INNER JOIN (
+                    SELECT
+                        {1} AS module_name,
+                        COUNT(DISTINCT git_module_entries.revision) AS num_revisions,
+                        COUNT(DISTINCT git_log.author) AS num_authors
+                    FROM git_module_entries
+                    INNER JOIN git_log ON git_log.revision = git_module_entries.revision
+                    GROUP BY {1}
+                ) module_revisions
+                    ON module_revisions.module_name = module_entries.module_name
+                ORDER BY num_revisions DESC
+                "#,
+                module_name_at_depth("module_name", Some(depth)),
+                module_name_at_depth("git_module_entries.module_name", Some(depth))
+            )
         ).await?;
 
         let result_df = add_optional_limit(result_df, count)?;
@@ -580,49 +815,66 @@ impl RepositoryQuerying {
         ).await
     }
 
-    pub async fn module_change_couplings(&self, count: Option<usize>) -> QueryingResult<Vec<ChangeCouplingEntry>> {
+    pub async fn module_change_couplings(&self, count: Option<usize>, depth: Option<usize>) -> QueryingResult<Vec<ChangeCouplingEntry>> {
         let result_df = self.ctx.sql(
-            r#"
-            SELECT
-                left_module_name,
-                right_module_name,
-                COUNT(revision) AS coupled_revisions
-            FROM (
+            &format!(
+                r#"
                 SELECT
-                     *
-                FROM module_coupled_revisions
+                    left_module_name,
+                    right_module_name,
+                    COUNT(DISTINCT revision) AS coupled_revisions
+                FROM (
+                    SELECT
+                        revision,
+                        {} AS left_module_name,
+                        {} AS right_module_name
+                    FROM module_coupled_revisions
+                )
                 WHERE left_module_name < right_module_name
+                GROUP BY left_module_name, right_module_name
+                ORDER BY coupled_revisions DESC
+                "#,
+                module_name_at_depth("left_module_name", depth),
+                module_name_at_depth("right_module_name", depth)
             )
-            GROUP BY left_module_name, right_module_name
-            ORDER BY coupled_revisions DESC
-            "#
         ).await?;
 
         let result_df = add_optional_limit(result_df, count)?;
 
         self.create_change_coupling_results(
             result_df,
-            &self.get_num_module_revisions().await?
+            &self.get_num_module_revisions(depth).await?
         ).await
     }
 
-    pub async fn change_couplings_for_module(&self, module_name: &str, count: Option<usize>) -> QueryingResult<Vec<ChangeCouplingEntry>> {
+    pub async fn change_couplings_for_module(
+        &self,
+        module_name: &str,
+        count: Option<usize>,
+        depth: Option<usize>
+    ) -> QueryingResult<Vec<ChangeCouplingEntry>> {
         let result_df = self.ctx
             .sql(
-                r#"
-                SELECT
-                    left_module_name,
-                    right_module_name,
-                    COUNT(revision) AS coupled_revisions
-                FROM (
+                &format!(
+                    r#"
                     SELECT
-                         *
-                    FROM module_coupled_revisions
-                    WHERE left_module_name = $1
+                        left_module_name,
+                        right_module_name,
+                        COUNT(DISTINCT revision) AS coupled_revisions
+                    FROM (
+                        SELECT
+                            revision,
+                            {} AS left_module_name,
+                            {} AS right_module_name
+                        FROM module_coupled_revisions
+                    )
+                    WHERE left_module_name = $1 AND left_module_name != right_module_name
+                    GROUP BY left_module_name, right_module_name
+                    ORDER BY coupled_revisions DESC
+                    "#,
+                    module_name_at_depth("left_module_name", depth),
+                    module_name_at_depth("right_module_name", depth)
                 )
-                GROUP BY left_module_name, right_module_name
-                ORDER BY coupled_revisions DESC
-                "#
             )
             .await?
             .with_param_values(vec![ScalarValue::Utf8(Some(module_name.to_owned()))])?;
@@ -631,10 +883,175 @@ impl RepositoryQuerying {
 
         self.create_change_coupling_results(
             result_df,
-            &self.get_num_module_revisions().await?
+            &self.get_num_module_revisions(depth).await?
         ).await
     }
+
+    pub async fn file_coupling_graph(&self, min_coupled_revisions: u64, min_coupling_ratio: f64) -> QueryingResult<Graph> {
+        let change_couplings = self.file_change_couplings(None).await?;
+        let hotspots = self.file_hotspots(None).await?;
+
+        Ok(Graph::from_change_couplings("file_change_coupling", &change_couplings, &hotspots, min_coupled_revisions, min_coupling_ratio))
+    }
+
+    pub async fn module_coupling_graph(
+        &self,
+        depth: Option<usize>,
+        min_coupled_revisions: u64,
+        min_coupling_ratio: f64
+    ) -> QueryingResult<Graph> {
+        let change_couplings = self.module_change_couplings(None, depth).await?;
+        let hotspots = self.module_hotspots(None, depth).await?;
+
+        Ok(Graph::from_change_couplings("module_change_coupling", &change_couplings, &hotspots, min_coupled_revisions, min_coupling_ratio))
+    }
+
+    // Authors are connected by the files they both have changed, where the ratio is the number of shared files relative
+    // to the average number of files the authors have changed
+    pub async fn author_collaboration_graph(&self, min_shared_files: u64, min_collaboration_ratio: f64) -> QueryingResult<Graph> {
+        let result_df = self.ctx.sql(
+            r#"
+            SELECT
+                git_log.author,
+                COUNT(DISTINCT git_log.revision) AS num_revisions,
+                COUNT(DISTINCT git_file_entries.file_name) AS num_files,
+                SUM(git_file_entries.added_lines - git_file_entries.removed_lines) AS net_added_lines
+            FROM git_file_entries
+            INNER JOIN git_log ON git_log.revision = git_file_entries.revision
+            GROUP BY git_log.author
+            ORDER BY git_log.author
+            "#
+        ).await?;
+
+        let mut nodes = Vec::new();
+        let mut num_author_files = HashMap::new();
+        yield_rows(
+            result_df.collect().await?,
+            4,
+            |columns, row_index| {
+                let author = columns[0].as_string_view().value(row_index).to_owned();
+                let num_files = columns[2].as_primitive::<Int64Type>().value(row_index) as u64;
+                num_author_files.insert(author.clone(), num_files);
+
+                nodes.push(
+                    GraphNode {
+                        id: author,
+                        attributes: graph_attributes([
+                            ("num_revisions", (columns[1].as_primitive::<Int64Type>().value(row_index) as u64).into()),
+                            ("num_files", num_files.into()),
+                            ("net_added_lines", columns[3].as_primitive::<Int64Type>().value(row_index).into())
+                        ])
+                    }
+                );
+            }
+        );
+
+        let result_df = self.ctx.sql(
+            r#"
+            WITH author_files AS (
+                SELECT DISTINCT
+                    git_log.author,
+                    git_file_entries.file_name
+                FROM git_file_entries
+                INNER JOIN git_log ON git_log.revision = git_file_entries.revision
+            )
+            SELECT
+                left_files.author AS left_author,
+                right_files.author AS right_author,
+                COUNT(*) AS shared_files
+            FROM author_files AS left_files
+            INNER JOIN author_files AS right_files
+                ON left_files.file_name = right_files.file_name AND left_files.author < right_files.author
+            GROUP BY left_files.author, right_files.author
+            ORDER BY left_author, right_author
+            "#
+        ).await?;
+
+        let mut shared_files = Vec::new();
+        yield_rows(
+            result_df.collect().await?,
+            3,
+            |columns, row_index| {
+                shared_files.push(
+                    (
+                        columns[0].as_string_view().value(row_index).to_owned(),
+                        columns[1].as_string_view().value(row_index).to_owned(),
+                        columns[2].as_primitive::<Int64Type>().value(row_index) as u64
+                    )
+                );
+            }
+        );
+
+        Ok(
+            Graph {
+                name: "author_collaboration".to_owned(),
+                nodes,
+                edges: author_collaboration_edges(&num_author_files, shared_files, min_shared_files, min_collaboration_ratio)
+            }
+        )
+    }
     
+    pub async fn coupling_violations(&self) -> QueryingResult<Vec<CouplingViolationEntry>> {
+        let result_df = self.ctx.sql(
+            r#"
+            SELECT
+                module_coupled_revisions.revision,
+                left_module_name,
+                right_module_name
+            FROM module_coupled_revisions
+            INNER JOIN git_log ON git_log.revision = module_coupled_revisions.revision
+            WHERE left_module_name < right_module_name
+            ORDER BY git_log.date
+            "#
+        ).await?;
+
+        let mut coupled_revisions = BTreeMap::new();
+        yield_rows(
+            result_df.collect().await?,
+            3,
+            |columns, row_index| {
+                let revision = columns[0].as_string_view().value(row_index).to_owned();
+                let left_name = columns[1].as_string_view().value(row_index).to_owned();
+                let right_name = columns[2].as_string_view().value(row_index).to_owned();
+
+                coupled_revisions.entry((left_name, right_name)).or_insert_with(Vec::new).push(revision);
+            }
+        );
+
+        let num_revisions = self.get_num_module_revisions(None).await?;
+
+        let mut violations = Vec::new();
+        for ((left_name, right_name), revisions) in coupled_revisions {
+            if let Some(rule) = self.architecture_rules.forbidding_rule(&left_name, &right_name) {
+                // A module has at least the revisions it is coupled in
+                let coupled_revisions = revisions.len() as u64;
+                let change_coupling = ChangeCouplingEntry {
+                    coupled_revisions,
+                    num_left_revisions: num_revisions.get(&left_name).copied().unwrap_or(coupled_revisions),
+                    num_right_revisions: num_revisions.get(&right_name).copied().unwrap_or(coupled_revisions),
+                    left_name,
+                    right_name
+                };
+
+                violations.push(
+                    CouplingViolationEntry {
+                        rule: rule.definition.clone(),
+                        coupling_ratio: change_coupling.coupling_ratio(),
+                        left_name: change_coupling.left_name,
+                        right_name: change_coupling.right_name,
+                        coupled_revisions,
+                        num_left_revisions: change_coupling.num_left_revisions,
+                        num_right_revisions: change_coupling.num_right_revisions,
+                        revisions
+                    }
+                );
+            }
+        }
+
+        violations.sort_by_key(|violation| std::cmp::Reverse(violation.coupled_revisions));
+        Ok(violations)
+    }
+
     async fn create_change_coupling_results(
         &self,
         result_df: DataFrame,
@@ -653,8 +1070,8 @@ impl RepositoryQuerying {
                         left_name: left_name.clone(),
                         right_name: right_name.clone(),
                         coupled_revisions: columns[2].as_primitive::<Int64Type>().value(row_index) as u64,
-                        num_left_revisions: num_revisions.get(&left_name).unwrap().clone(),
-                        num_right_revisions: num_revisions.get(&right_name).unwrap().clone()
+                        num_left_revisions: *num_revisions.get(&left_name).unwrap(),
+                        num_right_revisions: *num_revisions.get(&right_name).unwrap()
                     }
                 );
             }
@@ -663,6 +1080,252 @@ impl RepositoryQuerying {
         Ok(change_couplings)
     }
 
+    pub async fn file_co_change_clusters(
+        &self,
+        min_support: u64,
+        max_set_size: usize,
+        count: Option<usize>
+    ) -> QueryingResult<Vec<CoChangeClusterEntry>> {
+        let result_df = self.ctx.sql(
+            r#"
+            SELECT
+                revision,
+                file_name
+            FROM coupling_file_entries
+            "#
+        ).await?;
+
+        self.create_co_change_cluster_results(result_df, min_support, max_set_size, count).await
+    }
+
+    pub async fn module_co_change_clusters(
+        &self,
+        min_support: u64,
+        max_set_size: usize,
+        count: Option<usize>
+    ) -> QueryingResult<Vec<CoChangeClusterEntry>> {
+        let result_df = self.ctx.sql(
+            r#"
+            SELECT
+                revision,
+                module_name
+            FROM coupling_module_entries
+            "#
+        ).await?;
+
+        self.create_co_change_cluster_results(result_df, min_support, max_set_size, count).await
+    }
+
+    async fn create_co_change_cluster_results(
+        &self,
+        result_df: DataFrame,
+        min_support: u64,
+        max_set_size: usize,
+        count: Option<usize>
+    ) -> QueryingResult<Vec<CoChangeClusterEntry>> {
+        let mut revisions = BTreeMap::new();
+        yield_rows(
+            result_df.collect().await?,
+            2,
+            |columns, row_index| {
+                let revision = columns[0].as_string_view().value(row_index).to_owned();
+                let name = columns[1].as_string_view().value(row_index).to_owned();
+                revisions.entry(revision).or_insert_with(Vec::new).push(name);
+            }
+        );
+
+        let transactions = revisions.into_values().collect::<Vec<_>>();
+        let clusters = closed_frequent_itemsets(&transactions, min_support, max_set_size)
+            .into_iter()
+            .map(|(names, coupled_revisions)| CoChangeClusterEntry { names, coupled_revisions })
+            .take(count.unwrap_or(usize::MAX))
+            .collect();
+
+        Ok(clusters)
+    }
+
+    pub async fn suggest_module_definition(
+        &self,
+        min_coupled_revisions: u64,
+        min_coupling_ratio: f64
+    ) -> QueryingResult<ModuleDefinitionSuggestion> {
+        let files = self.get_latest_file_names().await?;
+        let file_indices = files
+            .iter()
+            .enumerate()
+            .map(|(index, file_name)| (file_name.as_str(), index))
+            .collect::<HashMap<_, _>>();
+
+        let edges = self.file_change_couplings(None).await?
+            .into_iter()
+            .filter(|coupling| coupling.coupled_revisions >= min_coupled_revisions && coupling.coupling_ratio() >= min_coupling_ratio)
+            .map(|coupling| {
+                (
+                    file_indices[coupling.left_name.as_str()],
+                    file_indices[coupling.right_name.as_str()],
+                    coupling.coupled_revisions as f64
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let communities = louvain_communities(files.len(), &edges);
+
+        let mut groups = BTreeMap::new();
+        for (file_index, community) in communities.into_iter().enumerate() {
+            groups.entry(community).or_insert_with(Vec::new).push(files[file_index].clone());
+        }
+
+        let modules = name_modules(
+            groups
+                .into_values()
+                .filter(|files| files.len() >= 2)
+                .collect()
+        );
+
+        Ok(
+            ModuleDefinitionSuggestion {
+                definition: create_module_definition(&modules, &files),
+                modules: modules
+                    .into_iter()
+                    .map(|(name, files)| SuggestedModule { name, files })
+                    .collect()
+            }
+        )
+    }
+
+    pub async fn compare_module_definition(&self, definition: &str) -> QueryingResult<ModuleDefinitionComparison> {
+        let module_definitions = ModuleDefinitions::new(&module_definition_file_name(DEFAULT_MODULE_VIEW)?, definition)?;
+
+        let result_df = self.ctx.sql(
+            r#"
+            SELECT
+                file_name,
+                extract_module_name(file_name) AS module_name
+            FROM latest_revision_file_entries
+            ORDER BY file_name
+            "#
+        ).await?;
+
+        let mut comparison = ModuleDefinitionComparison {
+            num_files: 0,
+            num_changed_files: 0,
+            files: Vec::new()
+        };
+
+        yield_rows(
+            result_df.collect().await?,
+            2,
+            |columns, row_index| {
+                let file_name = columns[0].as_string_view().value(row_index).to_owned();
+                let current_module = columns[1].as_string_view().value(row_index).to_owned();
+                let suggested_module = module_definitions
+                    .get_module(&file_name)
+                    .unwrap_or_else(|| default_module_name(&file_name));
+
+                comparison.num_files += 1;
+                if current_module != suggested_module {
+                    comparison.num_changed_files += 1;
+                }
+
+                comparison.files.push(ModuleAssignmentEntry { file_name, current_module, suggested_module });
+            }
+        );
+
+        Ok(comparison)
+    }
+
+    // Validates the definition against the current files, where a file is shadowed if more than one rule matches it
+    pub async fn validate_module_definition(&self, view: &str, definition: &str) -> QueryingResult<ModuleDefinitionValidation> {
+        let module_definitions = ModuleDefinitions::new(&module_definition_file_name(view)?, definition)?;
+        let rules = module_definitions.rules();
+        let files = self.get_latest_file_names().await?;
+
+        let mut validation = ModuleDefinitionValidation {
+            num_files: files.len() as u64,
+            num_matched_files: 0,
+            unmatched_files: Vec::new(),
+            unused_rules: Vec::new(),
+            shadowed_files: Vec::new()
+        };
+
+        let rule_entry = |rule: &ModuleRule| ModuleRuleEntry { line_number: rule.line_number, definition: rule.definition.clone() };
+
+        let mut used_rules = vec![false; rules.len()];
+        for file_name in files {
+            let Some(module_name) = module_definitions.get_module(&file_name) else {
+                validation.unmatched_files.push(UnmatchedFileEntry { default_module: default_module_name(&file_name), file_name });
+                continue;
+            };
+
+            validation.num_matched_files += 1;
+
+            let mut matching_rules = rules
+                .iter()
+                .enumerate()
+                .filter_map(|(rule_index, rule)| Some((rule_index, rule, rule.get_module(&file_name)?)));
+
+            let (rule_index, rule, _) = matching_rules.next().unwrap();
+            used_rules[rule_index] = true;
+
+            let mut shadowed_rules = Vec::new();
+            for (rule_index, shadowed_rule, shadowed_module_name) in matching_rules {
+                used_rules[rule_index] = true;
+                shadowed_rules.push(ShadowedRuleEntry { rule: rule_entry(shadowed_rule), module_name: shadowed_module_name });
+            }
+
+            if !shadowed_rules.is_empty() {
+                validation.shadowed_files.push(
+                    ShadowedFileEntry {
+                        file_name,
+                        module_name,
+                        rule: rule_entry(rule),
+                        shadowed_rules
+                    }
+                );
+            }
+        }
+
+        validation.unused_rules = rules
+            .iter()
+            .zip(used_rules)
+            .filter(|(_, used)| !used)
+            .map(|(rule, _)| rule_entry(rule))
+            .collect();
+
+        Ok(validation)
+    }
+
+    pub async fn suggest_author_aliases(&self) -> QueryingResult<Vec<AuthorAliasSuggestion>> {
+        let result_df = self.ctx.sql(
+            r#"
+            SELECT
+                author,
+                author_email,
+                COUNT(*) AS num_revisions
+            FROM git_log
+            GROUP BY author, author_email
+            ORDER BY author, author_email
+            "#
+        ).await?;
+
+        let mut identities = Vec::new();
+        yield_rows(
+            result_df.collect().await?,
+            3,
+            |columns, row_index| {
+                identities.push(
+                    AuthorIdentity {
+                        name: columns[0].as_string_view().value(row_index).to_owned(),
+                        email: columns[1].as_string_view().value(row_index).to_owned(),
+                        num_revisions: columns[2].as_primitive::<Int64Type>().value(row_index) as u64
+                    }
+                );
+            }
+        );
+
+        Ok(suggest_author_aliases(&identities))
+    }
+
     pub async fn file_sum_of_couplings(&self, count: Option<usize>) -> QueryingResult<Vec<SumOfCouplingEntry>> {
         let result_df = self.ctx.sql(
             r#"
@@ -739,70 +1402,248 @@ impl RepositoryQuerying {
         collect_rows::<MainDeveloperEntry>(result_df).await
     }
 
-    pub async fn modules_main_developer(&self) -> QueryingResult<Vec<MainDeveloperEntry>> {
+    pub async fn modules_main_developer(&self, depth: Option<usize>) -> QueryingResult<Vec<MainDeveloperEntry>> {
         let result_df = self.ctx
             .sql(
-                r#"
-                SELECT
-                    module_name,
-                    SUM(net_added_lines) AS total_net_added_lines,
-                    LAST_VALUE(author ORDER BY net_added_lines, author) AS main_developer,
-                    LAST_VALUE(net_added_lines ORDER BY net_added_lines, author) AS main_developer_net_added_lines
-                FROM module_developers
-                GROUP BY module_name
-                ORDER BY ratio(main_developer_net_added_lines, total_net_added_lines) DESC, total_net_added_lines DESC
-                "#
+                &format!(
+                    r#"
+                    SELECT
+                        module_name,
+                        SUM(net_added_lines) AS total_net_added_lines,
+                        LAST_VALUE(author ORDER BY net_added_lines, author) AS main_developer,
+                        LAST_VALUE(net_added_lines ORDER BY net_added_lines, author) AS main_developer_net_added_lines
+                    FROM (
+                        SELECT
+                            {0} AS module_name,
+                            author,
+                            SUM(net_added_lines) AS net_added_lines
+                        FROM module_developers
+                        GROUP BY {0}, author
+                    )
+                    GROUP BY module_name
+                    ORDER BY ratio(main_developer_net_added_lines, total_net_added_lines) DESC, total_net_added_lines DESC
+                    "#,
+                    module_name_at_depth("module_name", depth)
+                )
             )
             .await?;
 
         collect_rows::<MainDeveloperEntry>(result_df).await
     }
 
-    pub async fn commit_spread(&self) -> QueryingResult<Vec<CommitSpreadEntry>> {
+    pub async fn modules_truck_factor(&self, depth: Option<usize>) -> QueryingResult<Vec<TruckFactorEntry>> {
+        let result_df = self.ctx
+            .sql(
+                &format!(
+                    r#"
+                    SELECT
+                        {0} AS module_name,
+                        author,
+                        SUM(net_added_lines) AS net_added_lines
+                    FROM module_developers
+                    GROUP BY {0}, author
+                    ORDER BY module_name, net_added_lines DESC, author
+                    "#,
+                    module_name_at_depth("module_name", depth)
+                )
+            )
+            .await?;
+
+        let mut module_developers = BTreeMap::<String, Vec<i64>>::new();
+        yield_rows(
+            result_df.collect().await?,
+            3,
+            |columns, row_index| {
+                let module_name = columns[0].as_string_view().value(row_index).to_owned();
+                let net_added_lines = columns[2].as_primitive::<Int64Type>().value(row_index);
+                module_developers.entry(module_name).or_default().push(net_added_lines);
+            }
+        );
+
+        Ok(
+            module_developers
+                .into_iter()
+                .map(|(name, net_added_lines)| {
+                    TruckFactorEntry {
+                        name,
+                        truck_factor: truck_factor(&net_added_lines),
+                        num_authors: net_added_lines.len() as u64
+                    }
+                })
+                .collect()
+        )
+    }
+
+    pub async fn commit_spread(&self, depth: Option<usize>) -> QueryingResult<Vec<CommitSpreadEntry>> {
+        let result_df = self.ctx
+            .sql(
+                &format!(
+                    r#"
+                    SELECT
+                        module_name,
+                        author,
+                        COUNT(DISTINCT revision) AS num_revisions
+                    FROM (
+                        SELECT
+                            {} AS module_name,
+                            author,
+                            git_module_entries.revision
+                        FROM git_module_entries
+                        INNER JOIN git_log ON git_log.revision = git_module_entries.revision
+                    )
+                    GROUP BY module_name, author
+                    ORDER BY module_name, num_revisions DESC
+                    "#,
+                    module_name_at_depth("module_name", depth)
+                )
+            )
+            .await?;
+
+        collect_rows::<CommitSpreadEntry>(result_df).await
+    }
+
+    // Oldest files first
+    pub async fn file_code_age(&self, count: Option<usize>) -> QueryingResult<Vec<CodeAgeEntry>> {
         let result_df = self.ctx
             .sql(
                 r#"
                 SELECT
-                    module_name,
-                    author,
-                    COUNT(git_module_entries.revision) AS num_revisions
-                FROM git_module_entries
-                INNER JOIN git_log ON git_log.revision = git_module_entries.revision
-                GROUP BY module_name, author
-                ORDER BY module_name, num_revisions DESC
+                    file_name,
+                    last_changed_date,
+                    (head_date - last_changed_date) / 86400 AS age_days,
+                    num_code_lines
+                FROM (
+                    SELECT
+                        file_name,
+                        MAX(date) AS last_changed_date,
+                        LAST_VALUE(num_code_lines ORDER BY date) AS num_code_lines
+                    FROM git_file_entries
+                    GROUP BY file_name
+                )
+                CROSS JOIN (
+                    SELECT
+                        MAX(date) AS head_date
+                    FROM git_log
+                )
+                ORDER BY last_changed_date ASC, file_name
                 "#
             )
             .await?;
 
-        collect_rows::<CommitSpreadEntry>(result_df).await
+        let result_df = add_optional_limit(result_df, count)?;
+
+        collect_rows::<CodeAgeEntry>(result_df).await
+    }
+
+    // The tables, views and custom functions available when writing custom analysis
+    pub async fn schema(&self) -> QueryingResult<QuerySchema> {
+        let mut tables = Vec::new();
+
+        let catalog_schema = self.ctx.catalog("datafusion")
+            .and_then(|catalog| catalog.schema("public"));
+
+        if let Some(catalog_schema) = catalog_schema {
+            let mut table_names = catalog_schema.table_names();
+            table_names.sort();
+
+            for table_name in table_names {
+                let Some(table) = catalog_schema.table(&table_name).await? else {
+                    continue;
+                };
+
+                tables.push(
+                    TableSchema {
+                        name: table_name,
+                        is_view: table.table_type() == TableType::View,
+                        columns: table.schema()
+                            .fields()
+                            .iter()
+                            .map(|field| {
+                                ColumnSchema {
+                                    name: field.name().to_owned(),
+                                    data_type: field.data_type().to_string(),
+                                    nullable: field.is_nullable()
+                                }
+                            })
+                            .collect()
+                    }
+                );
+            }
+        }
+
+        // Only the functions registered on top of the built-in ones
+        let builtin_functions = SessionContext::new().state().scalar_functions().keys().cloned().collect::<HashSet<_>>();
+
+        let state = self.ctx.state();
+        let mut functions = state.scalar_functions()
+            .iter()
+            .filter(|(name, _)| !builtin_functions.contains(*name))
+            .map(|(name, function)| {
+                let signatures = function.signature().type_signature.get_example_types()
+                    .into_iter()
+                    .map(|argument_types| {
+                        let return_type = function.return_type(&argument_types)
+                            .map(|return_type| return_type.to_string())
+                            .unwrap_or_else(|_| "?".to_owned());
+
+                        format!(
+                            "{}({}) -> {}",
+                            name,
+                            argument_types.iter().map(|argument_type| argument_type.to_string()).collect::<Vec<_>>().join(", "),
+                            return_type
+                        )
+                    })
+                    .collect();
+
+                FunctionSchema { name: name.clone(), signatures }
+            })
+            .collect::<Vec<_>>();
+        functions.sort_by(|left, right| left.name.cmp(&right.name));
+
+        Ok(QuerySchema { tables, functions })
     }
 
-    pub async fn custom_analysis(&self, sql: &str) -> QueryingResult<CustomAnalysis> {
-        let result_df = self.ctx.sql(sql).await?;
+    // Checks that the query can be planned without running it
+    pub async fn validate_query(&self, sql: &str) -> QueryingResult<()> {
+        self.ctx.state().create_logical_plan(sql).await?;
+        Ok(())
+    }
 
-        let mut columns = Vec::new();
-        let mut rows = Vec::new();
-        let mut has_definition = false;
+    // Runs the query in a read-only context of its own, such that it can't change the views used by the other analyses
+    pub async fn custom_analysis(
+        &self,
+        sql: &str,
+        parameters: &QueryParameters,
+        limits: &CustomAnalysisLimits
+    ) -> QueryingResult<CustomAnalysis> {
+        let ctx = self.create_custom_analysis_context(limits).await?;
+
+        let run_query = async {
+            let mut result_df = create_read_only_dataframe(&ctx, sql, parameters).await?;
+            if let Some(max_rows) = limits.max_rows {
+                result_df = result_df.limit(0, Some(max_rows + 1))?;
+            }
 
-        for batch in result_df.collect().await? {
-            for record_index in 0..batch.column(0).len() {
-                let mut row = Vec::new();
+            let columns = result_df.schema().fields().iter().map(|field| field.name().to_owned()).collect::<Vec<_>>();
+            let batches = result_df.collect().await.map_err(execution_error)?;
+            Ok((columns, batches))
+        };
 
-                for (column_index, column_def) in batch.schema().fields().iter().enumerate() {
-                    let column = batch.column(column_index);
+        let (columns, batches) = with_timeout(limits, run_query).await?;
 
-                    if let Some(value) = CustomValue::from_column(column_def, column, record_index) {
-                        row.push(value);
+        let num_rows = batches.iter().map(|batch| batch.num_rows()).sum::<usize>();
+        if let Some(max_rows) = limits.max_rows.filter(|max_rows| num_rows > *max_rows) {
+            return Err(QueryingError::QueryMaxRowsExceeded(max_rows));
+        }
 
-                        if !has_definition {
-                            columns.push(column_def.name().to_owned());
-                        }
-                    } else {
-                        warn!("Unsupported type '{}'", column.data_type())
-                    }
+        let mut rows = Vec::new();
+        for batch in batches {
+            for record_index in 0..batch.num_rows() {
+                let mut row = Vec::new();
+                for (column_index, column_def) in batch.schema().fields().iter().enumerate() {
+                    row.push(CustomValue::from_column(column_def, batch.column(column_index), record_index)?);
                 }
 
-                has_definition = true;
                 rows.push(row);
             }
         }
@@ -815,6 +1656,116 @@ impl RepositoryQuerying {
         )
     }
 
+    // Runs the query as a stream of record batches (such as when exporting large results), where the result is truncated
+    // at the max rows
+    pub async fn custom_analysis_stream(
+        &self,
+        sql: &str,
+        parameters: &QueryParameters,
+        limits: &CustomAnalysisLimits
+    ) -> QueryingResult<SendableRecordBatchStream> {
+        let ctx = self.create_custom_analysis_context(limits).await?;
+        let mut result_df = create_read_only_dataframe(&ctx, sql, parameters).await?;
+        if let Some(max_rows) = limits.max_rows {
+            result_df = result_df.limit(0, Some(max_rows))?;
+        }
+
+        result_df.execute_stream().await.map_err(execution_error)
+    }
+
+    // Returns the logical and physical plan of the query, where analyzing runs the query to get the metrics of each operator
+    pub async fn explain_custom_analysis(
+        &self,
+        sql: &str,
+        parameters: &QueryParameters,
+        analyze: bool,
+        limits: &CustomAnalysisLimits
+    ) -> QueryingResult<QueryPlan> {
+        let ctx = self.create_custom_analysis_context(limits).await?;
+
+        let result_df = create_read_only_dataframe(&ctx, sql, parameters).await?;
+        let logical_plan = result_df.clone().into_optimized_plan()?.display_indent().to_string();
+        let physical_plan = result_df.create_physical_plan().await?;
+
+        if !analyze {
+            let physical_plan = displayable(physical_plan.as_ref()).indent(true).to_string();
+            return Ok(
+                QueryPlan {
+                    logical_plan,
+                    physical_plan,
+                    operators: None
+                }
+            );
+        }
+
+        // The batches are dropped as they are produced, since only the metrics are needed
+        let run_query = async {
+            let mut stream = execute_stream(physical_plan.clone(), ctx.task_ctx()).map_err(execution_error)?;
+            while let Some(batch) = stream.next().await {
+                batch.map_err(execution_error)?;
+            }
+
+            Ok(())
+        };
+        with_timeout(limits, run_query).await?;
+
+        let operators = QueryPlanOperator::from_execution_plan(physical_plan.as_ref());
+        let physical_plan = DisplayableExecutionPlan::with_metrics(physical_plan.as_ref()).indent(true).to_string();
+        Ok(
+            QueryPlan {
+                logical_plan,
+                physical_plan,
+                operators: Some(operators)
+            }
+        )
+    }
+
+    // Creates a context sharing the tables, views and functions with the main context, but with its own memory pool
+    async fn create_custom_analysis_context(&self, limits: &CustomAnalysisLimits) -> QueryingResult<SessionContext> {
+        let mut runtime_env = RuntimeEnvBuilder::new();
+        if let Some(memory_limit) = limits.memory_limit {
+            runtime_env = runtime_env.with_memory_limit(memory_limit, 1.0);
+        }
+
+        let ctx = SessionContext::new_with_config_rt(SessionConfig::new(), runtime_env.build_arc()?);
+
+        if let Some(catalog_schema) = self.ctx.catalog("datafusion").and_then(|catalog| catalog.schema("public")) {
+            for table_name in catalog_schema.table_names() {
+                if let Some(table) = catalog_schema.table(&table_name).await? {
+                    ctx.register_table(table_name.as_str(), table)?;
+                }
+            }
+        }
+
+        for function in self.ctx.state().scalar_functions().values() {
+            ctx.register_udf(function.as_ref().clone());
+        }
+
+        Ok(ctx)
+    }
+
+    async fn get_latest_file_names(&self) -> QueryingResult<Vec<String>> {
+        let result_df = self.ctx.sql(
+            r#"
+            SELECT
+                file_name
+            FROM latest_revision_file_entries
+            ORDER BY file_name
+            "#
+        ).await?;
+
+        let mut file_names = Vec::new();
+        yield_rows(
+            result_df.collect().await?,
+            1,
+            |columns, row_index| {
+                file_names.push(columns[0].as_string_view().value(row_index).to_owned());
+            }
+        );
+
+        Ok(file_names)
+    }
+
     async fn get_num_file_revisions(&self) -> QueryingResult<HashMap<String, u64>> {
         let result_df = self.ctx.sql(
             r#"
@@ -829,8 +1780,25 @@ impl RepositoryQuerying {
         self.create_num_revisions_results(result_df).await
     }
 
-    async fn get_num_module_revisions(&self) -> QueryingResult<HashMap<String, u64>> {
-        let result_df = self.ctx.sql(r#"SELECT * FROM num_module_revisions"#).await?;
+    async fn get_num_module_revisions(&self, depth: Option<usize>) -> QueryingResult<HashMap<String, u64>> {
+        let result_df = self.ctx.sql(
+            &format!(
+                r#"
+                SELECT
+                    module_name,
+                    COUNT(DISTINCT revision) AS num_revisions
+                FROM (
+                    SELECT
+                        {} AS module_name,
+                        revision
+                    FROM git_module_entries
+                )
+                GROUP BY module_name
+                "#,
+                module_name_at_depth("module_name", depth)
+            )
+        ).await?;
+
         self.create_num_revisions_results(result_df).await
     }
 
@@ -848,4 +1816,179 @@ impl RepositoryQuerying {
 
         Ok(num_revisions_results)
     }
-}
\ No newline at end of file
+}
+
+fn module_name_at_depth(column: &str, depth: Option<usize>) -> String {
+    match depth {
+        Some(depth) => format!("truncate_module_name({}, {})", column, depth),
+        None => column.to_owned()
+    }
+}
+
+fn create_coupling_file_entries_view(config: &RepositoryQueryingConfig) -> QueryingResult<String> {
+    fn quote(value: &str) -> String {
+        format!("'{}'", value.replace('\'', "''"))
+    }
+
+    let mut conditions = Vec::new();
+
+    if let Some(max_files_per_commit) = config.coupling_max_files_per_commit {
+        conditions.push(
+            format!(
+                r#"
+                revision IN (
+                    SELECT
+                        revision
+                    FROM all_git_file_entries
+                    GROUP BY revision
+                    HAVING COUNT(*) <= {}
+                )
+                "#,
+                max_files_per_commit
+            )
+        );
+    }
+
+    for revision in &config.coupling_excluded_revisions {
+        // Shorter prefixes (such as an empty one) would exclude unrelated commits, and are skipped if persisted anyway
+        let revision = revision.trim();
+        if revision.len() < MIN_EXCLUDED_REVISION_LENGTH {
+            continue;
+        }
+
+        let revision = quote(revision);
+        conditions.push(format!("NOT (starts_with(revision, {0}) OR starts_with({0}, revision))", revision));
+    }
+
+    if !config.coupling_excluded_commit_message_patterns.is_empty() {
+        let mut message_conditions = Vec::new();
+        for pattern in &config.coupling_excluded_commit_message_patterns {
+            Regex::new(pattern).map_err(QueryingError::CommitMessagePattern)?;
+            message_conditions.push(format!("regexp_like(commit_message, {})", quote(pattern)));
+        }
+
+        conditions.push(
+            format!(
+                r#"
+                revision NOT IN (
+                    SELECT
+                        revision
+                    FROM raw_git_log
+                    WHERE {}
+                )
+                "#,
+                message_conditions.join(" OR ")
+            )
+        );
+    }
+
+    if conditions.is_empty() {
+        conditions.push("TRUE".to_owned());
+    }
+
+    Ok(
+        format!(
+            r#"
+            CREATE VIEW coupling_file_entries AS
+            SELECT
+                *
+            FROM git_file_entries
+            WHERE
+                {}
+            "#,
+            conditions.join(" AND ")
+        )
+    )
+}
+
+// Plans the query, where only queries that can't change the context are allowed
+async fn create_read_only_dataframe(
+    ctx: &SessionContext,
+    sql: &str,
+    parameters: &QueryParameters
+) -> QueryingResult<DataFrame> {
+    let logical_plan = ctx.state().create_logical_plan(sql).await?;
+    SQLOptions::new()
+        .with_allow_ddl(false)
+        .with_allow_dml(false)
+        .with_allow_statements(false)
+        .verify_plan(&logical_plan)
+        .map_err(|err| QueryingError::QueryNotReadOnly(err.strip_backtrace()))?;
+
+    bind_parameters(ctx.execute_logical_plan(logical_plan).await?, parameters)
+}
+
+// The edges between the authors sharing enough files, where the ratio is the shared files relative to the average number
+// of files changed by the two authors
+pub fn author_collaboration_edges(
+    num_author_files: &HashMap<String, u64>,
+    shared_files: Vec<(String, String, u64)>,
+    min_shared_files: u64,
+    min_collaboration_ratio: f64
+) -> Vec<GraphEdge> {
+    let mut edges = Vec::new();
+    for (left_author, right_author, shared_files) in shared_files {
+        let num_files = |author: &str| num_author_files.get(author).copied().unwrap_or(shared_files);
+        let average_files = (num_files(&left_author) + num_files(&right_author)) as f64 / 2.0;
+        let collaboration_ratio = shared_files as f64 / average_files;
+
+        if shared_files >= min_shared_files && collaboration_ratio >= min_collaboration_ratio {
+            edges.push(
+                GraphEdge {
+                    source: left_author,
+                    target: right_author,
+                    attributes: graph_attributes([
+                        ("shared_files", shared_files.into()),
+                        ("collaboration_ratio", collaboration_ratio.into())
+                    ])
+                }
+            );
+        }
+    }
+
+    edges
+}
+
+// The number of authors (in descending order of net added lines) needed to cover more than half of the net added lines
+pub fn truck_factor(net_added_lines: &[i64]) -> u64 {
+    // Authors that have removed more lines than they have added have not written any of the remaining lines
+    let net_added_lines = net_added_lines.iter().map(|lines| (*lines).max(0)).collect::<Vec<_>>();
+    let total_net_added_lines = net_added_lines.iter().sum::<i64>();
+    if total_net_added_lines == 0 {
+        // No lines remain that anyone could be the owner of
+        return 0;
+    }
+
+    let mut truck_factor = 0;
+    let mut covered_net_added_lines = 0;
+    for author_net_added_lines in net_added_lines {
+        if 2 * covered_net_added_lines > total_net_added_lines {
+            break;
+        }
+
+        covered_net_added_lines += author_net_added_lines;
+        truck_factor += 1;
+    }
+
+    truck_factor
+}
+
+async fn with_timeout<T>(
+    limits: &CustomAnalysisLimits,
+    future: impl Future<Output=QueryingResult<T>>
+) -> QueryingResult<T> {
+    match limits.timeout {
+        Some(timeout) => {
+            tokio::time::timeout(timeout, future).await
+                .map_err(|_| QueryingError::QueryTimeout(timeout.as_secs_f64()))?
+        }
+        None => future.await
+    }
+}
+
+fn execution_error(err: DataFusionError) -> QueryingError {
+    match err.find_root() {
+        DataFusionError::ResourcesExhausted(message) => QueryingError::QueryMemoryLimitExceeded(message.clone()),
+        _ => QueryingError::DataFusion(err)
+    }
+}
@@ -0,0 +1,359 @@
+use std::io::Write;
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use datafusion::arrow::array::RecordBatch;
+use datafusion::arrow::csv;
+use datafusion::arrow::datatypes::{DataType, Field, Fields, Schema, SchemaRef};
+use datafusion::arrow::json::reader::Decoder;
+use datafusion::arrow::json::{LineDelimitedWriter, ReaderBuilder};
+use datafusion::common::DataFusionError;
+use datafusion::parquet::arrow::ArrowWriter;
+
+use crate::indexing::indexer::GitLogEntry;
+use crate::querying::model::{ChangeCouplingEntry, CoChangeClusterEntry, CodeAgeEntry, CommitSpreadEntry, CouplingViolationEntry, FileEntry, FileHistoryEntry, HotspotEntry, MainDeveloperEntry, Module, SumOfCouplingEntry, TruckFactorEntry};
+use crate::querying::{QueryingError, QueryingResult};
+
+const EXPORT_BATCH_SIZE: usize = 8192;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Csv,
+    JsonLines,
+    Parquet
+}
+
+impl ExportFormat {
+    pub fn from_name(name: &str) -> Option<ExportFormat> {
+        match name {
+            "csv" => Some(ExportFormat::Csv),
+            "jsonl" => Some(ExportFormat::JsonLines),
+            "parquet" => Some(ExportFormat::Parquet),
+            _ => None
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::JsonLines => "application/x-ndjson",
+            ExportFormat::Parquet => "application/vnd.apache.parquet"
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::JsonLines => "jsonl",
+            ExportFormat::Parquet => "parquet"
+        }
+    }
+}
+
+// A row of an exported analysis, where the fields give the columns of the export (also when there are no rows)
+pub trait ExportRow: Serialize {
+    fn fields() -> Fields;
+}
+
+// Converts the rows of an analysis to record batches as they are consumed, where the columns are the fields of the row
+// type. Nested values (such as the files of a co-change cluster) are given as JSON strings in CSV, which has no nested
+// types.
+pub fn rows_to_record_batches<T: ExportRow>(
+    rows: Vec<T>,
+    format: ExportFormat
+) -> QueryingResult<(SchemaRef, impl Iterator<Item=QueryingResult<RecordBatch>>)> {
+    let schema = row_schema::<T>(format);
+    let mut decoder = ReaderBuilder::new(schema.clone()).with_batch_size(EXPORT_BATCH_SIZE).build_decoder()?;
+
+    let mut rows = rows.into_iter();
+    let batches = std::iter::from_fn(move || {
+        let chunk = rows.by_ref().take(EXPORT_BATCH_SIZE).collect::<Vec<_>>();
+        if chunk.is_empty() {
+            return None;
+        }
+
+        rows_to_record_batch(&mut decoder, &chunk, format).transpose()
+    });
+
+    Ok((schema, batches))
+}
+
+fn rows_to_record_batch<T: Serialize>(
+    decoder: &mut Decoder,
+    rows: &[T],
+    format: ExportFormat
+) -> QueryingResult<Option<RecordBatch>> {
+    if format == ExportFormat::Csv {
+        let mut values = Vec::with_capacity(rows.len());
+        for row in rows {
+            let mut value = serde_json::to_value(row).map_err(|err| QueryingError::Export(err.to_string()))?;
+            if let serde_json::Value::Object(fields) = &mut value {
+                for field in fields.values_mut() {
+                    if field.is_array() || field.is_object() {
+                        *field = serde_json::Value::String(field.to_string());
+                    }
+                }
+            }
+
+            values.push(value);
+        }
+
+        decoder.serialize(&values)?;
+    } else {
+        decoder.serialize(rows)?;
+    }
+
+    Ok(decoder.flush()?)
+}
+
+fn row_schema<T: ExportRow>(format: ExportFormat) -> SchemaRef {
+    let fields = T::fields();
+    if format != ExportFormat::Csv {
+        return Arc::new(Schema::new(fields));
+    }
+
+    let fields = fields
+        .iter()
+        .map(|field| {
+            if field.data_type().is_nested() {
+                Arc::new(field.as_ref().clone().with_data_type(DataType::Utf8))
+            } else {
+                field.clone()
+            }
+        })
+        .collect::<Fields>();
+
+    Arc::new(Schema::new(fields))
+}
+
+fn string_field(name: &str) -> Field {
+    Field::new(name, DataType::Utf8, false)
+}
+
+fn u64_field(name: &str) -> Field {
+    Field::new(name, DataType::UInt64, false)
+}
+
+fn i64_field(name: &str) -> Field {
+    Field::new(name, DataType::Int64, false)
+}
+
+fn f64_field(name: &str) -> Field {
+    Field::new(name, DataType::Float64, false)
+}
+
+fn list_field(name: &str, data_type: DataType) -> Field {
+    Field::new_list(name, Field::new_list_field(data_type, false), false)
+}
+
+impl ExportRow for GitLogEntry {
+    fn fields() -> Fields {
+        Fields::from(vec![
+            string_field("revision"),
+            i64_field("date"),
+            string_field("author"),
+            string_field("author_email"),
+            string_field("commit_message")
+        ])
+    }
+}
+
+impl ExportRow for FileEntry {
+    fn fields() -> Fields {
+        Fields::from(vec![
+            string_field("name"),
+            u64_field("num_code_lines"),
+            u64_field("num_comment_lines"),
+            u64_field("num_blank_lines"),
+            u64_field("total_indent_levels"),
+            f64_field("avg_indent_levels"),
+            f64_field("std_indent_levels")
+        ])
+    }
+}
+
+impl ExportRow for Module {
+    fn fields() -> Fields {
+        Fields::from(vec![
+            string_field("name"),
+            list_field("files", DataType::Struct(FileEntry::fields()))
+        ])
+    }
+}
+
+impl ExportRow for FileHistoryEntry {
+    fn fields() -> Fields {
+        Fields::from(vec![
+            string_field("name"),
+            string_field("revision"),
+            i64_field("date"),
+            u64_field("num_code_lines"),
+            u64_field("num_comment_lines"),
+            u64_field("num_blank_lines"),
+            u64_field("total_indent_levels"),
+            f64_field("avg_indent_levels"),
+            f64_field("std_indent_level")
+        ])
+    }
+}
+
+impl ExportRow for HotspotEntry {
+    fn fields() -> Fields {
+        Fields::from(vec![
+            string_field("name"),
+            u64_field("num_revisions"),
+            u64_field("num_authors"),
+            u64_field("num_code_lines"),
+            u64_field("num_comment_lines"),
+            u64_field("num_blank_lines"),
+            u64_field("total_indent_levels"),
+            f64_field("avg_indent_levels")
+        ])
+    }
+}
+
+impl ExportRow for ChangeCouplingEntry {
+    fn fields() -> Fields {
+        Fields::from(vec![
+            string_field("left_name"),
+            string_field("right_name"),
+            u64_field("coupled_revisions"),
+            u64_field("num_left_revisions"),
+            u64_field("num_right_revisions")
+        ])
+    }
+}
+
+impl ExportRow for CouplingViolationEntry {
+    fn fields() -> Fields {
+        Fields::from(vec![
+            string_field("rule"),
+            string_field("left_name"),
+            string_field("right_name"),
+            u64_field("coupled_revisions"),
+            u64_field("num_left_revisions"),
+            u64_field("num_right_revisions"),
+            f64_field("coupling_ratio"),
+            list_field("revisions", DataType::Utf8)
+        ])
+    }
+}
+
+impl ExportRow for CoChangeClusterEntry {
+    fn fields() -> Fields {
+        Fields::from(vec![
+            list_field("names", DataType::Utf8),
+            u64_field("coupled_revisions")
+        ])
+    }
+}
+
+impl ExportRow for SumOfCouplingEntry {
+    fn fields() -> Fields {
+        Fields::from(vec![
+            string_field("name"),
+            u64_field("sum_of_couplings")
+        ])
+    }
+}
+
+impl ExportRow for MainDeveloperEntry {
+    fn fields() -> Fields {
+        Fields::from(vec![
+            string_field("name"),
+            string_field("main_developer"),
+            i64_field("net_added_lines"),
+            i64_field("total_net_added_lines")
+        ])
+    }
+}
+
+impl ExportRow for CommitSpreadEntry {
+    fn fields() -> Fields {
+        Fields::from(vec![
+            string_field("module_name"),
+            string_field("author"),
+            u64_field("num_revisions")
+        ])
+    }
+}
+
+impl ExportRow for TruckFactorEntry {
+    fn fields() -> Fields {
+        Fields::from(vec![
+            string_field("name"),
+            u64_field("truck_factor"),
+            u64_field("num_authors")
+        ])
+    }
+}
+
+impl ExportRow for CodeAgeEntry {
+    fn fields() -> Fields {
+        Fields::from(vec![
+            string_field("name"),
+            i64_field("last_changed_date"),
+            u64_field("age_days"),
+            u64_field("num_code_lines")
+        ])
+    }
+}
+
+// Writes record batches in the export format, where the output can be consumed after each batch (except for the
+// footer of Parquet files that is written when finishing)
+pub enum RecordBatchWriter<W: Write + Send> {
+    Csv(Box<csv::Writer<W>>),
+    JsonLines(LineDelimitedWriter<W>),
+    Parquet(Box<ArrowWriter<W>>)
+}
+
+impl<W: Write + Send> RecordBatchWriter<W> {
+    pub fn new(format: ExportFormat, schema: SchemaRef, output: W) -> QueryingResult<RecordBatchWriter<W>> {
+        match format {
+            ExportFormat::Csv => {
+                if let Some(field) = schema.fields().iter().find(|field| field.data_type().is_nested()) {
+                    return Err(QueryingError::UnsupportedColumnType(field.name().to_owned(), format!("{} in CSV", field.data_type())));
+                }
+
+                // The header is written with the first batch, so an empty batch makes sure that it is written even without rows
+                let mut writer = csv::WriterBuilder::new().with_header(true).build(output);
+                writer.write(&RecordBatch::new_empty(schema))?;
+                Ok(RecordBatchWriter::Csv(Box::new(writer)))
+            }
+            ExportFormat::JsonLines => {
+                Ok(RecordBatchWriter::JsonLines(LineDelimitedWriter::new(output)))
+            }
+            ExportFormat::Parquet => {
+                let writer = ArrowWriter::try_new(output, schema, None).map_err(DataFusionError::from)?;
+                Ok(RecordBatchWriter::Parquet(Box::new(writer)))
+            }
+        }
+    }
+
+    pub fn write(&mut self, batch: &RecordBatch) -> QueryingResult<()> {
+        match self {
+            RecordBatchWriter::Csv(writer) => writer.write(batch)?,
+            RecordBatchWriter::JsonLines(writer) => writer.write(batch)?,
+            RecordBatchWriter::Parquet(writer) => writer.write(batch).map_err(DataFusionError::from)?
+        }
+
+        Ok(())
+    }
+
+    pub fn finish(self) -> QueryingResult<()> {
+        match self {
+            RecordBatchWriter::Csv(writer) => {
+                writer.into_inner().flush().map_err(|err| QueryingError::Export(err.to_string()))?;
+            }
+            RecordBatchWriter::JsonLines(mut writer) => {
+                writer.finish()?;
+            }
+            RecordBatchWriter::Parquet(writer) => {
+                writer.close().map_err(DataFusionError::from)?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,165 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+type ItemSet = Vec<usize>;
+
+// Uses the Apriori algorithm, only returning closed sets (no superset with the same support) of at least two items
+pub fn closed_frequent_itemsets(
+    transactions: &[Vec<String>],
+    min_support: u64,
+    max_set_size: usize
+) -> Vec<(Vec<String>, u64)> {
+    let mut item_ids = HashMap::new();
+    let mut items = Vec::new();
+    let mut encoded_transactions = Vec::new();
+    for transaction in transactions {
+        let mut encoded = transaction
+            .iter()
+            .map(|item| {
+                *item_ids.entry(item.clone()).or_insert_with(|| {
+                    items.push(item.clone());
+                    items.len() - 1
+                })
+            })
+            .collect::<Vec<_>>();
+        encoded.sort();
+        encoded.dedup();
+        encoded_transactions.push(encoded);
+    }
+
+    let frequent_itemsets = frequent_itemsets(encoded_transactions, min_support, max_set_size);
+
+    let mut closed_itemsets = Vec::new();
+    for (itemset, support) in &frequent_itemsets {
+        if itemset.len() < 2 {
+            continue;
+        }
+
+        let has_closed_superset = frequent_itemsets
+            .iter()
+            .any(|(other, other_support)| {
+                other.len() == itemset.len() + 1 && other_support == support && is_subset(itemset, other)
+            });
+
+        if !has_closed_superset {
+            let mut names = itemset.iter().map(|item| items[*item].clone()).collect::<Vec<_>>();
+            names.sort();
+            closed_itemsets.push((names, *support));
+        }
+    }
+
+    closed_itemsets.sort_by(|(left_items, left_support), (right_items, right_support)| {
+        right_items.len().cmp(&left_items.len())
+            .then(right_support.cmp(left_support))
+            .then(left_items.cmp(right_items))
+    });
+
+    closed_itemsets
+}
+
+fn frequent_itemsets(
+    transactions: Vec<ItemSet>,
+    min_support: u64,
+    max_set_size: usize
+) -> Vec<(ItemSet, u64)> {
+    let min_support = min_support.max(1);
+
+    let mut item_support = BTreeMap::new();
+    for transaction in &transactions {
+        for item in transaction {
+            *item_support.entry(*item).or_insert(0u64) += 1;
+        }
+    }
+
+    let frequent_items = item_support
+        .iter()
+        .filter(|(_, support)| **support >= min_support)
+        .map(|(item, _)| *item)
+        .collect::<HashSet<_>>();
+
+    // Items that are not frequent can never be part of a frequent set
+    let transactions = transactions
+        .into_iter()
+        .map(|transaction| transaction.into_iter().filter(|item| frequent_items.contains(item)).collect::<Vec<_>>())
+        .filter(|transaction| transaction.len() >= 2)
+        .collect::<Vec<_>>();
+
+    let mut result = item_support
+        .into_iter()
+        .filter(|(_, support)| *support >= min_support)
+        .map(|(item, support)| (vec![item], support))
+        .collect::<Vec<_>>();
+
+    let mut current_level = result.clone();
+    let mut set_size = 2;
+    while set_size <= max_set_size && !current_level.is_empty() {
+        let previous_level = current_level
+            .iter()
+            .map(|(itemset, _)| itemset.clone())
+            .collect::<HashSet<_>>();
+
+        let mut candidates = generate_candidates(&current_level, &previous_level)
+            .into_iter()
+            .map(|candidate| (candidate, 0u64))
+            .collect::<Vec<_>>();
+
+        for transaction in &transactions {
+            if transaction.len() < set_size {
+                continue;
+            }
+
+            for (candidate, support) in candidates.iter_mut() {
+                if is_subset(candidate, transaction) {
+                    *support += 1;
+                }
+            }
+        }
+
+        current_level = candidates
+            .into_iter()
+            .filter(|(_, support)| *support >= min_support)
+            .collect();
+        result.extend(current_level.iter().cloned());
+        set_size += 1;
+    }
+
+    result
+}
+
+fn generate_candidates(level: &[(ItemSet, u64)], previous_level: &HashSet<ItemSet>) -> Vec<ItemSet> {
+    let mut candidates = Vec::new();
+
+    for (left_index, (left, _)) in level.iter().enumerate() {
+        for (right, _) in &level[(left_index + 1)..] {
+            let prefix_length = left.len() - 1;
+            if left[..prefix_length] != right[..prefix_length] {
+                continue;
+            }
+
+            let mut candidate = left.clone();
+            candidate.push(right[prefix_length]);
+            candidate.sort();
+
+            // All subsets of a frequent set must themselves be frequent
+            let all_subsets_frequent = (0..candidate.len()).all(|skip_index| {
+                let subset = candidate
+                    .iter()
+                    .enumerate()
+                    .filter(|(index, _)| *index != skip_index)
+                    .map(|(_, item)| *item)
+                    .collect::<Vec<_>>();
+                previous_level.contains(&subset)
+            });
+
+            if all_subsets_frequent {
+                candidates.push(candidate);
+            }
+        }
+    }
+
+    candidates
+}
+
+fn is_subset(subset: &[usize], set: &[usize]) -> bool {
+    let mut set_iter = set.iter();
+    subset.iter().all(|item| set_iter.any(|other| other == item))
+}
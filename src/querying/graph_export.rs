@@ -0,0 +1,165 @@
+use std::fmt::Write;
+
+use crate::querying::model::Graph;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GraphFormat {
+    GraphML,
+    Dot
+}
+
+impl GraphFormat {
+    pub fn from_name(name: &str) -> Option<GraphFormat> {
+        match name {
+            "graphml" => Some(GraphFormat::GraphML),
+            "dot" => Some(GraphFormat::Dot),
+            _ => None
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            GraphFormat::GraphML => "application/graphml+xml",
+            GraphFormat::Dot => "text/vnd.graphviz"
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            GraphFormat::GraphML => "graphml",
+            GraphFormat::Dot => "dot"
+        }
+    }
+
+    pub fn write(&self, graph: &Graph) -> String {
+        match self {
+            GraphFormat::GraphML => to_graphml(graph),
+            GraphFormat::Dot => to_dot(graph)
+        }
+    }
+}
+
+// The attributes are declared as keys, where the type is taken from the first node (or edge) having the attribute
+pub fn to_graphml(graph: &Graph) -> String {
+    let mut keys = Vec::new();
+    let node_attributes = graph.nodes.iter().flat_map(|node| node.attributes.iter().map(|attribute| ("node", attribute)));
+    let edge_attributes = graph.edges.iter().flat_map(|edge| edge.attributes.iter().map(|attribute| ("edge", attribute)));
+    for (element, (name, value)) in node_attributes.chain(edge_attributes) {
+        if !keys.iter().any(|(key_element, key_name, _)| *key_element == element && key_name == name) {
+            keys.push((element, name.clone(), graphml_type(value)));
+        }
+    }
+
+    let mut output = String::new();
+    output += "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n";
+    output += "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\" xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" ";
+    output += "xsi:schemaLocation=\"http://graphml.graphdrawing.org/xmlns http://graphml.graphdrawing.org/xmlns/1.0/graphml.xsd\">\n";
+
+    for (element, name, attribute_type) in &keys {
+        writeln!(
+            output,
+            "  <key id=\"{0}_{1}\" for=\"{0}\" attr.name=\"{1}\" attr.type=\"{2}\"/>",
+            element,
+            escape_xml(name),
+            attribute_type
+        ).unwrap();
+    }
+
+    writeln!(output, "  <graph id=\"{}\" edgedefault=\"undirected\">", escape_xml(&graph.name)).unwrap();
+
+    for node in &graph.nodes {
+        writeln!(output, "    <node id=\"{}\">", escape_xml(&node.id)).unwrap();
+        for (name, value) in &node.attributes {
+            writeln!(output, "      <data key=\"node_{}\">{}</data>", escape_xml(name), escape_xml(&attribute_value(value))).unwrap();
+        }
+        output += "    </node>\n";
+    }
+
+    for edge in &graph.edges {
+        writeln!(output, "    <edge source=\"{}\" target=\"{}\">", escape_xml(&edge.source), escape_xml(&edge.target)).unwrap();
+        for (name, value) in &edge.attributes {
+            writeln!(output, "      <data key=\"edge_{}\">{}</data>", escape_xml(name), escape_xml(&attribute_value(value))).unwrap();
+        }
+        output += "    </edge>\n";
+    }
+
+    output += "  </graph>\n";
+    output += "</graphml>\n";
+    output
+}
+
+pub fn to_dot(graph: &Graph) -> String {
+    fn dot_attributes(attributes: &serde_json::Map<String, serde_json::Value>) -> String {
+        if attributes.is_empty() {
+            return String::new();
+        }
+
+        let attributes = attributes
+            .iter()
+            .map(|(name, value)| {
+                match value {
+                    serde_json::Value::String(value) => format!("{}={}", name, quote_dot(value)),
+                    _ => format!("{}={}", name, attribute_value(value))
+                }
+            })
+            .collect::<Vec<_>>();
+
+        format!(" [{}]", attributes.join(", "))
+    }
+
+    let mut output = String::new();
+    writeln!(output, "graph {} {{", quote_dot(&graph.name)).unwrap();
+
+    for node in &graph.nodes {
+        writeln!(output, "    {}{};", quote_dot(&node.id), dot_attributes(&node.attributes)).unwrap();
+    }
+
+    for edge in &graph.edges {
+        writeln!(
+            output,
+            "    {} -- {}{};",
+            quote_dot(&edge.source),
+            quote_dot(&edge.target),
+            dot_attributes(&edge.attributes)
+        ).unwrap();
+    }
+
+    output += "}\n";
+    output
+}
+
+fn graphml_type(value: &serde_json::Value) -> &'static str {
+    match value {
+        serde_json::Value::Bool(_) => "boolean",
+        serde_json::Value::Number(number) if number.is_f64() => "double",
+        serde_json::Value::Number(_) => "long",
+        _ => "string"
+    }
+}
+
+fn attribute_value(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(value) => value.clone(),
+        _ => value.to_string()
+    }
+}
+
+pub fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped += "&amp;",
+            '<' => escaped += "&lt;",
+            '>' => escaped += "&gt;",
+            '"' => escaped += "&quot;",
+            '\'' => escaped += "&apos;",
+            c => escaped.push(c)
+        }
+    }
+
+    escaped
+}
+
+fn quote_dot(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
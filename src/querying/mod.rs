@@ -1,3 +1,6 @@
+use std::sync::Arc;
+
+use datafusion::arrow::error::ArrowError;
 use datafusion::common::DataFusionError;
 use thiserror::Error;
 
@@ -7,11 +10,18 @@ pub mod data_transformers;
 pub mod custom_functions;
 pub mod model_data_extraction;
 pub mod querying_helpers;
+pub mod frequent_itemsets;
+pub mod module_suggestion;
+pub mod author_aliases;
+pub mod saved_queries;
+pub mod user_views;
+pub mod export;
+pub mod graph_export;
 
 #[cfg(test)]
 pub mod querying_tests;
 
-use crate::querying::data_transformers::ModuleDefinitionError;
+use crate::querying::data_transformers::{AuthorExclusionError, RuleFileError};
 
 type QueryingResult<T> = Result<T, QueryingError>;
 
@@ -19,8 +29,53 @@ type QueryingResult<T> = Result<T, QueryingError>;
 pub enum QueryingError {
     #[error("DataFusion: {0}")]
     DataFusion(DataFusionError),
-    #[error("Module definition: {0}")]
-    ModuleDefinition(ModuleDefinitionError)
+    #[error("Rule file {0}")]
+    RuleFile(RuleFileError),
+    #[error("Excluded author: {0}")]
+    ExcludedAuthor(AuthorExclusionError),
+    #[error("Commit message pattern: {0}")]
+    CommitMessagePattern(regex::Error),
+    #[error("Module view '{0}': {1}")]
+    ModuleView(String, Arc<RuleFileError>),
+    #[error("Invalid module view name '{0}' (only letters, digits, '-' and '_' are allowed)")]
+    InvalidModuleView(String),
+    #[error("Unknown module view '{0}'")]
+    UnknownModuleView(String),
+    #[error("Missing value for query parameter '{0}'")]
+    MissingQueryParameter(String),
+    #[error("Invalid value for query parameter '{0}': {1}")]
+    InvalidQueryParameter(String, String),
+    #[error("Only read-only queries are allowed ({0})")]
+    QueryNotReadOnly(String),
+    #[error("The query exceeded the time limit of {0:.1} seconds")]
+    QueryTimeout(f64),
+    #[error("The query exceeded the memory limit: {0}")]
+    QueryMemoryLimitExceeded(String),
+    #[error("The query returned more than the maximum of {0} rows")]
+    QueryMaxRowsExceeded(usize),
+    #[error("Column '{0}' has an unsupported type: {1}")]
+    UnsupportedColumnType(String, String),
+    #[error("View file '{file}', statement at line {line_number} ({statement}): {message}")]
+    ViewFile {
+        file: String,
+        line_number: usize,
+        statement: String,
+        message: String
+    },
+    #[error("Failed to read view file '{0}': {1}")]
+    ViewFileRead(String, std::io::Error),
+    #[error("Invalid view file name '{0}' (expected 'views.sql' or 'views/<name>.sql')")]
+    InvalidViewFileName(String),
+    #[error("Unknown timezone '{0}'")]
+    UnknownTimezone(String),
+    #[error("Export: {0}")]
+    Export(String)
+}
+
+impl From<ArrowError> for QueryingError {
+    fn from(err: ArrowError) -> Self {
+        QueryingError::DataFusion(err.into())
+    }
 }
 
 impl From<DataFusionError> for QueryingError {
@@ -29,8 +84,14 @@ impl From<DataFusionError> for QueryingError {
     }
 }
 
-impl From<ModuleDefinitionError> for QueryingError {
-    fn from(err: ModuleDefinitionError) -> Self {
-        QueryingError::ModuleDefinition(err)
+impl From<RuleFileError> for QueryingError {
+    fn from(err: RuleFileError) -> Self {
+        QueryingError::RuleFile(err)
+    }
+}
+
+impl From<AuthorExclusionError> for QueryingError {
+    fn from(err: AuthorExclusionError) -> Self {
+        QueryingError::ExcludedAuthor(err)
     }
 }
\ No newline at end of file
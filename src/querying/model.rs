@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use serde::{Serialize, Serializer};
 
 use crate::indexing::indexer::GitLogEntry;
@@ -47,6 +47,70 @@ pub struct Module {
     pub files: Vec<FileEntry>
 }
 
+#[derive(Debug, Serialize)]
+pub struct ModuleTree {
+    pub name: String,
+    pub full_name: String,
+    pub is_module: bool,
+    pub num_files: u64,
+    pub num_code_lines: u64,
+    pub children: Vec<ModuleTree>
+}
+
+impl ModuleTree {
+    pub fn from_modules(modules: &[Module]) -> ModuleTree {
+        let mut root = ModuleTree::new("root".to_owned(), String::new());
+
+        for module in modules {
+            let num_files = module.files.len() as u64;
+            let num_code_lines = module.files.iter().map(|file| file.num_code_lines).sum::<u64>();
+
+            let mut current = &mut root;
+            current.num_files += num_files;
+            current.num_code_lines += num_code_lines;
+
+            let parts = module.name.split('/').collect::<Vec<_>>();
+            for (part_index, part) in parts.iter().enumerate() {
+                let child_index = match current.children.iter().position(|child| child.name == *part) {
+                    Some(child_index) => child_index,
+                    None => {
+                        let full_name = parts[..(part_index + 1)].join("/");
+                        current.children.push(ModuleTree::new(part.to_string(), full_name));
+                        current.children.len() - 1
+                    }
+                };
+
+                current = &mut current.children[child_index];
+                current.num_files += num_files;
+                current.num_code_lines += num_code_lines;
+            }
+
+            current.is_module = true;
+        }
+
+        root.sort();
+        root
+    }
+
+    fn new(name: String, full_name: String) -> ModuleTree {
+        ModuleTree {
+            name,
+            full_name,
+            is_module: false,
+            num_files: 0,
+            num_code_lines: 0,
+            children: Vec::new()
+        }
+    }
+
+    fn sort(&mut self) {
+        self.children.sort_by(|left, right| left.name.cmp(&right.name));
+        for child in &mut self.children {
+            child.sort();
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct FileHistoryEntry {
     pub name: String,
@@ -101,7 +165,7 @@ impl HotspotTree {
             RawHotspotTree::Tree { name, children } => {
                 let mut children = children
                     .into_values()
-                    .map(|value| HotspotTree::from_raw(value))
+                    .map(HotspotTree::from_raw)
                     .collect::<Vec<_>>();
 
                 children.sort_by_key(|child| child.name().to_owned());
@@ -121,8 +185,8 @@ impl HotspotTree {
 impl HotspotTree {
     pub fn name(&self) -> &str {
         match self {
-            HotspotTree::Tree { name, .. } => &name,
-            HotspotTree::Leaf { name, .. } => &name
+            HotspotTree::Tree { name, .. } => name,
+            HotspotTree::Leaf { name, .. } => name
         }
     }
 }
@@ -268,8 +332,8 @@ impl ChangeCouplingTree {
 impl ChangeCouplingTree {
     pub fn name(&self) -> &str {
         match self {
-            ChangeCouplingTree::Tree { name, .. } => &name,
-            ChangeCouplingTree::Leaf { name, .. } => &name
+            ChangeCouplingTree::Tree { name, .. } => name,
+            ChangeCouplingTree::Leaf { name, .. } => name
         }
     }
 
@@ -281,6 +345,89 @@ impl ChangeCouplingTree {
     }
 }
 
+#[derive(Debug, Serialize)]
+pub struct Graph {
+    pub name: String,
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>
+}
+
+#[derive(Debug, Serialize)]
+pub struct GraphNode {
+    pub id: String,
+    pub attributes: serde_json::Map<String, serde_json::Value>
+}
+
+#[derive(Debug, Serialize)]
+pub struct GraphEdge {
+    pub source: String,
+    pub target: String,
+    pub attributes: serde_json::Map<String, serde_json::Value>
+}
+
+impl Graph {
+    // The files (or modules) are the nodes and the couplings above the thresholds are the edges
+    pub fn from_change_couplings(
+        name: &str,
+        change_couplings: &[ChangeCouplingEntry],
+        hotspots: &[HotspotEntry],
+        min_coupled_revisions: u64,
+        min_coupling_ratio: f64
+    ) -> Graph {
+        let edges = change_couplings
+            .iter()
+            .filter(|coupling| coupling.coupled_revisions >= min_coupled_revisions && coupling.coupling_ratio() >= min_coupling_ratio)
+            .map(|coupling| {
+                GraphEdge {
+                    source: coupling.left_name.clone(),
+                    target: coupling.right_name.clone(),
+                    attributes: graph_attributes([
+                        ("coupled_revisions", coupling.coupled_revisions.into()),
+                        ("coupling_ratio", coupling.coupling_ratio().into())
+                    ])
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let coupled = edges
+            .iter()
+            .flat_map(|edge| [edge.source.as_str(), edge.target.as_str()])
+            .collect::<HashSet<_>>();
+
+        let nodes = hotspots
+            .iter()
+            .filter(|hotspot| coupled.contains(hotspot.name.as_str()))
+            .map(|hotspot| {
+                GraphNode {
+                    id: hotspot.name.clone(),
+                    attributes: graph_attributes([
+                        ("num_code_lines", hotspot.num_code_lines.into()),
+                        ("num_revisions", hotspot.num_revisions.into()),
+                        ("num_authors", hotspot.num_authors.into())
+                    ])
+                }
+            })
+            .collect::<Vec<_>>();
+
+        // Couplings can include files no longer in the hotspots (such as ignored ones)
+        let node_ids = nodes.iter().map(|node| node.id.as_str()).collect::<HashSet<_>>();
+        let edges = edges
+            .into_iter()
+            .filter(|edge| node_ids.contains(edge.source.as_str()) && node_ids.contains(edge.target.as_str()))
+            .collect();
+
+        Graph {
+            name: name.to_owned(),
+            nodes,
+            edges
+        }
+    }
+}
+
+pub fn graph_attributes<const N: usize>(attributes: [(&str, serde_json::Value); N]) -> serde_json::Map<String, serde_json::Value> {
+    attributes.into_iter().map(|(name, value)| (name.to_owned(), value)).collect()
+}
+
 #[derive(Debug, Serialize)]
 pub struct Coupling {
     pub coupled: String,
@@ -394,6 +541,126 @@ impl RawChangeCouplingTree {
     }
 }
 
+#[derive(Debug, Serialize)]
+pub struct CouplingViolationEntry {
+    pub rule: String,
+    pub left_name: String,
+    pub right_name: String,
+    pub coupled_revisions: u64,
+    pub num_left_revisions: u64,
+    pub num_right_revisions: u64,
+    pub coupling_ratio: f64,
+    pub revisions: Vec<String>
+}
+
+#[derive(Debug, Serialize)]
+pub struct CoChangeClusterEntry {
+    pub names: Vec<String>,
+    pub coupled_revisions: u64
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModuleDefinitionSuggestion {
+    pub definition: String,
+    pub modules: Vec<SuggestedModule>
+}
+
+#[derive(Debug, Serialize)]
+pub struct SuggestedModule {
+    pub name: String,
+    pub files: Vec<String>
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModuleDefinitionComparison {
+    pub num_files: u64,
+    pub num_changed_files: u64,
+    pub files: Vec<ModuleAssignmentEntry>
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModuleAssignmentEntry {
+    pub file_name: String,
+    pub current_module: String,
+    pub suggested_module: String
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModuleDefinitionValidation {
+    pub num_files: u64,
+    pub num_matched_files: u64,
+    pub unmatched_files: Vec<UnmatchedFileEntry>,
+    pub unused_rules: Vec<ModuleRuleEntry>,
+    pub shadowed_files: Vec<ShadowedFileEntry>
+}
+
+#[derive(Debug, Serialize)]
+pub struct UnmatchedFileEntry {
+    pub file_name: String,
+    pub default_module: String
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ModuleRuleEntry {
+    pub line_number: usize,
+    pub definition: String
+}
+
+#[derive(Debug, Serialize)]
+pub struct ShadowedFileEntry {
+    pub file_name: String,
+    pub module_name: String,
+    pub rule: ModuleRuleEntry,
+    pub shadowed_rules: Vec<ShadowedRuleEntry>
+}
+
+#[derive(Debug, Serialize)]
+pub struct ShadowedRuleEntry {
+    #[serde(flatten)]
+    pub rule: ModuleRuleEntry,
+    pub module_name: String
+}
+
+#[derive(Debug, Serialize)]
+pub struct QuerySchema {
+    pub tables: Vec<TableSchema>,
+    pub functions: Vec<FunctionSchema>
+}
+
+#[derive(Debug, Serialize)]
+pub struct TableSchema {
+    pub name: String,
+    pub is_view: bool,
+    pub columns: Vec<ColumnSchema>
+}
+
+#[derive(Debug, Serialize)]
+pub struct ColumnSchema {
+    pub name: String,
+    pub data_type: String,
+    pub nullable: bool
+}
+
+#[derive(Debug, Serialize)]
+pub struct FunctionSchema {
+    pub name: String,
+    pub signatures: Vec<String>
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuthorAliasSuggestion {
+    pub name: String,
+    pub num_revisions: u64,
+    pub aliases: Vec<AuthorAlias>
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuthorAlias {
+    pub name: String,
+    pub emails: Vec<String>,
+    pub num_revisions: u64
+}
+
 #[derive(Debug, Serialize)]
 pub struct SumOfCouplingEntry {
     pub name: String,
@@ -432,7 +699,7 @@ impl MainDeveloperTree {
             RawMainDeveloperTree::Tree { name, children } => {
                 let mut children = children
                     .into_values()
-                    .map(|value| MainDeveloperTree::from_raw(value))
+                    .map(MainDeveloperTree::from_raw)
                     .collect::<Vec<_>>();
 
                 children.sort_by_key(|child| child.name().to_owned());
@@ -452,8 +719,8 @@ impl MainDeveloperTree {
 impl MainDeveloperTree {
     pub fn name(&self) -> &str {
         match self {
-            MainDeveloperTree::Tree { name, .. } => &name,
-            MainDeveloperTree::Leaf { name, .. } => &name
+            MainDeveloperTree::Tree { name, .. } => name,
+            MainDeveloperTree::Leaf { name, .. } => name
         }
     }
 }
@@ -519,6 +786,23 @@ pub struct CommitSpreadEntry {
     pub num_revisions: u64
 }
 
+// The truck factor is the smallest number of authors that together have added more than half of the lines of the module
+#[derive(Debug, Serialize)]
+pub struct TruckFactorEntry {
+    pub name: String,
+    pub truck_factor: u64,
+    pub num_authors: u64
+}
+
+// The age is the number of days between the last change of the file and the last commit of the repository
+#[derive(Debug, Serialize)]
+pub struct CodeAgeEntry {
+    pub name: String,
+    pub last_changed_date: i64,
+    pub age_days: u64,
+    pub num_code_lines: u64
+}
+
 #[derive(Debug, Serialize)]
 pub struct CustomAnalysis {
     pub columns: Vec<String>,
@@ -527,29 +811,65 @@ pub struct CustomAnalysis {
 
 pub type CustomValueRow = Vec<CustomValue>;
 
+#[derive(Debug, Serialize)]
+pub struct QueryPlan {
+    pub logical_plan: String,
+    pub physical_plan: String,
+    // Only when analyzing the query
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub operators: Option<QueryPlanOperator>
+}
+
+#[derive(Debug, Serialize)]
+pub struct QueryPlanOperator {
+    pub operator: String,
+    pub metrics: Vec<QueryPlanMetric>,
+    pub children: Vec<QueryPlanOperator>
+}
+
+#[derive(Debug, Serialize)]
+pub struct QueryPlanMetric {
+    pub name: String,
+    // Counts, bytes or nanoseconds depending on the metric
+    pub value: usize,
+    pub display_value: String
+}
+
 #[derive(Debug)]
 pub enum CustomValue {
+    Null,
     UInt8(Option<u8>),
+    UInt16(Option<u16>),
     UInt32(Option<u32>),
     UInt64(Option<u64>),
     Int8(Option<i8>),
+    Int16(Option<i16>),
     Int32(Option<i32>),
     Int64(Option<i64>),
     Bool(Option<bool>),
     Float32(Option<f32>),
     Float64(Option<f64>),
-    String(Option<String>)
+    String(Option<String>),
+    // Nested values, such as lists and structs
+    Json(Option<serde_json::Value>)
 }
 
 impl Serialize for CustomValue {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
         match self {
+            CustomValue::Null => serializer.serialize_none(),
             CustomValue::UInt8(value) => {
                 match value {
                     Some(value) => serializer.serialize_u8(*value),
                     None => serializer.serialize_none()
                 }
             }
+            CustomValue::UInt16(value) => {
+                match value {
+                    Some(value) => serializer.serialize_u16(*value),
+                    None => serializer.serialize_none()
+                }
+            }
             CustomValue::UInt32(value) => {
                 match value {
                     Some(value) => serializer.serialize_u32(*value),
@@ -568,6 +888,12 @@ impl Serialize for CustomValue {
                     None => serializer.serialize_none()
                 }
             }
+            CustomValue::Int16(value) => {
+                match value {
+                    Some(value) => serializer.serialize_i16(*value),
+                    None => serializer.serialize_none()
+                }
+            }
             CustomValue::Int32(value) => {
                 match value {
                     Some(value) => serializer.serialize_i32(*value),
@@ -604,6 +930,12 @@ impl Serialize for CustomValue {
                     None => serializer.serialize_none()
                 }
             }
+            CustomValue::Json(value) => {
+                match value {
+                    Some(value) => value.serialize(serializer),
+                    None => serializer.serialize_none()
+                }
+            }
         }
     }
 }
@@ -1,10 +1,12 @@
-use datafusion::arrow::array::{ArrayRef, ArrowPrimitiveType, AsArray, RecordBatch};
+use datafusion::arrow::array::{Array, ArrayRef, ArrowPrimitiveType, AsArray, RecordBatch};
 use datafusion::arrow::datatypes::*;
+use datafusion::arrow::util::display::{ArrayFormatter, FormatOptions};
 use datafusion::dataframe::DataFrame;
+use datafusion::physical_plan::{displayable, ExecutionPlan};
 
 use crate::indexing::indexer::GitLogEntry;
 use crate::querying::model::*;
-use crate::querying::QueryingResult;
+use crate::querying::{QueryingError, QueryingResult};
 
 pub fn yield_rows<F: FnMut(&[&ArrayRef], usize)>(results: Vec<RecordBatch>, num_columns: usize, mut callback: F) {
     let mut row_columns = Vec::new();
@@ -56,14 +58,15 @@ impl FromRow for Author {
 }
 
 impl FromRow for GitLogEntry {
-    const NUM_COLUMNS: usize = 4;
+    const NUM_COLUMNS: usize = 5;
 
     fn from_row(columns: &[&ArrayRef], row_index: usize, base_column_index: usize) -> GitLogEntry {
         GitLogEntry {
             revision: columns[base_column_index].as_string_view().value(row_index).to_owned(),
             date: columns[base_column_index + 1].as_primitive::<Int64Type>().value(row_index),
             author: columns[base_column_index + 2].as_string_view().value(row_index).to_owned(),
-            commit_message: columns[base_column_index + 3].as_string_view().value(row_index).to_owned()
+            author_email: columns[base_column_index + 3].as_string_view().value(row_index).to_owned(),
+            commit_message: columns[base_column_index + 4].as_string_view().value(row_index).to_owned()
         }
     }
 }
@@ -136,6 +139,19 @@ impl FromRow for SumOfCouplingEntry {
     }
 }
 
+impl FromRow for CodeAgeEntry {
+    const NUM_COLUMNS: usize = 4;
+
+    fn from_row(columns: &[&ArrayRef], row_index: usize, base_column_index: usize) -> CodeAgeEntry {
+        CodeAgeEntry {
+            name: columns[base_column_index].as_string_view().value(row_index).to_owned(),
+            last_changed_date: columns[base_column_index + 1].as_primitive::<Int64Type>().value(row_index),
+            age_days: columns[base_column_index + 2].as_primitive::<Int64Type>().value(row_index).max(0) as u64,
+            num_code_lines: columns[base_column_index + 3].as_primitive::<UInt64Type>().value(row_index)
+        }
+    }
+}
+
 impl FromRow for MainDeveloperEntry {
     const NUM_COLUMNS: usize = 4;
 
@@ -171,7 +187,11 @@ impl FromRow for CommitSpreadEntry {
 }
 
 impl CustomValue {
-    pub fn from_column(column_def: &FieldRef, column: &ArrayRef, record_index: usize) -> Option<CustomValue> {
+    pub fn from_column(column_def: &FieldRef, column: &ArrayRef, record_index: usize) -> QueryingResult<CustomValue> {
+        CustomValue::from_array(column_def.name(), column, record_index)
+    }
+
+    fn from_array(name: &str, column: &ArrayRef, record_index: usize) -> QueryingResult<CustomValue> {
         fn extract_primitive<T: ArrowPrimitiveType<Native = U>, U>(column: &ArrayRef, record_index: usize) -> Option<U> {
             if column.is_valid(record_index) {
                 Some(column.as_primitive::<T>().value(record_index))
@@ -180,55 +200,184 @@ impl CustomValue {
             }
         }
 
-        match column_def.data_type() {
+        fn extract<T>(column: &ArrayRef, record_index: usize, get_value: impl FnOnce() -> T) -> Option<T> {
+            if column.is_valid(record_index) {
+                Some(get_value())
+            } else {
+                None
+            }
+        }
+
+        let value = match column.data_type() {
+            DataType::Null => CustomValue::Null,
             DataType::Utf8 => {
-                if column.is_valid(record_index) {
-                    Some(CustomValue::String(Some(column.as_string_view().value(record_index).to_owned())))
-                } else {
-                    Some(CustomValue::String(None))
-                }
+                CustomValue::String(extract(column, record_index, || column.as_string::<i32>().value(record_index).to_owned()))
+            }
+            DataType::LargeUtf8 => {
+                CustomValue::String(extract(column, record_index, || column.as_string::<i64>().value(record_index).to_owned()))
             }
             DataType::Utf8View => {
-                if column.is_valid(record_index) {
-                    Some(CustomValue::String(Some(column.as_string_view().value(record_index).to_owned())))
-                } else {
-                    Some(CustomValue::String(None))
-                }
+                CustomValue::String(extract(column, record_index, || column.as_string_view().value(record_index).to_owned()))
             }
             DataType::Int8 => {
-                Some(CustomValue::Int8(extract_primitive::<Int8Type, _>(column, record_index)))
+                CustomValue::Int8(extract_primitive::<Int8Type, _>(column, record_index))
+            }
+            DataType::Int16 => {
+                CustomValue::Int16(extract_primitive::<Int16Type, _>(column, record_index))
             }
             DataType::Int32 => {
-                Some(CustomValue::Int32(extract_primitive::<Int32Type, _>(column, record_index)))
+                CustomValue::Int32(extract_primitive::<Int32Type, _>(column, record_index))
             }
             DataType::Int64 => {
-                Some(CustomValue::Int64(extract_primitive::<Int64Type, _>(column, record_index)))
+                CustomValue::Int64(extract_primitive::<Int64Type, _>(column, record_index))
             }
             DataType::UInt8 => {
-                Some(CustomValue::UInt8(extract_primitive::<UInt8Type, _>(column, record_index)))
+                CustomValue::UInt8(extract_primitive::<UInt8Type, _>(column, record_index))
+            }
+            DataType::UInt16 => {
+                CustomValue::UInt16(extract_primitive::<UInt16Type, _>(column, record_index))
             }
             DataType::UInt32 => {
-                Some(CustomValue::UInt32(extract_primitive::<UInt32Type, _>(column, record_index)))
+                CustomValue::UInt32(extract_primitive::<UInt32Type, _>(column, record_index))
             }
             DataType::UInt64 => {
-                Some(CustomValue::UInt64(extract_primitive::<UInt64Type, _>(column, record_index)))
+                CustomValue::UInt64(extract_primitive::<UInt64Type, _>(column, record_index))
             }
             DataType::Boolean => {
+                CustomValue::Bool(extract(column, record_index, || column.as_boolean().value(record_index)))
+            }
+            DataType::Float16 => {
+                CustomValue::Float32(extract_primitive::<Float16Type, _>(column, record_index).map(|value| value.to_f32()))
+            }
+            DataType::Float32 => {
+                CustomValue::Float32(extract_primitive::<Float32Type, f32>(column, record_index))
+            }
+            DataType::Float64 => {
+                CustomValue::Float64(extract_primitive::<Float64Type, f64>(column, record_index))
+            }
+            DataType::Decimal128(_, _)
+            | DataType::Decimal256(_, _)
+            | DataType::Date32
+            | DataType::Date64
+            | DataType::Timestamp(_, _)
+            | DataType::Time32(_)
+            | DataType::Time64(_)
+            | DataType::Duration(_)
+            | DataType::Interval(_) => {
+                CustomValue::String(format_value(name, column, record_index)?)
+            }
+            DataType::Dictionary(_, _) => {
+                let dictionary = column.as_any_dictionary();
                 if column.is_valid(record_index) {
-                    Some(CustomValue::Bool(Some(column.as_boolean().value(record_index))))
+                    let value_index = dictionary.normalized_keys()[record_index];
+                    CustomValue::from_array(name, dictionary.values(), value_index)?
                 } else {
-                    Some(CustomValue::Bool(None))
+                    CustomValue::Null
                 }
             }
-            DataType::Float32 => {
-                Some(CustomValue::Float32(extract_primitive::<Float32Type, f32>(column, record_index)))
+            DataType::List(_)
+            | DataType::LargeList(_)
+            | DataType::FixedSizeList(_, _)
+            | DataType::Struct(_)
+            | DataType::Map(_, _) => {
+                if column.is_valid(record_index) {
+                    CustomValue::Json(Some(json_value(name, column, record_index)?))
+                } else {
+                    CustomValue::Json(None)
+                }
             }
-            DataType::Float64 => {
-                Some(CustomValue::Float64(extract_primitive::<Float64Type, f64>(column, record_index)))
+            data_type => {
+                return Err(QueryingError::UnsupportedColumnType(name.to_owned(), data_type.to_string()));
             }
-            _ => {
-                None
+        };
+
+        Ok(value)
+    }
+}
+
+impl QueryPlanOperator {
+    pub fn from_execution_plan(plan: &dyn ExecutionPlan) -> QueryPlanOperator {
+        let mut metrics = Vec::new();
+        if let Some(metrics_set) = plan.metrics() {
+            for metric in metrics_set.aggregate_by_name().sorted_for_display().timestamps_removed().iter() {
+                metrics.push(
+                    QueryPlanMetric {
+                        name: metric.value().name().to_owned(),
+                        value: metric.value().as_usize(),
+                        display_value: metric.value().to_string()
+                    }
+                );
+            }
+        }
+
+        QueryPlanOperator {
+            operator: displayable(plan).one_line().to_string().trim_end().to_owned(),
+            metrics,
+            children: plan.children().into_iter().map(|child| QueryPlanOperator::from_execution_plan(child.as_ref())).collect()
+        }
+    }
+}
+
+// Dates and times are formatted in ISO 8601 (such as '2025-01-31T12:00:00' or '2025-01-31T12:00:00+01:00')
+fn format_value(name: &str, column: &ArrayRef, record_index: usize) -> QueryingResult<Option<String>> {
+    if !column.is_valid(record_index) {
+        return Ok(None);
+    }
+
+    let formatter = ArrayFormatter::try_new(column.as_ref(), &FormatOptions::default())
+        .map_err(|err| QueryingError::UnsupportedColumnType(name.to_owned(), err.to_string()))?;
+    Ok(Some(formatter.value(record_index).to_string()))
+}
+
+// Lists become JSON arrays and structs and maps become JSON objects
+fn json_value(name: &str, column: &ArrayRef, record_index: usize) -> QueryingResult<serde_json::Value> {
+    fn json_array(name: &str, values: &ArrayRef) -> QueryingResult<serde_json::Value> {
+        let mut array = Vec::new();
+        for value_index in 0..values.len() {
+            array.push(json_value(name, values, value_index)?);
+        }
+
+        Ok(serde_json::Value::Array(array))
+    }
+
+    if !column.is_valid(record_index) {
+        return Ok(serde_json::Value::Null);
+    }
+
+    match column.data_type() {
+        DataType::List(_) => json_array(name, &column.as_list::<i32>().value(record_index)),
+        DataType::LargeList(_) => json_array(name, &column.as_list::<i64>().value(record_index)),
+        DataType::FixedSizeList(_, _) => json_array(name, &column.as_fixed_size_list().value(record_index)),
+        DataType::Struct(fields) => {
+            let struct_array = column.as_struct();
+
+            let mut object = serde_json::Map::new();
+            for (field, field_column) in fields.iter().zip(struct_array.columns()) {
+                object.insert(field.name().to_owned(), json_value(name, field_column, record_index)?);
+            }
+
+            Ok(serde_json::Value::Object(object))
+        }
+        DataType::Map(_, _) => {
+            let entries = column.as_map().value(record_index);
+            let keys = entries.column(0);
+            let values = entries.column(1);
+
+            let mut object = serde_json::Map::new();
+            for entry_index in 0..entries.len() {
+                let key = match json_value(name, keys, entry_index)? {
+                    serde_json::Value::String(key) => key,
+                    key => key.to_string()
+                };
+
+                object.insert(key, json_value(name, values, entry_index)?);
             }
+
+            Ok(serde_json::Value::Object(object))
+        }
+        _ => {
+            let value = CustomValue::from_array(name, column, record_index)?;
+            serde_json::to_value(&value).map_err(|err| QueryingError::UnsupportedColumnType(name.to_owned(), err.to_string()))
         }
     }
 }
@@ -0,0 +1,187 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use glob::Pattern;
+
+use crate::querying::data_transformers::default_module_name;
+
+// Finds communities using the Louvain method, where nodes are visited in a fixed order and ties are resolved
+// towards the current/smallest community, which makes the resulting partition deterministic
+pub fn louvain_communities(num_nodes: usize, edges: &[(usize, usize, f64)]) -> Vec<usize> {
+    let mut graph = vec![BTreeMap::new(); num_nodes];
+    for (left, right, weight) in edges {
+        *graph[*left].entry(*right).or_insert(0.0) += weight;
+        *graph[*right].entry(*left).or_insert(0.0) += weight;
+    }
+
+    let mut node_communities = (0..num_nodes).collect::<Vec<_>>();
+    loop {
+        let communities = louvain_local_moving(&graph);
+
+        let mut community_indices = BTreeMap::new();
+        for community in &communities {
+            let next_index = community_indices.len();
+            community_indices.entry(*community).or_insert(next_index);
+        }
+
+        if community_indices.len() == graph.len() {
+            break;
+        }
+
+        for node_community in node_communities.iter_mut() {
+            *node_community = community_indices[&communities[*node_community]];
+        }
+
+        let mut aggregated_graph = vec![BTreeMap::new(); community_indices.len()];
+        for (node, neighbors) in graph.iter().enumerate() {
+            let community = community_indices[&communities[node]];
+            for (neighbor, weight) in neighbors {
+                let neighbor_community = community_indices[&communities[*neighbor]];
+                *aggregated_graph[community].entry(neighbor_community).or_insert(0.0) += weight;
+            }
+        }
+
+        graph = aggregated_graph;
+    }
+
+    node_communities
+}
+
+fn louvain_local_moving(graph: &[BTreeMap<usize, f64>]) -> Vec<usize> {
+    let degrees = graph.iter().map(|neighbors| neighbors.values().sum::<f64>()).collect::<Vec<_>>();
+    let total_weight = degrees.iter().sum::<f64>();
+
+    let mut communities = (0..graph.len()).collect::<Vec<_>>();
+    let mut community_degrees = degrees.clone();
+    if total_weight == 0.0 {
+        return communities;
+    }
+
+    loop {
+        let mut changed = false;
+
+        for node in 0..graph.len() {
+            let current_community = communities[node];
+            community_degrees[current_community] -= degrees[node];
+
+            let mut community_weights = BTreeMap::new();
+            community_weights.insert(current_community, 0.0);
+            for (neighbor, weight) in &graph[node] {
+                if *neighbor != node {
+                    *community_weights.entry(communities[*neighbor]).or_insert(0.0) += weight;
+                }
+            }
+
+            let gain = |community: usize, weight: f64| {
+                weight - community_degrees[community] * degrees[node] / total_weight
+            };
+
+            let mut best_community = current_community;
+            let mut best_gain = gain(current_community, community_weights[&current_community]);
+            for (community, weight) in &community_weights {
+                let community_gain = gain(*community, *weight);
+                if community_gain > best_gain + 1E-12 {
+                    best_community = *community;
+                    best_gain = community_gain;
+                }
+            }
+
+            community_degrees[best_community] += degrees[node];
+            if best_community != current_community {
+                communities[node] = best_community;
+                changed = true;
+            }
+        }
+
+        if !changed {
+            break;
+        }
+    }
+
+    communities
+}
+
+// Names each group by the most common folder of its files, adding a suffix when the name is already taken
+pub fn name_modules(groups: Vec<Vec<String>>) -> BTreeMap<String, Vec<String>> {
+    let mut modules = BTreeMap::new();
+
+    for files in groups {
+        let mut folder_counts = BTreeMap::new();
+        for file in &files {
+            *folder_counts.entry(default_module_name(file)).or_insert(0) += 1;
+        }
+
+        let max_count = folder_counts.values().cloned().max().unwrap_or(0);
+        let base_name = folder_counts
+            .into_iter()
+            .find(|(_, count)| *count == max_count)
+            .map(|(folder, _)| folder)
+            .unwrap_or_else(|| "<root>".to_owned());
+
+        let mut name = base_name.clone();
+        let mut suffix = 2;
+        while modules.contains_key(&name) {
+            name = format!("{}-{}", base_name, suffix);
+            suffix += 1;
+        }
+
+        modules.insert(name, files);
+    }
+
+    modules
+}
+
+// Creates a definition in the format of 'modules.txt', where whole folders are used if all files in them belong to the
+// same module
+pub fn create_module_definition(modules: &BTreeMap<String, Vec<String>>, all_files: &[String]) -> String {
+    let mut file_modules = HashMap::new();
+    for (module_name, files) in modules {
+        for file in files {
+            file_modules.insert(file.as_str(), module_name.as_str());
+        }
+    }
+
+    let mut folder_modules = HashMap::<String, Option<&str>>::new();
+    for file in all_files {
+        let module_name = file_modules.get(file.as_str()).cloned();
+        for folder in parent_folders(file) {
+            folder_modules
+                .entry(folder)
+                .and_modify(|current| {
+                    if *current != module_name {
+                        *current = None;
+                    }
+                })
+                .or_insert(module_name);
+        }
+    }
+
+    let mut definition = String::new();
+    for (module_name, files) in modules {
+        let mut patterns = BTreeSet::new();
+        for file in files {
+            let folder = parent_folders(file)
+                .into_iter()
+                .find(|folder| folder_modules.get(folder).cloned().flatten() == Some(module_name.as_str()));
+
+            match folder {
+                Some(folder) => patterns.insert(format!("{}/**/*", Pattern::escape(&folder))),
+                None => patterns.insert(Pattern::escape(file))
+            };
+        }
+
+        for pattern in patterns {
+            definition += &format!("{} => {}\n", pattern, module_name);
+        }
+        definition += "\n";
+    }
+
+    definition.trim_end().to_owned()
+}
+
+// The parent folders of the file, starting with the top-most one
+fn parent_folders(file_name: &str) -> Vec<String> {
+    let parts = file_name.split('/').collect::<Vec<_>>();
+    (1..parts.len())
+        .map(|length| parts[..length].join("/"))
+        .collect()
+}
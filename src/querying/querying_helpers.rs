@@ -1,9 +1,106 @@
+use std::collections::HashMap;
+
 use datafusion::dataframe::DataFrame;
-use datafusion::common::DataFusionError;
+use datafusion::common::{DataFusionError, ScalarValue};
+
+use crate::querying::{QueryingError, QueryingResult};
 
 pub fn add_optional_limit(result_df: DataFrame, count: Option<usize>) -> Result<DataFrame, DataFusionError> {
     match count {
         Some(count) => result_df.limit(0, Some(count)),
         None => Ok(result_df)
     }
-}
\ No newline at end of file
+}
+
+// Values for the placeholders of a query, either positional ($1, $2, ...) or named ($module, ...)
+#[derive(Debug, Clone, Default)]
+pub struct QueryParameters {
+    pub positional: Vec<ScalarValue>,
+    pub named: HashMap<String, ScalarValue>
+}
+
+impl QueryParameters {
+    // Positional parameters are given as an array and named as an object, where the types are inferred from the JSON
+    pub fn from_json(value: &serde_json::Value) -> QueryingResult<QueryParameters> {
+        let mut parameters = QueryParameters::default();
+
+        match value {
+            serde_json::Value::Null => {}
+            serde_json::Value::Array(values) => {
+                for (index, value) in values.iter().enumerate() {
+                    parameters.positional.push(json_to_scalar(&format!("${}", index + 1), value)?);
+                }
+            }
+            serde_json::Value::Object(values) => {
+                for (name, value) in values {
+                    let name = name.strip_prefix('$').unwrap_or(name);
+                    parameters.named.insert(name.to_owned(), json_to_scalar(&format!("${}", name), value)?);
+                }
+            }
+            _ => {
+                return Err(QueryingError::InvalidQueryParameter(
+                    "parameters".to_owned(),
+                    "expected an array or an object".to_owned()
+                ));
+            }
+        }
+
+        Ok(parameters)
+    }
+
+    fn get(&self, id: &str) -> Option<&ScalarValue> {
+        let name = id.strip_prefix('$').unwrap_or(id);
+        match name.parse::<usize>() {
+            Ok(position) => position.checked_sub(1).and_then(|index| self.positional.get(index)),
+            Err(_) => self.named.get(name)
+        }
+    }
+}
+
+fn json_to_scalar(id: &str, value: &serde_json::Value) -> QueryingResult<ScalarValue> {
+    match value {
+        serde_json::Value::Null => Ok(ScalarValue::Null),
+        serde_json::Value::Bool(value) => Ok(ScalarValue::Boolean(Some(*value))),
+        serde_json::Value::Number(value) => {
+            if let Some(value) = value.as_i64() {
+                Ok(ScalarValue::Int64(Some(value)))
+            } else if let Some(value) = value.as_u64() {
+                Ok(ScalarValue::UInt64(Some(value)))
+            } else {
+                Ok(ScalarValue::Float64(value.as_f64()))
+            }
+        }
+        serde_json::Value::String(value) => Ok(ScalarValue::Utf8(Some(value.clone()))),
+        _ => Err(QueryingError::InvalidQueryParameter(id.to_owned(), "arrays and objects are not supported".to_owned()))
+    }
+}
+
+// Binds the parameters to the placeholders of the query, where the values are cast to the types DataFusion inferred for
+// the placeholders (such that '5' can be used for an integer column)
+pub fn bind_parameters(result_df: DataFrame, parameters: &QueryParameters) -> QueryingResult<DataFrame> {
+    let parameter_types = result_df.logical_plan().get_parameter_types()?;
+    if parameter_types.is_empty() {
+        return Ok(result_df);
+    }
+
+    let mut ids = parameter_types.keys().collect::<Vec<_>>();
+    ids.sort();
+
+    let mut values = HashMap::new();
+    for id in ids {
+        let value = parameters.get(id).ok_or_else(|| QueryingError::MissingQueryParameter(id.clone()))?;
+
+        let value = match &parameter_types[id] {
+            Some(data_type) if &value.data_type() != data_type && !value.is_null() => {
+                value
+                    .cast_to(data_type)
+                    .map_err(|err| QueryingError::InvalidQueryParameter(id.clone(), err.to_string()))?
+            }
+            _ => value.clone()
+        };
+
+        values.insert(id[1..].to_owned(), value);
+    }
+
+    Ok(result_df.with_param_values(values)?)
+}
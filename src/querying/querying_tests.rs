@@ -1,6 +1,28 @@
+use std::collections::HashMap;
 use std::path::Path;
 
-use crate::querying::engine::{RepositoryQuerying, RepositoryQueryingConfig};
+use chrono_tz::Tz;
+use futures::StreamExt;
+use serde_json::json;
+use tempfile::TempDir;
+
+use datafusion::arrow::array::{AsArray, RecordBatch};
+use datafusion::arrow::datatypes::{DataType, SchemaRef};
+use datafusion::parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+use crate::indexing::{GIT_FILE_ENTRIES_PATH, GIT_LOG_PATH};
+
+use crate::querying::custom_functions::{base_name, commit_first_line, conventional_commit_type, directory_at_depth, file_extension, file_language, issue_keys, parse_timezone, path_depth, time_bucket};
+use crate::querying::author_aliases::{suggest_author_aliases, AuthorIdentity};
+use crate::querying::data_transformers::{ArchitectureRules, AuthorExclusions, AuthorNormalizer, IgnoreFile, ModuleDefinitions};
+use crate::querying::graph_export::GraphFormat;
+use crate::querying::export::{rows_to_record_batches, ExportFormat, RecordBatchWriter};
+use crate::querying::engine::{author_collaboration_edges, module_definition_path, module_views, truck_factor, CustomAnalysisLimits, RepositoryQuerying, RepositoryQueryingConfig, DEFAULT_MODULE_VIEW};
+use crate::querying::QueryingError;
+use crate::querying::querying_helpers::QueryParameters;
+use crate::querying::saved_queries::{delete_saved_query, list_saved_queries, load_saved_query, save_query, SavedQuery, SavedQueryError};
+use crate::querying::user_views::{load_view_files, split_statements, view_file_path, ViewFile, ViewStatement, VIEWS_DIRECTORY, VIEWS_FILE};
+use crate::querying::model::{graph_attributes, CoChangeClusterEntry, CouplingViolationEntry, CustomValue, Graph, GraphEdge, GraphNode, HotspotEntry, ModuleTree};
 
 #[tokio::test]
 async fn test_summary() {
@@ -24,6 +46,198 @@ async fn test_log() {
     assert_eq!("82169c2", entry.revision);
 }
 
+#[tokio::test]
+async fn test_excluded_authors() {
+    let repository_querying = create_querying().await;
+    let log = repository_querying.log().await.unwrap();
+    assert_eq!(286, log.len());
+
+    // The test data only has a single author
+    let repository_querying = create_querying_with_config(
+        RepositoryQueryingConfig {
+            excluded_authors: vec!["anton jansson".to_owned()],
+            ..RepositoryQueryingConfig::default()
+        }
+    ).await;
+
+    let log = repository_querying.log().await.unwrap();
+    assert_eq!(0, log.len());
+
+    let repository_querying = create_querying_with_config(
+        RepositoryQueryingConfig {
+            excluded_authors: vec!["/(?i)^anton/".to_owned()],
+            ..RepositoryQueryingConfig::default()
+        }
+    ).await;
+
+    let log = repository_querying.log().await.unwrap();
+    assert_eq!(0, log.len());
+
+    let files = repository_querying.files().await.unwrap();
+    assert_eq!(0, files.len());
+}
+
+#[test]
+fn test_author_exclusions() {
+    let author_exclusions = AuthorExclusions::new(
+        &["renovate[bot]".to_owned(), "/^ci-.*@example\\.com$/".to_owned()]
+    ).unwrap();
+
+    assert!(author_exclusions.is_excluded("Renovate[bot]", None));
+    assert!(author_exclusions.is_excluded("Build Server", Some("ci-runner@example.com")));
+    assert!(!author_exclusions.is_excluded("Anton Jansson", Some("anton@example.com")));
+
+    let result = AuthorExclusions::new(&["renovate[bot]".to_owned(), "/(/".to_owned()]);
+    assert_eq!(
+        "Invalid pattern '/(/': regex parse error:\n    (\n    ^\nerror: unclosed group",
+        result.err().unwrap().to_string()
+    );
+}
+
+#[test]
+fn test_author_normalizer() {
+    let author_normalizer = AuthorNormalizer::new(
+        r#"
+        # Old names
+        ajansson => Anton Jansson
+        /^(?i)dependabot/ => Bots
+        <Anton@Example.com> => Anton Jansson
+        </@ci\.example\.com$/> => Build Server
+        "#
+    ).unwrap();
+
+    assert_eq!(Some("Anton Jansson"), author_normalizer.normalize("ajansson", None));
+    assert_eq!(Some("Bots"), author_normalizer.normalize("Dependabot[bot]", None));
+    assert_eq!(Some("Anton Jansson"), author_normalizer.normalize("anton", Some("anton@example.com")));
+    assert_eq!(Some("Build Server"), author_normalizer.normalize("runner", Some("runner-1@ci.example.com")));
+    assert_eq!(None, author_normalizer.normalize("Other", Some("")));
+
+    let result = AuthorNormalizer::new("ajansson => Anton Jansson\n/(/ => Anton Jansson");
+    assert_eq!(
+        "authors.txt, line 2: invalid pattern '(': regex parse error:\n    (\n    ^\nerror: unclosed group",
+        result.err().unwrap().to_string()
+    );
+
+    let result = AuthorNormalizer::new("# Aliases\nanton -> Anton Jansson");
+    assert_eq!(
+        "authors.txt, line 2: invalid alias 'anton -> Anton Jansson' (expected '<source> => <name>')",
+        result.err().unwrap().to_string()
+    );
+
+    assert_eq!("anton => Anton Jansson", AuthorNormalizer::create_alias_definition("anton", "Anton Jansson"));
+    assert_eq!("/^/anton/$/ => Anton Jansson", AuthorNormalizer::create_alias_definition("/anton/", "Anton Jansson"));
+}
+
+#[test]
+fn test_suggest_author_aliases() {
+    let identity = |name: &str, email: &str, num_revisions: u64| {
+        AuthorIdentity { name: name.to_owned(), email: email.to_owned(), num_revisions }
+    };
+
+    let suggestions = suggest_author_aliases(
+        &[
+            identity("Anton Jansson", "anton@example.com", 10),
+            identity("anton jansson", "anton@work.com", 2),
+            identity("Jansson, Anton", "", 1),
+            identity("AJ", "Anton@Example.com", 3),
+            identity("José Núñez", "jose@example.com", 4),
+            identity("Jose Nunez", "jose.nunez@example.com", 5),
+            identity("Someone Else", "someone@example.com", 7)
+        ]
+    );
+
+    assert_eq!(2, suggestions.len());
+
+    assert_eq!("Anton Jansson", suggestions[0].name);
+    assert_eq!(16, suggestions[0].num_revisions);
+    assert_eq!(
+        vec!["AJ", "anton jansson", "Jansson, Anton"],
+        suggestions[0].aliases.iter().map(|alias| alias.name.as_str()).collect::<Vec<_>>()
+    );
+
+    assert_eq!("Jose Nunez", suggestions[1].name);
+    assert_eq!("José Núñez", suggestions[1].aliases[0].name);
+    assert_eq!(vec!["jose@example.com"], suggestions[1].aliases[0].emails);
+}
+
+#[tokio::test]
+async fn test_author_normalization_by_pattern() {
+    let test_directory = create_test_data_directory();
+    let data_directory = test_directory.path();
+
+    let repository_querying = RepositoryQuerying::new(data_directory, RepositoryQueryingConfig::default()).await.unwrap();
+    assert_eq!(0, repository_querying.suggest_author_aliases().await.unwrap().len());
+
+    std::fs::write(data_directory.join("authors.txt"), "# Initials\n/^Anton\\s+J/ => A. Jansson").unwrap();
+    let repository_querying = RepositoryQuerying::new(data_directory, RepositoryQueryingConfig::default()).await.unwrap();
+
+    let log = repository_querying.log().await.unwrap();
+    assert_eq!(286, log.len());
+    assert!(log.iter().all(|entry| entry.author == "A. Jansson"));
+}
+
+#[test]
+fn test_ignore_file() {
+    let ignore_file = IgnoreFile::new(
+        r#"
+        # Generated files
+        *.lock
+        !Cargo.lock
+
+        build/
+        /docs/*.md
+        !docs/README.md
+        src/**/generated_*.rs
+        [Tt]emp?.txt
+        \#notes.txt
+        "#
+    ).unwrap();
+
+    assert!(ignore_file.is_ignored("yarn.lock"));
+    assert!(ignore_file.is_ignored("frontend/yarn.lock"));
+    assert!(!ignore_file.is_ignored("Cargo.lock"));
+
+    assert!(ignore_file.is_ignored("build/output.rs"));
+    assert!(ignore_file.is_ignored("tools/build/output.rs"));
+    assert!(!ignore_file.is_ignored("build"));
+
+    assert!(ignore_file.is_ignored("docs/guide.md"));
+    assert!(!ignore_file.is_ignored("docs/README.md"));
+    assert!(!ignore_file.is_ignored("docs/api/guide.md"));
+    assert!(!ignore_file.is_ignored("other/docs/guide.md"));
+
+    assert!(ignore_file.is_ignored("src/generated_parser.rs"));
+    assert!(ignore_file.is_ignored("src/parsing/generated_parser.rs"));
+    assert!(!ignore_file.is_ignored("tests/generated_parser.rs"));
+
+    assert!(ignore_file.is_ignored("Temp1.txt"));
+    assert!(ignore_file.is_ignored("data/temp2.txt"));
+    assert!(!ignore_file.is_ignored("temp12.txt"));
+    assert!(ignore_file.is_ignored("#notes.txt"));
+
+    // Files can't be re-included when the parent folder is ignored
+    let ignore_file = IgnoreFile::new("vendor/\n!vendor/keep.rs").unwrap();
+    assert!(ignore_file.is_ignored("vendor/keep.rs"));
+
+    let result = IgnoreFile::new("*.rs\n[abc");
+    assert_eq!("ignore.txt, line 2: invalid pattern '[abc'", result.err().unwrap().to_string());
+
+    let result = IgnoreFile::new("# Root\n!/");
+    assert_eq!("ignore.txt, line 2: empty pattern '!/'", result.err().unwrap().to_string());
+}
+
+#[tokio::test]
+async fn test_ignore_file_in_data_directory() {
+    let test_directory = create_test_data_directory();
+    let data_directory = test_directory.path();
+    std::fs::write(data_directory.join("ignore.txt"), "# Tests\ntestdata/\n*_tests.rs").unwrap();
+
+    let repository_querying = RepositoryQuerying::new(data_directory, RepositoryQueryingConfig::default()).await.unwrap();
+    let files = repository_querying.files().await.unwrap();
+    assert!(files.iter().all(|file| !file.name.starts_with("testdata/") && !file.name.ends_with("_tests.rs")));
+    assert!(files.len() < 50);
+}
+
 #[tokio::test]
 async fn test_files() {
     let repository_querying = create_querying().await;
@@ -48,6 +262,105 @@ async fn test_modules() {
     assert_eq!(3, entry.files.len());
 }
 
+#[tokio::test]
+async fn test_module_tree() {
+    let (_test_directory, repository_querying) = create_querying_with_modules(NESTED_MODULES).await;
+
+    let modules = repository_querying.modules().await.unwrap();
+    let module_tree = ModuleTree::from_modules(&modules);
+    assert_eq!(50, module_tree.num_files);
+
+    let backend = module_tree.children.iter().find(|child| child.name == "backend").unwrap();
+    assert!(!backend.is_module);
+    assert_eq!(17, backend.num_files);
+    assert_eq!(3, backend.children.len());
+    assert_eq!("backend/execution", backend.children[0].full_name);
+    assert!(backend.children[0].is_module);
+}
+
+#[tokio::test]
+async fn test_module_views() {
+    let test_directory = create_test_data_directory();
+    let data_directory = test_directory.path();
+    std::fs::write(data_directory.join("modules.layers.txt"), "src/**/* => core\ntestdata/**/* => core").unwrap();
+    assert_eq!(vec!["default".to_owned(), "layers".to_owned()], module_views(data_directory));
+
+    let config = RepositoryQueryingConfig { module_view: Some("layers".to_owned()), ..Default::default() };
+    let repository_querying = RepositoryQuerying::new(data_directory, config).await.unwrap();
+
+    let modules = repository_querying.modules().await.unwrap();
+    assert_eq!(
+        vec![".github/workflows", "<root>", "benches", "core", "samples", "tools"],
+        modules.iter().map(|module| module.name.as_str()).collect::<Vec<_>>()
+    );
+
+    let custom_analysis = repository_querying.custom_analysis(
+        r#"
+        SELECT
+            extract_module_name(file_name) AS layer,
+            extract_module_name(file_name, 'default') AS module_name
+        FROM latest_revision_file_entries
+        WHERE file_name = 'src/model.rs'
+        "#,
+        &QueryParameters::default(),
+        &CustomAnalysisLimits::default()
+    ).await.unwrap();
+
+    assert_eq!(1, custom_analysis.rows.len());
+    assert!(matches!(&custom_analysis.rows[0][0], CustomValue::String(Some(name)) if name == "core"));
+    assert!(matches!(&custom_analysis.rows[0][1], CustomValue::String(Some(name)) if name == "src"));
+
+    // Deriving the view from a querying of the default view gives the same result
+    let default_querying = RepositoryQuerying::new(data_directory, RepositoryQueryingConfig::default()).await.unwrap();
+    let derived_querying = default_querying.with_scope(Some("layers".to_owned()), None, None).await.unwrap();
+    assert_eq!(
+        modules.iter().map(|module| &module.name).collect::<Vec<_>>(),
+        derived_querying.modules().await.unwrap().iter().map(|module| &module.name).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        repository_querying.module_hotspots(None, None).await.unwrap().len(),
+        derived_querying.module_hotspots(None, None).await.unwrap().len()
+    );
+    assert_eq!(9, default_querying.modules().await.unwrap().len());
+
+    let result = repository_querying.custom_analysis(
+        "SELECT extract_module_name(file_name, 'teams') FROM latest_revision_file_entries",
+        &QueryParameters::default(),
+        &CustomAnalysisLimits::default()
+    ).await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_invalid_module_views() {
+    let test_directory = create_test_data_directory();
+    let data_directory = test_directory.path();
+
+    let config = RepositoryQueryingConfig { module_view: Some("teams".to_owned()), ..Default::default() };
+    let result = RepositoryQuerying::new(data_directory, config).await;
+    assert_eq!("Unknown module view 'teams'", result.err().unwrap().to_string());
+
+    // Only using the invalid view gives an error
+    std::fs::write(data_directory.join("modules.teams.txt"), "src/**/*").unwrap();
+    let repository_querying = RepositoryQuerying::new(data_directory, RepositoryQueryingConfig::default()).await.unwrap();
+    assert_eq!(9, repository_querying.modules().await.unwrap().len());
+
+    let result = repository_querying.with_scope(Some("teams".to_owned()), None, None).await;
+    assert_eq!(
+        "Module view 'teams': modules.teams.txt, line 1: invalid definition 'src/**/*' (expected '<pattern> => <module>')",
+        result.err().unwrap().to_string()
+    );
+
+    let result = repository_querying.custom_analysis(
+        "SELECT extract_module_name(file_name, 'teams') FROM latest_revision_file_entries",
+        &QueryParameters::default(),
+        &CustomAnalysisLimits::default()
+    ).await;
+    assert!(result.err().unwrap().to_string().contains("Module view 'teams': modules.teams.txt, line 1: invalid definition 'src/**/*' (expected '<pattern> => <module>')"));
+
+    assert!(module_definition_path(data_directory, "../modules").is_err());
+}
+
 #[tokio::test]
 async fn test_module_files() {
     let repository_querying = create_querying().await;
@@ -76,7 +389,7 @@ async fn test_file_hotspots() {
 async fn test_module_hotspots() {
     let repository_querying = create_querying().await;
 
-    let hotspots = repository_querying.module_hotspots(None).await.unwrap();
+    let hotspots = repository_querying.module_hotspots(None, None).await.unwrap();
     assert_eq!(13, hotspots.len());
 
     let entry = hotspots.first().unwrap();
@@ -84,6 +397,732 @@ async fn test_module_hotspots() {
     assert_eq!(5211, entry.num_code_lines);
 }
 
+#[tokio::test]
+async fn test_module_hotspots_at_depth() {
+    let (_test_directory, repository_querying) = create_querying_with_modules(NESTED_MODULES).await;
+
+    let hotspots = repository_querying.module_hotspots(None, None).await.unwrap();
+    assert_eq!(10, hotspots.len());
+
+    let hotspots = repository_querying.module_hotspots(None, Some(1)).await.unwrap();
+    assert_eq!(8, hotspots.len());
+
+    let entry = hotspots.first().unwrap();
+    assert_eq!("backend", entry.name);
+    assert_eq!(9704, entry.num_code_lines);
+}
+
+#[tokio::test]
+async fn test_date_range() {
+    let repository_querying = create_querying().await;
+    let all_hotspots = repository_querying.file_hotspots(None).await.unwrap();
+
+    let mut dates = repository_querying.log().await.unwrap().iter().map(|entry| entry.date).collect::<Vec<_>>();
+    dates.sort();
+    let min_date = dates[dates.len() / 2];
+
+    let config = RepositoryQueryingConfig { min_date: Some(min_date), ..Default::default() };
+    let repository_querying = create_querying_with_config(config).await;
+    let hotspots = repository_querying.file_hotspots(None).await.unwrap();
+
+    let num_revisions = |hotspots: &[HotspotEntry]| hotspots.iter().map(|entry| entry.num_revisions).sum::<u64>();
+    assert!(num_revisions(&hotspots) < num_revisions(&all_hotspots));
+    assert!(hotspots.len() <= all_hotspots.len());
+
+    let config = RepositoryQueryingConfig { min_date: Some(min_date), max_date: Some(min_date - 1), ..Default::default() };
+    let repository_querying = create_querying_with_config(config).await;
+    assert_eq!(0, repository_querying.file_hotspots(None).await.unwrap().len());
+}
+
+#[test]
+fn test_path_functions() {
+    assert_eq!("rs", file_extension("src/querying/engine.rs"));
+    assert_eq!("gz", file_extension("data/archive.tar.gz"));
+    assert_eq!("", file_extension("Makefile"));
+    assert_eq!("", file_extension("src/.gitignore"));
+
+    assert_eq!("engine.rs", base_name("src/querying/engine.rs"));
+    assert_eq!("main.rs", base_name("main.rs"));
+
+    assert_eq!(0, path_depth("main.rs"));
+    assert_eq!(2, path_depth("src/querying/engine.rs"));
+
+    assert_eq!("src", directory_at_depth("src/querying/engine.rs", 1));
+    assert_eq!("src/querying", directory_at_depth("src/querying/engine.rs", 2));
+    assert_eq!("src/querying", directory_at_depth("src/querying/engine.rs", 5));
+    assert_eq!("<root>", directory_at_depth("main.rs", 1));
+
+    assert_eq!(Some("Rust"), file_language("src/main.rs"));
+    assert_eq!(Some("TypeScript"), file_language("frontend/scripts/view.TSX"));
+    assert_eq!(Some("Dockerfile"), file_language("Dockerfile"));
+    assert_eq!(Some("Makefile"), file_language("tools/Makefile"));
+    assert_eq!(None, file_language("LICENSE"));
+}
+
+#[test]
+fn test_commit_message_functions() {
+    assert_eq!("Fix parsing of joins", commit_first_line("\n  Fix parsing of joins\n\nThe parser failed on nested joins."));
+    assert_eq!("", commit_first_line(""));
+
+    assert_eq!(Some("feat".to_owned()), conventional_commit_type("feat(parser): add support for joins"));
+    assert_eq!(Some("fix".to_owned()), conventional_commit_type("Fix!: breaking change"));
+    assert_eq!(Some("chore".to_owned()), conventional_commit_type("chore: bump version\n\nfix: not the type"));
+    assert_eq!(None, conventional_commit_type("Fixed the parser: joins work now"));
+    assert_eq!(None, conventional_commit_type("feat:missing space"));
+
+    assert_eq!(
+        vec!["PROJ-123", "#45", "AB2-7"],
+        issue_keys("PROJ-123: fix crash (closes #45, see AB2-7 and PROJ-123)")
+    );
+    assert!(issue_keys("Update utf-8 handling in A-1").is_empty());
+    assert!(issue_keys("No issue here").is_empty());
+}
+
+#[test]
+fn test_time_bucket() {
+    // 2025-01-01 00:30:00 UTC (a Wednesday in ISO week 1)
+    let timestamp = 1735691400;
+
+    assert_eq!(Some("2025-01-01T00:00".to_owned()), time_bucket(timestamp, "hour", &Tz::UTC));
+    assert_eq!(Some("2025-01-01".to_owned()), time_bucket(timestamp, "day", &Tz::UTC));
+    assert_eq!(Some("2025-W01".to_owned()), time_bucket(timestamp, "week", &Tz::UTC));
+    assert_eq!(Some("2025-01".to_owned()), time_bucket(timestamp, "month", &Tz::UTC));
+    assert_eq!(Some("2025-Q1".to_owned()), time_bucket(timestamp, "quarter", &Tz::UTC));
+    assert_eq!(Some("2025".to_owned()), time_bucket(timestamp, "year", &Tz::UTC));
+    assert_eq!(None, time_bucket(timestamp, "decade", &Tz::UTC));
+
+    let new_york = parse_timezone("America/New_York").unwrap();
+    assert_eq!(Some("2024-12-31".to_owned()), time_bucket(timestamp, "day", &new_york));
+    assert_eq!(Some("2024-Q4".to_owned()), time_bucket(timestamp, "quarter", &new_york));
+    assert!(parse_timezone("Mars/Olympus_Mons").is_none());
+}
+
+#[tokio::test]
+async fn test_helper_functions_in_custom_analysis() {
+    let repository_querying = create_querying_with_config(
+        RepositoryQueryingConfig {
+            timezone: Some("America/New_York".to_owned()),
+            ..Default::default()
+        }
+    ).await;
+
+    let custom_analysis = repository_querying.custom_analysis(
+        r#"
+        SELECT
+            file_extension(file_name),
+            base_name(file_name),
+            path_depth(file_name),
+            directory_at_depth(file_name, 1),
+            file_language(file_name),
+            glob_match(file_name, 'src/**/*.rs'),
+            glob_match(file_name, 'src/*.rs'),
+            commit_first_line('feat(parser): add joins' || chr(10) || chr(10) || 'Closes #12 and PROJ-7'),
+            conventional_commit_type('feat(parser): add joins'),
+            issue_keys('Closes #12 and PROJ-7'),
+            time_bucket(1735691400, 'day'),
+            time_bucket(1735691400, 'day', 'UTC')
+        FROM latest_revision_file_entries
+        WHERE file_name = 'src/execution/mod.rs'
+        "#,
+        &QueryParameters::default(),
+        &CustomAnalysisLimits::default()
+    ).await.unwrap();
+
+    assert_eq!(
+        json!([[
+            "rs",
+            "mod.rs",
+            2,
+            "src",
+            "Rust",
+            true,
+            false,
+            "feat(parser): add joins",
+            "feat",
+            ["#12", "PROJ-7"],
+            "2024-12-31",
+            "2025-01-01"
+        ]]),
+        serde_json::to_value(&custom_analysis.rows).unwrap()
+    );
+
+    let result = repository_querying.custom_analysis(
+        "SELECT time_bucket(date, 'decade') FROM git_log",
+        &QueryParameters::default(),
+        &CustomAnalysisLimits::default()
+    ).await;
+    assert!(result.is_err());
+
+    let result = RepositoryQuerying::new(
+        Path::new("test_data/sqlgrep"),
+        RepositoryQueryingConfig {
+            timezone: Some("Mars/Olympus_Mons".to_owned()),
+            ..Default::default()
+        }
+    ).await;
+    assert!(matches!(result, Err(QueryingError::UnknownTimezone(_))));
+}
+
+#[test]
+fn test_split_view_statements() {
+    let statements = split_statements(
+        r#"
+        -- Views; used by the reports
+        CREATE VIEW a AS SELECT 'x;y' AS "c;d";
+
+        /* Multi-line
+           comment; */
+        CREATE VIEW b AS
+        SELECT 'it''s' AS value -- trailing;
+        ;
+        "#
+    );
+
+    assert_eq!(
+        vec![
+            ViewStatement { line_number: 3, sql: r#"CREATE VIEW a AS SELECT 'x;y' AS "c;d""#.to_owned() },
+            ViewStatement { line_number: 7, sql: "CREATE VIEW b AS\n        SELECT 'it''s' AS value -- trailing;".to_owned() }
+        ],
+        statements
+    );
+}
+
+#[tokio::test]
+async fn test_user_views() {
+    let test_directory = create_test_data_directory();
+    let data_directory = test_directory.path();
+    std::fs::write(
+        data_directory.join(VIEWS_FILE),
+        "CREATE VIEW large_files AS SELECT file_name, num_code_lines FROM latest_revision_file_entries WHERE num_code_lines >= 500;"
+    ).unwrap();
+    std::fs::create_dir_all(data_directory.join(VIEWS_DIRECTORY)).unwrap();
+    std::fs::write(
+        data_directory.join(VIEWS_DIRECTORY).join("modules.sql"),
+        "CREATE VIEW large_file_modules AS SELECT DISTINCT extract_module_name(file_name) AS module_name FROM large_files"
+    ).unwrap();
+
+    let view_files = load_view_files(data_directory).unwrap();
+    assert_eq!(
+        vec!["views.sql", "views/modules.sql"],
+        view_files.iter().map(|view_file| view_file.name.as_str()).collect::<Vec<_>>()
+    );
+
+    let repository_querying = RepositoryQuerying::new(data_directory, RepositoryQueryingConfig::default()).await.unwrap();
+    let custom_analysis = repository_querying.custom_analysis(
+        "SELECT module_name FROM large_file_modules",
+        &QueryParameters::default(),
+        &CustomAnalysisLimits::default()
+    ).await.unwrap();
+    assert!(!custom_analysis.rows.is_empty());
+
+    let invalid_view_files = vec![
+        view_files[0].clone(),
+        ViewFile {
+            name: "views/modules.sql".to_owned(),
+            content: "CREATE VIEW a AS SELECT 1;\n\nCREATE VIEW b AS\nSELECT missing_column FROM large_files;".to_owned()
+        }
+    ];
+    let result = RepositoryQuerying::with_view_files(data_directory, RepositoryQueryingConfig::default(), &invalid_view_files).await;
+    assert!(
+        matches!(
+            result,
+            Err(QueryingError::ViewFile { file, line_number: 3, statement, .. }) if file == "views/modules.sql" && statement == "CREATE VIEW b AS"
+        )
+    );
+
+    for content in ["DROP VIEW git_log", "CREATE OR REPLACE VIEW git_log AS SELECT 1", "SELECT 1"] {
+        let view_files = vec![ViewFile { name: VIEWS_FILE.to_owned(), content: content.to_owned() }];
+        let result = RepositoryQuerying::with_view_files(data_directory, RepositoryQueryingConfig::default(), &view_files).await;
+        assert!(matches!(result, Err(QueryingError::ViewFile { line_number: 1, .. })));
+    }
+
+    assert!(view_file_path(data_directory, "views.sql").is_ok());
+    assert!(view_file_path(data_directory, "views/ownership.sql").is_ok());
+    assert!(view_file_path(data_directory, "views/../state.json").is_err());
+    assert!(view_file_path(data_directory, "other.sql").is_err());
+}
+
+#[tokio::test]
+async fn test_saved_queries() {
+    let test_directory = create_test_data_directory();
+    let data_directory = test_directory.path();
+    assert_eq!(0, list_saved_queries(data_directory).unwrap().len());
+
+    let saved_query = SavedQuery {
+        name: "largest-files".to_owned(),
+        description: "The files with the most code".to_owned(),
+        sql: "SELECT file_name, num_code_lines FROM latest_revision_file_entries ORDER BY num_code_lines DESC LIMIT 5".to_owned(),
+        chart: Some("bar".to_owned())
+    };
+    save_query(data_directory, &saved_query).unwrap();
+
+    let saved_queries = list_saved_queries(data_directory).unwrap();
+    assert_eq!(1, saved_queries.len());
+    assert_eq!("The files with the most code", saved_queries[0].description);
+    assert_eq!(Some("bar".to_owned()), saved_queries[0].chart);
+
+    let repository_querying = RepositoryQuerying::new(data_directory, RepositoryQueryingConfig::default()).await.unwrap();
+    let saved_query = load_saved_query(data_directory, "largest-files").unwrap();
+    repository_querying.validate_query(&saved_query.sql).await.unwrap();
+
+    let custom_analysis = repository_querying.custom_analysis(
+        &saved_query.sql,
+        &QueryParameters::default(),
+        &CustomAnalysisLimits::default()
+    ).await.unwrap();
+    assert_eq!(vec!["file_name", "num_code_lines"], custom_analysis.columns);
+    assert_eq!(5, custom_analysis.rows.len());
+
+    assert!(repository_querying.validate_query("SELECT missing FROM latest_revision_file_entries").await.is_err());
+    assert!(matches!(save_query(data_directory, &SavedQuery { name: "../x".to_owned(), ..saved_query }), Err(SavedQueryError::InvalidName(_))));
+
+    delete_saved_query(data_directory, "largest-files").unwrap();
+    assert!(matches!(load_saved_query(data_directory, "largest-files"), Err(SavedQueryError::NotFound(_))));
+}
+
+#[tokio::test]
+async fn test_schema() {
+    let repository_querying = create_querying().await;
+
+    let schema = repository_querying.schema().await.unwrap();
+
+    let table = schema.tables.iter().find(|table| table.name == "all_git_file_entries").unwrap();
+    assert!(!table.is_view);
+
+    let table = schema.tables.iter().find(|table| table.name == "module_developers").unwrap();
+    assert!(table.is_view);
+    assert_eq!(
+        vec!["module_name", "author", "net_added_lines"],
+        table.columns.iter().map(|column| column.name.as_str()).collect::<Vec<_>>()
+    );
+    assert_eq!("Utf8View", table.columns[0].data_type);
+
+    let function = schema.functions.iter().find(|function| function.name == "ratio").unwrap();
+    assert_eq!(vec!["ratio(Int64, Int64) -> Float64"], function.signatures);
+
+    let function = schema.functions.iter().find(|function| function.name == "extract_module_name").unwrap();
+    assert_eq!(
+        vec!["extract_module_name(Utf8) -> Utf8View", "extract_module_name(Utf8, Utf8) -> Utf8View"],
+        function.signatures
+    );
+
+    assert!(schema.functions.iter().all(|function| function.name != "lower"));
+}
+
+#[tokio::test]
+async fn test_custom_analysis_with_parameters() {
+    let repository_querying = create_querying().await;
+
+    let sql = r#"
+        SELECT file_name, num_code_lines
+        FROM latest_revision_file_entries
+        WHERE extract_module_name(file_name) = $module AND num_code_lines >= $min_code_lines
+        ORDER BY file_name
+    "#;
+
+    let parameters = QueryParameters::from_json(&json!({ "module": "execution", "min_code_lines": "100" })).unwrap();
+    let custom_analysis = repository_querying.custom_analysis(sql, &parameters, &CustomAnalysisLimits::default()).await.unwrap();
+    assert!(!custom_analysis.rows.is_empty());
+    assert!(custom_analysis.rows.iter().all(|row| matches!(&row[0], CustomValue::String(Some(name)) if name == "src/executor.rs" || name.starts_with("src/execution/"))));
+    assert!(custom_analysis.rows.iter().all(|row| matches!(&row[1], CustomValue::UInt64(Some(lines)) if *lines >= 100)));
+
+    let parameters = QueryParameters::from_json(&json!(["src/model.rs"])).unwrap();
+    let custom_analysis = repository_querying.custom_analysis(
+        "SELECT file_name FROM latest_revision_file_entries WHERE file_name = $1",
+        &parameters,
+        &CustomAnalysisLimits::default()
+    ).await.unwrap();
+    assert_eq!(1, custom_analysis.rows.len());
+
+    let parameters = QueryParameters::from_json(&json!({ "module": "execution" })).unwrap();
+    let result = repository_querying.custom_analysis(sql, &parameters, &CustomAnalysisLimits::default()).await;
+    assert_eq!("Missing value for query parameter '$min_code_lines'", result.err().unwrap().to_string());
+
+    let parameters = QueryParameters::from_json(&json!({ "module": "execution", "min_code_lines": "many" })).unwrap();
+    let result = repository_querying.custom_analysis(sql, &parameters, &CustomAnalysisLimits::default()).await;
+    assert!(matches!(result, Err(QueryingError::InvalidQueryParameter(name, _)) if name == "$min_code_lines"));
+
+    assert!(QueryParameters::from_json(&json!({ "module": ["execution"] })).is_err());
+}
+
+#[tokio::test]
+async fn test_custom_analysis_types() {
+    let repository_querying = create_querying().await;
+
+    let custom_analysis = repository_querying.custom_analysis(
+        r#"
+        SELECT
+            CAST(1 AS SMALLINT) AS int16,
+            CAST(2 AS SMALLINT UNSIGNED) AS uint16,
+            CAST('1.25' AS DECIMAL(10, 2)) AS decimal,
+            CAST('12345678901234567890.123456789' AS DECIMAL(38, 9)) AS large_decimal,
+            arrow_cast('text', 'LargeUtf8') AS large_text,
+            arrow_cast('text', 'Dictionary(Int32, Utf8)') AS dictionary_text,
+            CAST('2025-01-31' AS DATE) AS date,
+            to_timestamp(1738324800) AS timestamp,
+            arrow_cast(to_timestamp(1738324800), 'Timestamp(Second, Some("+01:00"))') AS timestamp_tz,
+            make_array(1, 2, 3) AS list,
+            named_struct('name', 'core', 'files', make_array('a.rs', 'b.rs')) AS struct,
+            NULL AS null_value
+        "#,
+        &QueryParameters::default(),
+        &CustomAnalysisLimits::default()
+    ).await.unwrap();
+
+    assert_eq!(12, custom_analysis.columns.len());
+    assert_eq!(
+        json!([[
+            1,
+            2,
+            "1.25",
+            "12345678901234567890.123456789",
+            "text",
+            "text",
+            "2025-01-31",
+            "2025-01-31T12:00:00",
+            "2025-01-31T12:00:00+01:00",
+            [1, 2, 3],
+            { "name": "core", "files": ["a.rs", "b.rs"] },
+            null
+        ]]),
+        serde_json::to_value(&custom_analysis.rows).unwrap()
+    );
+
+    let custom_analysis = repository_querying.custom_analysis(
+        "SELECT file_name FROM latest_revision_file_entries WHERE file_name = 'does-not-exist'",
+        &QueryParameters::default(),
+        &CustomAnalysisLimits::default()
+    ).await.unwrap();
+    assert_eq!(vec!["file_name"], custom_analysis.columns);
+    assert!(custom_analysis.rows.is_empty());
+
+    let result = repository_querying.custom_analysis(
+        "SELECT arrow_cast('abc', 'Binary') AS data",
+        &QueryParameters::default(),
+        &CustomAnalysisLimits::default()
+    ).await;
+    assert!(matches!(result, Err(QueryingError::UnsupportedColumnType(name, _)) if name == "data"));
+}
+
+#[tokio::test]
+async fn test_explain_custom_analysis() {
+    let repository_querying = create_querying().await;
+    let sql = "SELECT extract_module_name(file_name) AS module_name, COUNT(*) FROM git_file_entries GROUP BY module_name";
+
+    let query_plan = repository_querying.explain_custom_analysis(
+        sql,
+        &QueryParameters::default(),
+        false,
+        &CustomAnalysisLimits::default()
+    ).await.unwrap();
+    assert!(query_plan.logical_plan.contains("Aggregate"));
+    assert!(query_plan.physical_plan.contains("AggregateExec"));
+    assert!(query_plan.operators.is_none());
+
+    let query_plan = repository_querying.explain_custom_analysis(
+        sql,
+        &QueryParameters::default(),
+        true,
+        &CustomAnalysisLimits::default()
+    ).await.unwrap();
+    assert!(query_plan.physical_plan.contains("output_rows="));
+
+    let operators = query_plan.operators.unwrap();
+    assert!(!operators.operator.is_empty());
+    assert!(operators.metrics.iter().any(|metric| metric.name == "output_rows" && metric.value == 13));
+
+    let result = repository_querying.explain_custom_analysis(
+        "DROP VIEW git_log",
+        &QueryParameters::default(),
+        true,
+        &CustomAnalysisLimits::default()
+    ).await;
+    assert!(matches!(result, Err(QueryingError::QueryNotReadOnly(_))));
+}
+
+#[tokio::test]
+async fn test_custom_analysis_read_only() {
+    let repository_querying = create_querying().await;
+    let parameters = QueryParameters::default();
+    let limits = CustomAnalysisLimits::default();
+
+    let result = repository_querying.custom_analysis("DROP VIEW git_log", &parameters, &limits).await;
+    assert!(matches!(result, Err(QueryingError::QueryNotReadOnly(_))));
+
+    let result = repository_querying.custom_analysis(
+        "CREATE VIEW my_log AS SELECT * FROM git_log",
+        &parameters,
+        &limits
+    ).await;
+    assert!(matches!(result, Err(QueryingError::QueryNotReadOnly(_))));
+
+    let result = repository_querying.custom_analysis(
+        "INSERT INTO git_file_entries SELECT * FROM git_file_entries",
+        &parameters,
+        &limits
+    ).await;
+    assert!(matches!(result, Err(QueryingError::QueryNotReadOnly(_))));
+
+    let result = repository_querying.custom_analysis(
+        "INSERT INTO raw_git_log SELECT * FROM raw_git_log",
+        &parameters,
+        &limits
+    ).await;
+    assert!(matches!(result, Err(QueryingError::QueryNotReadOnly(_))));
+
+    let result = repository_querying.custom_analysis("SET datafusion.execution.batch_size = 1", &parameters, &limits).await;
+    assert!(matches!(result, Err(QueryingError::QueryNotReadOnly(_))));
+
+    let custom_analysis = repository_querying.custom_analysis(
+        "SELECT COUNT(*) FROM git_log",
+        &parameters,
+        &limits
+    ).await.unwrap();
+    assert_eq!(1, custom_analysis.rows.len());
+}
+
+#[tokio::test]
+async fn test_custom_analysis_limits() {
+    let repository_querying = create_querying().await;
+    let parameters = QueryParameters::default();
+    let sql = "SELECT file_name FROM latest_revision_file_entries";
+
+    let limits = CustomAnalysisLimits { max_rows: Some(10), ..Default::default() };
+    let result = repository_querying.custom_analysis(sql, &parameters, &limits).await;
+    assert!(matches!(result, Err(QueryingError::QueryMaxRowsExceeded(10))));
+
+    let limits = CustomAnalysisLimits { max_rows: Some(100000), ..Default::default() };
+    assert!(repository_querying.custom_analysis(sql, &parameters, &limits).await.is_ok());
+
+    let limits = CustomAnalysisLimits { timeout: Some(std::time::Duration::from_nanos(1)), ..Default::default() };
+    let result = repository_querying.custom_analysis(
+        "SELECT a.revision, b.revision FROM git_file_entries a CROSS JOIN git_file_entries b ORDER BY a.file_name, b.file_name",
+        &parameters,
+        &limits
+    ).await;
+    assert!(matches!(result, Err(QueryingError::QueryTimeout(_))));
+
+    let limits = CustomAnalysisLimits { memory_limit: Some(1024), ..Default::default() };
+    let result = repository_querying.custom_analysis(
+        "SELECT file_name, revision FROM git_file_entries ORDER BY file_name, revision",
+        &parameters,
+        &limits
+    ).await;
+    assert!(matches!(result, Err(QueryingError::QueryMemoryLimitExceeded(_))));
+}
+
+#[tokio::test]
+async fn test_export_analysis() {
+    let repository_querying = create_querying().await;
+    let hotspots = repository_querying.file_hotspots(Some(3)).await.unwrap();
+    let first_line = format!("{},{}", hotspots[0].name, hotspots[0].num_revisions);
+
+    let (schema, batches) = rows_to_record_batches(hotspots, ExportFormat::Csv).unwrap();
+    let batches = batches.collect::<Result<Vec<_>, _>>().unwrap();
+    assert_eq!("name", schema.field(0).name());
+    assert_eq!(3, batches.iter().map(|batch| batch.num_rows()).sum::<usize>());
+
+    let csv = export_to_bytes(ExportFormat::Csv, schema, &batches);
+    let csv = String::from_utf8(csv).unwrap();
+    let mut lines = csv.lines();
+    assert!(lines.next().unwrap().starts_with("name,num_revisions,num_authors,num_code_lines"));
+    assert!(lines.next().unwrap().starts_with(&first_line));
+    assert_eq!(2, lines.count());
+
+    let create_clusters = || vec![
+        CoChangeClusterEntry { names: vec!["src/a.rs".to_owned(), "src/b.rs".to_owned()], coupled_revisions: 5 }
+    ];
+
+    let (schema, batches) = rows_to_record_batches(create_clusters(), ExportFormat::Csv).unwrap();
+    let batches = batches.collect::<Result<Vec<_>, _>>().unwrap();
+    let csv = String::from_utf8(export_to_bytes(ExportFormat::Csv, schema, &batches)).unwrap();
+    assert_eq!("names,coupled_revisions\n\"[\"\"src/a.rs\"\",\"\"src/b.rs\"\"]\",5\n", csv);
+
+    let (schema, batches) = rows_to_record_batches(create_clusters(), ExportFormat::JsonLines).unwrap();
+    let batches = batches.collect::<Result<Vec<_>, _>>().unwrap();
+    let jsonl = String::from_utf8(export_to_bytes(ExportFormat::JsonLines, schema, &batches)).unwrap();
+    assert_eq!("{\"names\":[\"src/a.rs\",\"src/b.rs\"],\"coupled_revisions\":5}\n", jsonl);
+
+    let hotspots = repository_querying.file_hotspots(Some(3)).await.unwrap();
+    let (schema, batches) = rows_to_record_batches(hotspots, ExportFormat::Parquet).unwrap();
+    let batches = batches.collect::<Result<Vec<_>, _>>().unwrap();
+    let parquet = export_to_bytes(ExportFormat::Parquet, schema, &batches);
+    let reader = ParquetRecordBatchReaderBuilder::try_new(axum::body::Bytes::from(parquet)).unwrap().build().unwrap();
+    assert_eq!(3, reader.map(|batch| batch.unwrap().num_rows()).sum::<usize>());
+
+    // The columns are given by the row type, also when there are no rows
+    let (schema, batches) = rows_to_record_batches(Vec::<CouplingViolationEntry>::new(), ExportFormat::Csv).unwrap();
+    assert_eq!(0, batches.count());
+    let csv = String::from_utf8(export_to_bytes(ExportFormat::Csv, schema, &[])).unwrap();
+    assert_eq!(
+        "rule,left_name,right_name,coupled_revisions,num_left_revisions,num_right_revisions,coupling_ratio,revisions\n",
+        csv
+    );
+
+    // Nested values are only converted to strings for CSV
+    let (schema, _) = rows_to_record_batches(repository_querying.modules().await.unwrap(), ExportFormat::Csv).unwrap();
+    assert_eq!(&DataType::Utf8, schema.field_with_name("files").unwrap().data_type());
+
+    let (schema, batches) = rows_to_record_batches(create_clusters(), ExportFormat::Parquet).unwrap();
+    let batches = batches.collect::<Result<Vec<_>, _>>().unwrap();
+    let parquet = export_to_bytes(ExportFormat::Parquet, schema, &batches);
+    let mut reader = ParquetRecordBatchReaderBuilder::try_new(axum::body::Bytes::from(parquet)).unwrap().build().unwrap();
+    let batch = reader.next().unwrap().unwrap();
+    let names = batch.column_by_name("names").unwrap().as_list::<i32>().value(0);
+    assert_eq!(vec![Some("src/a.rs"), Some("src/b.rs")], names.as_string::<i32>().iter().collect::<Vec<_>>());
+
+    let modules = repository_querying.modules().await.unwrap();
+    let (schema, _) = rows_to_record_batches(modules, ExportFormat::Parquet).unwrap();
+    let DataType::List(file_field) = schema.field_with_name("files").unwrap().data_type() else {
+        panic!("Expected the files to be a list");
+    };
+    let DataType::Struct(file_fields) = file_field.data_type() else {
+        panic!("Expected the files to be structs");
+    };
+    assert_eq!(&DataType::Utf8, file_fields.find("name").unwrap().1.data_type());
+    assert_eq!(&DataType::UInt64, file_fields.find("num_code_lines").unwrap().1.data_type());
+}
+
+#[tokio::test]
+async fn test_export_custom_analysis() {
+    let repository_querying = create_querying().await;
+
+    let mut stream = repository_querying.custom_analysis_stream(
+        "SELECT file_name, num_code_lines FROM latest_revision_file_entries ORDER BY file_name",
+        &QueryParameters::default(),
+        &CustomAnalysisLimits { max_rows: Some(1), ..Default::default() }
+    ).await.unwrap();
+
+    let schema = stream.schema();
+    let mut batches = Vec::new();
+    while let Some(batch) = stream.next().await {
+        batches.push(batch.unwrap());
+    }
+
+    let csv = String::from_utf8(export_to_bytes(ExportFormat::Csv, schema, &batches)).unwrap();
+    assert_eq!("file_name,num_code_lines", csv.lines().next().unwrap());
+    assert_eq!(2, csv.lines().count());
+
+    let stream = repository_querying.custom_analysis_stream(
+        "SELECT make_array(1, 2) AS list",
+        &QueryParameters::default(),
+        &CustomAnalysisLimits::default()
+    ).await.unwrap();
+    let result = RecordBatchWriter::new(ExportFormat::Csv, stream.schema(), Vec::new());
+    assert!(matches!(result, Err(QueryingError::UnsupportedColumnType(name, _)) if name == "list"));
+}
+
+#[tokio::test]
+async fn test_file_coupling_graph() {
+    let repository_querying = create_querying().await;
+
+    let graph = repository_querying.file_coupling_graph(2, 0.3).await.unwrap();
+    assert_eq!("file_change_coupling", graph.name);
+    assert!(!graph.nodes.is_empty());
+    assert!(!graph.edges.is_empty());
+
+    for edge in &graph.edges {
+        assert!(edge.attributes["coupled_revisions"].as_u64().unwrap() >= 2);
+        assert!(edge.attributes["coupling_ratio"].as_f64().unwrap() >= 0.3);
+        assert!(graph.nodes.iter().any(|node| node.id == edge.source));
+        assert!(graph.nodes.iter().any(|node| node.id == edge.target));
+    }
+
+    for node in &graph.nodes {
+        assert!(node.attributes.contains_key("num_code_lines"));
+        assert!(node.attributes["num_revisions"].as_u64().unwrap() > 0);
+    }
+
+    let all_graph = repository_querying.file_coupling_graph(1, 0.0).await.unwrap();
+    assert!(all_graph.edges.len() > graph.edges.len());
+}
+
+#[tokio::test]
+async fn test_author_collaboration_graph() {
+    let repository_querying = create_querying().await;
+
+    let graph = repository_querying.author_collaboration_graph(1, 0.0).await.unwrap();
+    assert_eq!("author_collaboration", graph.name);
+    assert_eq!(1, graph.nodes.len());
+    assert_eq!(239, graph.nodes[0].attributes["num_revisions"].as_u64().unwrap());
+    assert!(graph.edges.is_empty());
+}
+
+#[test]
+fn test_author_collaboration_edges() {
+    let num_author_files = HashMap::from_iter(
+        [("alice", 10), ("bob", 6), ("carol", 20), ("dave", 2)].map(|(author, num_files)| (author.to_owned(), num_files))
+    );
+    let shared_files = || {
+        [("alice", "bob", 4), ("alice", "carol", 3), ("bob", "carol", 1), ("carol", "dave", 2)]
+            .map(|(left_author, right_author, shared_files)| (left_author.to_owned(), right_author.to_owned(), shared_files))
+            .to_vec()
+    };
+
+    let edges = author_collaboration_edges(&num_author_files, shared_files(), 2, 0.15);
+    assert_eq!(
+        vec![("alice", "bob", 4, 0.5), ("alice", "carol", 3, 0.2), ("carol", "dave", 2, 2.0 / 11.0)],
+        edges
+            .iter()
+            .map(|edge| {
+                (
+                    edge.source.as_str(),
+                    edge.target.as_str(),
+                    edge.attributes["shared_files"].as_u64().unwrap(),
+                    edge.attributes["collaboration_ratio"].as_f64().unwrap()
+                )
+            })
+            .collect::<Vec<_>>()
+    );
+
+    let edges = author_collaboration_edges(&num_author_files, shared_files(), 1, 0.3);
+    assert_eq!(vec![("alice", "bob")], edges.iter().map(|edge| (edge.source.as_str(), edge.target.as_str())).collect::<Vec<_>>());
+
+    let edges = author_collaboration_edges(&num_author_files, shared_files(), 1, 0.0);
+    assert_eq!(4, edges.len());
+}
+
+#[test]
+fn test_graph_formats() {
+    let graph = Graph {
+        name: "coupling".to_owned(),
+        nodes: vec![
+            GraphNode { id: "src/a<b>.rs".to_owned(), attributes: graph_attributes([("num_code_lines", json!(10)), ("language", json!("Rust"))]) },
+            GraphNode { id: "src/\"c\".rs".to_owned(), attributes: graph_attributes([("num_code_lines", json!(5)), ("language", json!("Rust"))]) }
+        ],
+        edges: vec![
+            GraphEdge {
+                source: "src/a<b>.rs".to_owned(),
+                target: "src/\"c\".rs".to_owned(),
+                attributes: graph_attributes([("coupled_revisions", json!(3)), ("coupling_ratio", json!(0.5))])
+            }
+        ]
+    };
+
+    let graphml = GraphFormat::GraphML.write(&graph);
+    assert!(graphml.contains("<key id=\"node_num_code_lines\" for=\"node\" attr.name=\"num_code_lines\" attr.type=\"long\"/>"));
+    assert!(graphml.contains("<key id=\"node_language\" for=\"node\" attr.name=\"language\" attr.type=\"string\"/>"));
+    assert!(graphml.contains("<key id=\"edge_coupling_ratio\" for=\"edge\" attr.name=\"coupling_ratio\" attr.type=\"double\"/>"));
+    assert!(graphml.contains("<node id=\"src/a&lt;b&gt;.rs\">"));
+    assert!(graphml.contains("<edge source=\"src/a&lt;b&gt;.rs\" target=\"src/&quot;c&quot;.rs\">"));
+    assert!(graphml.contains("<data key=\"edge_coupled_revisions\">3</data>"));
+
+    let dot = GraphFormat::Dot.write(&graph);
+    assert_eq!(
+        concat!(
+            "graph \"coupling\" {\n",
+            "    \"src/a<b>.rs\" [language=\"Rust\", num_code_lines=10];\n",
+            "    \"src/\\\"c\\\".rs\" [language=\"Rust\", num_code_lines=5];\n",
+            "    \"src/a<b>.rs\" -- \"src/\\\"c\\\".rs\" [coupled_revisions=3, coupling_ratio=0.5];\n",
+            "}\n"
+        ),
+        dot
+    );
+
+    assert_eq!(Some(GraphFormat::Dot), GraphFormat::from_name("dot"));
+    assert_eq!(None, GraphFormat::from_name("gexf"));
+}
+
 #[tokio::test]
 async fn test_file_change_couplings() {
     let repository_querying = create_querying().await;
@@ -97,6 +1136,46 @@ async fn test_file_change_couplings() {
     assert_eq!(25, entry.coupled_revisions);
 }
 
+#[tokio::test]
+async fn test_file_change_couplings_max_files_per_commit() {
+    let repository_querying = create_querying_with_config(
+        RepositoryQueryingConfig {
+            coupling_max_files_per_commit: Some(5),
+            ..RepositoryQueryingConfig::default()
+        }
+    ).await;
+
+    let change_couplings = repository_querying.file_change_couplings(None).await.unwrap();
+    assert_eq!(129, change_couplings.len());
+}
+
+#[tokio::test]
+async fn test_file_change_couplings_excluded_revisions() {
+    let repository_querying = create_querying_with_config(
+        RepositoryQueryingConfig {
+            coupling_excluded_revisions: vec!["6d5225d".to_owned()],
+            coupling_excluded_commit_message_patterns: vec!["^Update dependencies".to_owned()],
+            ..RepositoryQueryingConfig::default()
+        }
+    ).await;
+
+    let change_couplings = repository_querying.file_change_couplings(None).await.unwrap();
+    let entry = change_couplings.iter().find(|entry| entry.left_name == "Cargo.lock" && entry.right_name == "Cargo.toml").unwrap();
+    assert_eq!(21, entry.coupled_revisions);
+
+    // Blank and too short prefixes don't exclude any revisions
+    let repository_querying = create_querying_with_config(
+        RepositoryQueryingConfig {
+            coupling_excluded_revisions: vec!["".to_owned(), "  ".to_owned(), "6d5".to_owned()],
+            ..RepositoryQueryingConfig::default()
+        }
+    ).await;
+
+    let change_couplings = repository_querying.file_change_couplings(None).await.unwrap();
+    let entry = change_couplings.iter().find(|entry| entry.left_name == "Cargo.lock" && entry.right_name == "Cargo.toml").unwrap();
+    assert_eq!(25, entry.coupled_revisions);
+}
+
 #[tokio::test]
 async fn test_change_couplings_for_file() {
     let repository_querying = create_querying().await;
@@ -114,7 +1193,7 @@ async fn test_change_couplings_for_file() {
 async fn test_module_change_couplings() {
     let repository_querying = create_querying().await;
 
-    let change_couplings = repository_querying.module_change_couplings(None).await.unwrap();
+    let change_couplings = repository_querying.module_change_couplings(None, None).await.unwrap();
     assert_eq!(61, change_couplings.len());
 
     let entry = change_couplings.first().unwrap();
@@ -123,11 +1202,22 @@ async fn test_module_change_couplings() {
     assert_eq!(40, entry.coupled_revisions);
 }
 
+#[tokio::test]
+async fn test_module_change_couplings_at_depth() {
+    let (_test_directory, repository_querying) = create_querying_with_modules(NESTED_MODULES).await;
+
+    let change_couplings = repository_querying.module_change_couplings(None, Some(1)).await.unwrap();
+    assert!(change_couplings.iter().all(|entry| !entry.left_name.contains('/') && !entry.right_name.contains('/')));
+
+    let change_couplings = repository_querying.change_couplings_for_module("backend", None, Some(1)).await.unwrap();
+    assert!(change_couplings.iter().all(|entry| entry.left_name == "backend" && entry.right_name != "backend"));
+}
+
 #[tokio::test]
 async fn test_module_change_couplings_for_file() {
     let repository_querying = create_querying().await;
 
-    let change_couplings = repository_querying.change_couplings_for_module("execution", None).await.unwrap();
+    let change_couplings = repository_querying.change_couplings_for_module("execution", None, None).await.unwrap();
     assert_eq!(11, change_couplings.len());
 
     let entry = change_couplings.first().unwrap();
@@ -136,6 +1226,192 @@ async fn test_module_change_couplings_for_file() {
     assert_eq!(40, entry.coupled_revisions);
 }
 
+#[tokio::test]
+async fn test_file_co_change_clusters() {
+    let repository_querying = create_querying().await;
+
+    let clusters = repository_querying.file_co_change_clusters(10, 4, None).await.unwrap();
+    assert_eq!(44, clusters.len());
+
+    let entry = clusters.first().unwrap();
+    assert_eq!(
+        vec![
+            "src/execution/aggregate_execution.rs",
+            "src/model.rs",
+            "src/parsing/parser_tree_converter.rs",
+            "src/parsing/parser_tree_converter_tests.rs"
+        ],
+        entry.names
+    );
+    assert_eq!(10, entry.coupled_revisions);
+}
+
+#[tokio::test]
+async fn test_module_co_change_clusters() {
+    let repository_querying = create_querying().await;
+
+    let clusters = repository_querying.module_co_change_clusters(10, 3, None).await.unwrap();
+    assert_eq!(22, clusters.len());
+
+    let entry = clusters.first().unwrap();
+    assert_eq!(vec!["execution", "model", "parsing"], entry.names);
+    assert_eq!(28, entry.coupled_revisions);
+}
+
+#[tokio::test]
+async fn test_suggest_module_definition() {
+    let repository_querying = create_querying().await;
+
+    let suggestion = repository_querying.suggest_module_definition(5, 0.2).await.unwrap();
+    assert_eq!(3, suggestion.modules.len());
+
+    let entry = suggestion.modules.first().unwrap();
+    assert_eq!("src", entry.name);
+    assert_eq!(7, entry.files.len());
+
+    let module_definitions = ModuleDefinitions::new("modules.txt", &suggestion.definition).unwrap();
+    for module in &suggestion.modules {
+        for file_name in &module.files {
+            assert_eq!(Some(module.name.clone()), module_definitions.get_module(file_name));
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_compare_module_definition() {
+    let repository_querying = create_querying().await;
+
+    let suggestion = repository_querying.suggest_module_definition(5, 0.2).await.unwrap();
+    let comparison = repository_querying.compare_module_definition(&suggestion.definition).await.unwrap();
+    assert_eq!(50, comparison.num_files);
+    assert_eq!(28, comparison.num_changed_files);
+
+    let entry = comparison.files.iter().find(|entry| entry.file_name == "Cargo.lock").unwrap();
+    assert_eq!("build", entry.current_module);
+    assert_eq!("src", entry.suggested_module);
+}
+
+#[tokio::test]
+async fn test_validate_module_definition() {
+    let repository_querying = create_querying().await;
+
+    let definition = std::fs::read_to_string("test_data/sqlgrep/modules.txt").unwrap();
+    let validation = repository_querying.validate_module_definition(DEFAULT_MODULE_VIEW, &definition).await.unwrap();
+    assert_eq!(50, validation.num_files);
+    assert_eq!(validation.num_files, validation.num_matched_files + validation.unmatched_files.len() as u64);
+    assert_eq!(0, validation.shadowed_files.len());
+
+    let unused_rules = validation.unused_rules.iter().map(|rule| rule.line_number).collect::<Vec<_>>();
+    assert_eq!(vec![10, 15], unused_rules);
+
+    let validation = repository_querying.validate_module_definition(
+        DEFAULT_MODULE_VIEW,
+        "src/**/*.rs => core\nsrc/model.rs => model\nvendor/**/* => vendor"
+    ).await.unwrap();
+
+    assert_eq!(vec!["vendor/**/* => vendor"], validation.unused_rules.iter().map(|rule| rule.definition.as_str()).collect::<Vec<_>>());
+
+    assert_eq!(1, validation.shadowed_files.len());
+    let entry = &validation.shadowed_files[0];
+    assert_eq!("src/model.rs", entry.file_name);
+    assert_eq!("core", entry.module_name);
+    assert_eq!(1, entry.rule.line_number);
+    assert_eq!(2, entry.shadowed_rules[0].rule.line_number);
+    assert_eq!("model", entry.shadowed_rules[0].module_name);
+
+    let entry = validation.unmatched_files.iter().find(|entry| entry.file_name == "Cargo.toml").unwrap();
+    assert_eq!("<root>", entry.default_module);
+}
+
+#[tokio::test]
+async fn test_coupling_violations() {
+    let repository_querying = create_querying().await;
+
+    let violations = repository_querying.coupling_violations().await.unwrap();
+    assert_eq!(
+        vec![
+            ("forbid model <-> tests", "model", "tests", 19),
+            ("forbid cli <-> *", "build", "cli", 15),
+            ("forbid cli <-> *", "cli", "model", 15),
+            ("forbid cli <-> *", "cli", "parsing", 13),
+            ("forbid cli <-> *", "cli", "tests", 11),
+            ("forbid cli <-> *", "cli", "src", 7),
+            ("forbid cli <-> *", "benches", "cli", 6),
+            ("forbid cli <-> *", "<root>", "cli", 5),
+            ("forbid cli <-> *", "ci", "cli", 5),
+            ("forbid cli <-> *", "cli", "testdata", 3),
+            ("forbid cli <-> *", "cli", "tools", 1)
+        ],
+        violations
+            .iter()
+            .map(|entry| {
+                (
+                    entry.rule.as_str(),
+                    entry.left_name.as_str(),
+                    entry.right_name.as_str(),
+                    entry.coupled_revisions
+                )
+            })
+            .collect::<Vec<_>>()
+    );
+
+    let entry = violations.first().unwrap();
+    assert_eq!(19, entry.revisions.len());
+}
+
+#[test]
+fn test_module_definitions() {
+    let module_definitions = ModuleDefinitions::new(
+        "modules.txt",
+        r#"
+        # Services
+        regex: services/([^/]+)/.* => service:$1
+        regex: libs/(?P<name>[^/]+)/src/.* => lib:${name}
+
+        src/*.rs => core
+        prices/** => $prices
+        "#
+    ).unwrap();
+
+    assert_eq!(Some("service:payments".to_owned()), module_definitions.get_module("services/payments/api/handler.rs"));
+    assert_eq!(Some("lib:common".to_owned()), module_definitions.get_module("libs/common/src/lib.rs"));
+    assert_eq!(Some("core".to_owned()), module_definitions.get_module("src/main.rs"));
+    assert_eq!(None, module_definitions.get_module("docs/services/payments/README.md"));
+
+    // Only rules prefixed by 'regex:' are regexes, so a '$' in the module name of a glob is kept as is
+    assert_eq!(Some("$prices".to_owned()), module_definitions.get_module("prices/sek.rs"));
+}
+
+#[test]
+fn test_invalid_module_definitions() {
+    let result = ModuleDefinitions::new("modules.txt", "src/*.rs => core\n\nsrc/main.rs");
+    assert_eq!(
+        "modules.txt, line 3: invalid definition 'src/main.rs' (expected '<pattern> => <module>')",
+        result.err().unwrap().to_string()
+    );
+
+    let result = ModuleDefinitions::new("modules.txt", "# Services\nregex: services/([^/]+/.* => service:$1");
+    assert!(result.err().unwrap().to_string().starts_with("modules.txt, line 2: invalid regex 'services/([^/]+/.*':"));
+
+    let result = ModuleDefinitions::new("modules.layers.txt", "src/***.rs => core");
+    assert!(result.err().unwrap().to_string().starts_with("modules.layers.txt, line 1: invalid pattern 'src/***.rs':"));
+}
+
+#[test]
+fn test_invalid_architecture_rules() {
+    let result = ArchitectureRules::new("allow cli <-> execution\nforbid cli");
+    assert_eq!(
+        "architecture_rules.txt, line 2: invalid rule 'forbid cli' (expected 'allow <module> <-> <module>' or 'forbid <module> <-> <module>')",
+        result.err().unwrap().to_string()
+    );
+
+    let result = ArchitectureRules::new("# Layers\nforbid cli <-> ***");
+    assert_eq!(
+        "architecture_rules.txt, line 2: invalid pattern '***': Pattern syntax error near position 2: wildcards are either regular `*` or recursive `**`",
+        result.err().unwrap().to_string()
+    );
+}
+
 #[tokio::test]
 async fn test_file_sum_of_couplings() {
     let repository_querying = create_querying().await;
@@ -189,7 +1465,7 @@ async fn test_files_main_developer() {
 async fn test_modules_main_developer() {
     let repository_querying = create_querying().await;
 
-    let entries = repository_querying.modules_main_developer().await.unwrap();
+    let entries = repository_querying.modules_main_developer(None).await.unwrap();
     assert_eq!(13, entries.len());
 
     let entry = entries.first().unwrap();
@@ -198,11 +1474,70 @@ async fn test_modules_main_developer() {
     assert_eq!(10052, entry.net_added_lines);
 }
 
+#[tokio::test]
+async fn test_modules_main_developer_at_depth() {
+    let (_test_directory, repository_querying) = create_querying_with_modules(NESTED_MODULES).await;
+
+    let entries = repository_querying.modules_main_developer(Some(1)).await.unwrap();
+    assert_eq!(8, entries.len());
+    assert!(entries.iter().any(|entry| entry.name == "backend"));
+}
+
+#[tokio::test]
+async fn test_file_code_age() {
+    let repository_querying = create_querying().await;
+
+    let code_age = repository_querying.file_code_age(None).await.unwrap();
+    assert_eq!(50, code_age.len());
+    assert!(code_age.windows(2).all(|entries| entries[0].last_changed_date <= entries[1].last_changed_date));
+    assert!(code_age.windows(2).all(|entries| entries[0].age_days >= entries[1].age_days));
+
+    let summary = repository_querying.summary().await.unwrap();
+    let last_date = summary.last_commit.unwrap().date;
+    let newest = code_age.last().unwrap();
+    assert_eq!(((last_date - newest.last_changed_date) / 86400) as u64, newest.age_days);
+
+    assert_eq!(5, repository_querying.file_code_age(Some(5)).await.unwrap().len());
+}
+
+#[tokio::test]
+async fn test_modules_truck_factor() {
+    let repository_querying = create_querying().await;
+
+    let truck_factors = repository_querying.modules_truck_factor(None).await.unwrap();
+    assert_eq!(13, truck_factors.len());
+    assert!(truck_factors.windows(2).all(|entries| entries[0].name < entries[1].name));
+    assert!(truck_factors.iter().all(|entry| entry.truck_factor == 1 && entry.num_authors == 1));
+
+    assert_eq!(1, truck_factor(&[60, 30, 10]));
+    assert_eq!(2, truck_factor(&[50, 30, 20]));
+    assert_eq!(3, truck_factor(&[25, 25, 25, 25]));
+    assert_eq!(0, truck_factor(&[]));
+
+    // Net removed lines don't count
+    assert_eq!(0, truck_factor(&[-5]));
+    assert_eq!(0, truck_factor(&[-5, -3]));
+    assert_eq!(0, truck_factor(&[0, 0, 0]));
+    assert_eq!(1, truck_factor(&[10, -20]));
+    assert_eq!(2, truck_factor(&[20, 20, 10, -40]));
+}
+
+#[tokio::test]
+async fn test_commit_spread_at_depth() {
+    let (_test_directory, repository_querying) = create_querying_with_modules(NESTED_MODULES).await;
+
+    let entries = repository_querying.commit_spread(Some(1)).await.unwrap();
+    assert_eq!(8, entries.len());
+
+    let entry = entries.iter().find(|entry| entry.module_name == "backend").unwrap();
+    assert_eq!("Anton Jansson", entry.author);
+}
+
 #[tokio::test]
 async fn test_commit_spread() {
     let repository_querying = create_querying().await;
 
-    let entries = repository_querying.commit_spread().await.unwrap();
+    let entries = repository_querying.commit_spread(None).await.unwrap();
     assert_eq!(13, entries.len());
 
     let entry = entries.first().unwrap();
@@ -211,9 +1546,51 @@ async fn test_commit_spread() {
     assert_eq!(46, entry.num_revisions);
 }
 
-async fn create_querying() -> RepositoryQuerying {
+pub async fn create_querying() -> RepositoryQuerying {
+    create_querying_with_config(RepositoryQueryingConfig::default()).await
+}
+
+const NESTED_MODULES: &str = r#"
+src/execution/**/* => backend/execution
+src/parsing/**/* => backend/parsing
+src/model.rs => backend/model
+"#;
+
+// The querying reads from the returned data directory, so it must be kept alive as long as the querying
+async fn create_querying_with_modules(module_definition: &str) -> (TempDir, RepositoryQuerying) {
+    let test_directory = create_test_data_directory();
+    let data_directory = test_directory.path();
+    std::fs::write(data_directory.join("modules.txt"), module_definition).unwrap();
+
+    let repository_querying = RepositoryQuerying::new(data_directory, RepositoryQueryingConfig::default()).await.unwrap();
+    (test_directory, repository_querying)
+}
+
+// Copies the indexed repository into a new data directory (removed when dropped), so that it can be used with other definitions
+pub fn create_test_data_directory() -> TempDir {
+    let data_directory = tempfile::Builder::new().prefix("gitrends-test-").tempdir().unwrap();
+
+    for file_name in [GIT_LOG_PATH, GIT_FILE_ENTRIES_PATH] {
+        std::fs::copy(Path::new("test_data/sqlgrep").join(file_name), data_directory.path().join(file_name)).unwrap();
+    }
+
+    data_directory
+}
+
+fn export_to_bytes(format: ExportFormat, schema: SchemaRef, batches: &[RecordBatch]) -> Vec<u8> {
+    let mut output = Vec::new();
+    let mut writer = RecordBatchWriter::new(format, schema, &mut output).unwrap();
+    for batch in batches {
+        writer.write(batch).unwrap();
+    }
+    writer.finish().unwrap();
+
+    output
+}
+
+pub async fn create_querying_with_config(config: RepositoryQueryingConfig) -> RepositoryQuerying {
     RepositoryQuerying::new(
         Path::new("test_data/sqlgrep"),
-        RepositoryQueryingConfig::default()
+        config
     ).await.unwrap()
-}
\ No newline at end of file
+}
@@ -0,0 +1,96 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+const SAVED_QUERIES_DIRECTORY: &str = "saved_queries";
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SavedQuery {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub sql: String,
+    // How the result is best visualized (such as 'table', 'bar' or 'line')
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chart: Option<String>
+}
+
+// Saved queries are stored as one YAML file per query in the 'saved_queries' folder of the data directory
+pub fn list_saved_queries(data_directory: &Path) -> Result<Vec<SavedQuery>, SavedQueryError> {
+    let saved_queries_directory = data_directory.join(SAVED_QUERIES_DIRECTORY);
+    if !saved_queries_directory.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut saved_queries = Vec::new();
+    for entry in std::fs::read_dir(saved_queries_directory)? {
+        let path = entry?.path();
+        if path.extension().and_then(|extension| extension.to_str()) != Some("yaml") {
+            continue;
+        }
+
+        saved_queries.push(serde_yaml::from_str::<SavedQuery>(&std::fs::read_to_string(path)?)?);
+    }
+
+    saved_queries.sort_by(|left, right| left.name.cmp(&right.name));
+    Ok(saved_queries)
+}
+
+pub fn load_saved_query(data_directory: &Path, name: &str) -> Result<SavedQuery, SavedQueryError> {
+    let path = saved_query_path(data_directory, name)?;
+    if !path.exists() {
+        return Err(SavedQueryError::NotFound(name.to_owned()));
+    }
+
+    Ok(serde_yaml::from_str(&std::fs::read_to_string(path)?)?)
+}
+
+pub fn save_query(data_directory: &Path, saved_query: &SavedQuery) -> Result<(), SavedQueryError> {
+    let path = saved_query_path(data_directory, &saved_query.name)?;
+    std::fs::create_dir_all(data_directory.join(SAVED_QUERIES_DIRECTORY))?;
+    std::fs::write(path, serde_yaml::to_string(saved_query)?)?;
+    Ok(())
+}
+
+pub fn delete_saved_query(data_directory: &Path, name: &str) -> Result<(), SavedQueryError> {
+    let path = saved_query_path(data_directory, name)?;
+    if !path.exists() {
+        return Err(SavedQueryError::NotFound(name.to_owned()));
+    }
+
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+fn saved_query_path(data_directory: &Path, name: &str) -> Result<PathBuf, SavedQueryError> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err(SavedQueryError::InvalidName(name.to_owned()));
+    }
+
+    Ok(data_directory.join(SAVED_QUERIES_DIRECTORY).join(format!("{}.yaml", name)))
+}
+
+#[derive(Debug, Error)]
+pub enum SavedQueryError {
+    #[error("Saved query '{0}' not found")]
+    NotFound(String),
+    #[error("Invalid saved query name '{0}' (only letters, digits, '-' and '_' are allowed)")]
+    InvalidName(String),
+    #[error("I/O: {0}")]
+    IO(std::io::Error),
+    #[error("Format: {0}")]
+    Format(serde_yaml::Error)
+}
+
+impl From<std::io::Error> for SavedQueryError {
+    fn from(err: std::io::Error) -> Self {
+        SavedQueryError::IO(err)
+    }
+}
+
+impl From<serde_yaml::Error> for SavedQueryError {
+    fn from(err: serde_yaml::Error) -> Self {
+        SavedQueryError::Format(err)
+    }
+}
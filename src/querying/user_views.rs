@@ -0,0 +1,190 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use datafusion::logical_expr::{DdlStatement, LogicalPlan};
+use datafusion::prelude::SessionContext;
+
+use crate::querying::{QueryingError, QueryingResult};
+
+pub const VIEWS_FILE: &str = "views.sql";
+pub const VIEWS_DIRECTORY: &str = "views";
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ViewFile {
+    // Relative to the data directory, such as 'views.sql' or 'views/ownership.sql'
+    pub name: String,
+    pub content: String
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ViewStatement {
+    pub line_number: usize,
+    pub sql: String
+}
+
+// The 'views.sql' file is loaded first, followed by the '.sql' files of the 'views' folder in name order
+pub fn load_view_files(data_directory: &Path) -> QueryingResult<Vec<ViewFile>> {
+    let mut names = Vec::new();
+    if data_directory.join(VIEWS_FILE).exists() {
+        names.push(VIEWS_FILE.to_owned());
+    }
+
+    if let Ok(entries) = std::fs::read_dir(data_directory.join(VIEWS_DIRECTORY)) {
+        let mut directory_names = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.is_file() && path.extension().and_then(|extension| extension.to_str()) == Some("sql"))
+            .flat_map(|path| path.file_name().and_then(|name| name.to_str()).map(|name| format!("{}/{}", VIEWS_DIRECTORY, name)))
+            .collect::<Vec<_>>();
+        directory_names.sort();
+        names.extend(directory_names);
+    }
+
+    let mut view_files = Vec::new();
+    for name in names {
+        let content = std::fs::read_to_string(data_directory.join(&name))
+            .map_err(|err| QueryingError::ViewFileRead(name.clone(), err))?;
+        view_files.push(ViewFile { name, content });
+    }
+
+    Ok(view_files)
+}
+
+pub fn view_file_path(data_directory: &Path, name: &str) -> QueryingResult<PathBuf> {
+    if name == VIEWS_FILE {
+        return Ok(data_directory.join(VIEWS_FILE));
+    }
+
+    let valid_file_name = name
+        .strip_prefix(&format!("{}/", VIEWS_DIRECTORY))
+        .and_then(|name| name.strip_suffix(".sql"))
+        .filter(|name| !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+        .is_some();
+
+    if !valid_file_name {
+        return Err(QueryingError::InvalidViewFileName(name.to_owned()));
+    }
+
+    Ok(data_directory.join(name))
+}
+
+// Creates the views of the files in order, where only views not replacing the existing tables and views are allowed
+pub async fn create_views(ctx: &SessionContext, view_files: &[ViewFile]) -> QueryingResult<()> {
+    let existing_tables = ctx
+        .catalog("datafusion")
+        .and_then(|catalog| catalog.schema("public"))
+        .map(|schema| schema.table_names().into_iter().collect::<HashSet<_>>())
+        .unwrap_or_default();
+
+    for view_file in view_files {
+        for statement in split_statements(&view_file.content) {
+            let view_error = |message: String| {
+                QueryingError::ViewFile {
+                    file: view_file.name.clone(),
+                    line_number: statement.line_number,
+                    statement: statement.sql.lines().next().unwrap_or("").to_owned(),
+                    message
+                }
+            };
+
+            let logical_plan = ctx.state().create_logical_plan(&statement.sql).await
+                .map_err(|err| view_error(err.strip_backtrace()))?;
+
+            match &logical_plan {
+                LogicalPlan::Ddl(DdlStatement::CreateView(create_view)) => {
+                    if existing_tables.contains(create_view.name.table()) {
+                        return Err(view_error(format!("the view '{}' already exists", create_view.name.table())));
+                    }
+                }
+                _ => {
+                    return Err(view_error("only CREATE VIEW statements are allowed".to_owned()));
+                }
+            }
+
+            ctx.execute_logical_plan(logical_plan).await.map_err(|err| view_error(err.strip_backtrace()))?;
+        }
+    }
+
+    Ok(())
+}
+
+// Splits on ';' outside of strings, quoted identifiers and comments, where statements only containing comments are skipped
+pub fn split_statements(content: &str) -> Vec<ViewStatement> {
+    fn add_statement(statements: &mut Vec<ViewStatement>, content: &str, start: Option<(usize, usize)>, end: usize) {
+        if let Some((start_index, line_number)) = start {
+            statements.push(
+                ViewStatement {
+                    line_number,
+                    sql: content[start_index..end].trim().to_owned()
+                }
+            );
+        }
+    }
+
+    let mut statements = Vec::new();
+    let mut statement_start = None;
+    let mut line_number = 1;
+
+    let mut chars = content.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '\n' => {
+                line_number += 1;
+            }
+            '-' if chars.peek().map(|(_, next)| *next) == Some('-') => {
+                for (_, c) in chars.by_ref() {
+                    if c == '\n' {
+                        line_number += 1;
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek().map(|(_, next)| *next) == Some('*') => {
+                chars.next();
+                let mut previous = ' ';
+                for (_, c) in chars.by_ref() {
+                    if c == '\n' {
+                        line_number += 1;
+                    }
+
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+
+                    previous = c;
+                }
+            }
+            ';' => {
+                add_statement(&mut statements, content, statement_start.take(), index);
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                if statement_start.is_none() {
+                    statement_start = Some((index, line_number));
+                }
+
+                // Quoted strings and identifiers, where a doubled quote is an escaped quote
+                if c == '\'' || c == '"' {
+                    while let Some((_, next)) = chars.next() {
+                        if next == '\n' {
+                            line_number += 1;
+                        }
+
+                        if next == c {
+                            if chars.peek().map(|(_, next)| *next) == Some(c) {
+                                chars.next();
+                            } else {
+                                break;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    add_statement(&mut statements, content, statement_start, content.len());
+    statements
+}
@@ -0,0 +1,46 @@
+use thiserror::Error;
+
+use crate::querying::QueryingError;
+
+pub mod report;
+
+#[cfg(test)]
+pub mod reporting_tests;
+
+type ReportingResult<T> = Result<T, ReportingError>;
+
+#[derive(Debug, Error)]
+pub enum ReportingError {
+    #[error("I/O: {0}")]
+    IO(std::io::Error),
+    #[error("Querying: {0}")]
+    Querying(QueryingError),
+    #[error("Template: {0}")]
+    Template(askama::Error),
+    #[error("JSON: {0}")]
+    Json(serde_json::Error)
+}
+
+impl From<std::io::Error> for ReportingError {
+    fn from(err: std::io::Error) -> Self {
+        ReportingError::IO(err)
+    }
+}
+
+impl From<QueryingError> for ReportingError {
+    fn from(err: QueryingError) -> Self {
+        ReportingError::Querying(err)
+    }
+}
+
+impl From<askama::Error> for ReportingError {
+    fn from(err: askama::Error) -> Self {
+        ReportingError::Template(err)
+    }
+}
+
+impl From<serde_json::Error> for ReportingError {
+    fn from(err: serde_json::Error) -> Self {
+        ReportingError::Json(err)
+    }
+}
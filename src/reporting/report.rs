@@ -0,0 +1,283 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use askama::Template;
+use chrono::{DateTime, Local};
+use serde::Serialize;
+
+use crate::querying::engine::RepositoryQuerying;
+use crate::querying::model::{CodeAgeEntry, CommitSpreadEntry, HotspotEntry, MainDeveloperEntry, RepositorySummary};
+use crate::reporting::ReportingResult;
+use crate::web::app::WebAppConfig;
+
+pub const REPORT_FILE: &str = "index.html";
+pub const REPORT_DATA_FILE: &str = "report.json";
+
+// The upper bound (in days) of each code age bucket
+const CODE_AGE_BUCKETS: [(u64, &str); 6] = [
+    (30, "< 1 month"),
+    (91, "1-3 months"),
+    (182, "3-6 months"),
+    (365, "6-12 months"),
+    (730, "1-2 years"),
+    (u64::MAX, "> 2 years")
+];
+
+pub struct ReportConfig {
+    // The number of entries in each table of the report
+    pub count: usize,
+    pub change_coupling_min_commits: u64,
+    pub change_coupling_min_ratio: f64
+}
+
+impl ReportConfig {
+    pub fn from_web_app_config(config: &WebAppConfig) -> ReportConfig {
+        ReportConfig {
+            count: 20,
+            change_coupling_min_commits: config.change_coupling_min_commits,
+            change_coupling_min_ratio: config.change_coupling_min_ratio
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReportData {
+    pub generated: String,
+    pub summary: RepositorySummary,
+    pub hotspots: Vec<HotspotEntry>,
+    pub change_couplings: Vec<ReportChangeCoupling>,
+    pub main_developers: Vec<MainDeveloperEntry>,
+    pub commit_spread: Vec<ReportCommitSpread>,
+    pub code_age: Vec<ReportCodeAge>,
+    pub oldest_files: Vec<CodeAgeEntry>
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReportChangeCoupling {
+    pub left_name: String,
+    pub right_name: String,
+    pub coupled_revisions: u64,
+    pub coupling_ratio: f64
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReportCodeAge {
+    pub age: String,
+    pub num_files: u64,
+    pub num_code_lines: u64
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReportCommitSpread {
+    pub module_name: String,
+    pub num_authors: u64,
+    pub num_revisions: u64,
+    pub main_contributor: String,
+    pub main_contributor_revisions: u64
+}
+
+pub async fn create_report_data(repository_querying: &RepositoryQuerying, config: &ReportConfig) -> ReportingResult<ReportData> {
+    let change_couplings = repository_querying.file_change_couplings(None).await?
+        .into_iter()
+        .filter(|coupling| {
+            coupling.coupled_revisions >= config.change_coupling_min_commits
+                && coupling.coupling_ratio() >= config.change_coupling_min_ratio
+        })
+        .take(config.count)
+        .map(|coupling| {
+            ReportChangeCoupling {
+                coupling_ratio: coupling.coupling_ratio(),
+                left_name: coupling.left_name,
+                right_name: coupling.right_name,
+                coupled_revisions: coupling.coupled_revisions
+            }
+        })
+        .collect();
+
+    let mut main_developers = repository_querying.modules_main_developer(None).await?;
+    main_developers.truncate(config.count);
+
+    let mut code_age = CODE_AGE_BUCKETS
+        .iter()
+        .map(|(_, age)| ReportCodeAge { age: (*age).to_owned(), num_files: 0, num_code_lines: 0 })
+        .collect::<Vec<_>>();
+    let mut oldest_files = repository_querying.file_code_age(None).await?;
+    for entry in &oldest_files {
+        if let Some(bucket_index) = CODE_AGE_BUCKETS.iter().position(|(max_days, _)| entry.age_days < *max_days) {
+            code_age[bucket_index].num_files += 1;
+            code_age[bucket_index].num_code_lines += entry.num_code_lines;
+        }
+    }
+    oldest_files.truncate(config.count);
+
+    Ok(
+        ReportData {
+            generated: Local::now().format("%Y-%m-%d %H:%M").to_string(),
+            summary: repository_querying.summary().await?,
+            hotspots: repository_querying.file_hotspots(Some(config.count)).await?,
+            change_couplings,
+            main_developers,
+            commit_spread: summarize_commit_spread(repository_querying.commit_spread(None).await?, config.count),
+            code_age,
+            oldest_files
+        }
+    )
+}
+
+// Writes the report and its data to the output directory, where the report has no external dependencies
+pub fn write_report(report_data: &ReportData, output_directory: &Path) -> ReportingResult<()> {
+    std::fs::create_dir_all(output_directory)?;
+
+    let data_json = serde_json::to_string(report_data)?;
+    let template = ReportTemplate::new(report_data, &data_json);
+    std::fs::write(output_directory.join(REPORT_FILE), template.render()?)?;
+    std::fs::write(output_directory.join(REPORT_DATA_FILE), serde_json::to_string_pretty(report_data)?)?;
+
+    Ok(())
+}
+
+// The commit spread entries are ordered by module and number of revisions, so the first author is the main contributor
+fn summarize_commit_spread(entries: Vec<CommitSpreadEntry>, count: usize) -> Vec<ReportCommitSpread> {
+    let mut modules = BTreeMap::<String, ReportCommitSpread>::new();
+    for entry in entries {
+        let module = modules.entry(entry.module_name.clone()).or_insert_with(|| {
+            ReportCommitSpread {
+                module_name: entry.module_name.clone(),
+                num_authors: 0,
+                num_revisions: 0,
+                main_contributor: entry.author.clone(),
+                main_contributor_revisions: entry.num_revisions
+            }
+        });
+
+        module.num_authors += 1;
+        module.num_revisions += entry.num_revisions;
+    }
+
+    let mut commit_spread = modules.into_values().collect::<Vec<_>>();
+    commit_spread.sort_by(|a, b| b.num_revisions.cmp(&a.num_revisions).then(a.module_name.cmp(&b.module_name)));
+    commit_spread.truncate(count);
+    commit_spread
+}
+
+#[derive(Template)]
+#[template(path="report.html")]
+struct ReportTemplate<'a> {
+    data: &'a ReportData,
+    repository_name: &'a str,
+    period: String,
+    hotspot_chart: Vec<ChartBar>,
+    code_age_chart: Vec<ChartBar>,
+    change_couplings: Vec<[String; 4]>,
+    main_developers: Vec<[String; 4]>,
+    commit_spread: Vec<[String; 4]>,
+    oldest_files: Vec<[String; 3]>,
+    // Embedded in a script element, so '</' is escaped
+    data_json: String
+}
+
+struct ChartBar {
+    label: String,
+    value: String,
+    width: String
+}
+
+impl<'a> ReportTemplate<'a> {
+    fn new(data: &'a ReportData, data_json: &str) -> ReportTemplate<'a> {
+        let summary = &data.summary;
+        let period = match (&summary.first_commit, &summary.last_commit) {
+            (Some(first_commit), Some(last_commit)) => format!("{} - {}", format_date(first_commit.date), format_date(last_commit.date)),
+            _ => "N/A".to_owned()
+        };
+
+        let max_revisions = data.hotspots.iter().map(|hotspot| hotspot.num_revisions).max().unwrap_or(0);
+        let hotspot_chart = data.hotspots
+            .iter()
+            .map(|hotspot| ChartBar::new(&hotspot.name, hotspot.num_revisions, max_revisions))
+            .collect();
+
+        let max_code_lines = data.code_age.iter().map(|entry| entry.num_code_lines).max().unwrap_or(0);
+        let code_age_chart = data.code_age
+            .iter()
+            .map(|entry| ChartBar::new(&entry.age, entry.num_code_lines, max_code_lines))
+            .collect();
+
+        ReportTemplate {
+            data,
+            repository_name: &summary.data_directory,
+            period,
+            hotspot_chart,
+            code_age_chart,
+            change_couplings: data.change_couplings
+                .iter()
+                .map(|coupling| {
+                    [
+                        coupling.left_name.clone(),
+                        coupling.right_name.clone(),
+                        coupling.coupled_revisions.to_string(),
+                        format_percent(coupling.coupling_ratio)
+                    ]
+                })
+                .collect(),
+            main_developers: data.main_developers
+                .iter()
+                .map(|entry| {
+                    [
+                        entry.name.clone(),
+                        entry.main_developer.clone(),
+                        entry.net_added_lines.to_string(),
+                        format_percent(ratio(entry.net_added_lines as f64, entry.total_net_added_lines as f64))
+                    ]
+                })
+                .collect(),
+            commit_spread: data.commit_spread
+                .iter()
+                .map(|entry| {
+                    [
+                        entry.module_name.clone(),
+                        entry.num_authors.to_string(),
+                        entry.num_revisions.to_string(),
+                        format!(
+                            "{} ({})",
+                            entry.main_contributor,
+                            format_percent(ratio(entry.main_contributor_revisions as f64, entry.num_revisions as f64))
+                        )
+                    ]
+                })
+                .collect(),
+            oldest_files: data.oldest_files
+                .iter()
+                .map(|entry| [entry.name.clone(), format_date(entry.last_changed_date), entry.num_code_lines.to_string()])
+                .collect(),
+            data_json: data_json.replace("</", "<\\/")
+        }
+    }
+}
+
+impl ChartBar {
+    fn new(label: &str, value: u64, max_value: u64) -> ChartBar {
+        ChartBar {
+            label: label.to_owned(),
+            value: value.to_string(),
+            width: format!("{:.1}", 100.0 * ratio(value as f64, max_value as f64))
+        }
+    }
+}
+
+fn format_date(timestamp: i64) -> String {
+    DateTime::from_timestamp(timestamp, 0)
+        .map(|date| date.format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| "N/A".to_owned())
+}
+
+fn format_percent(value: f64) -> String {
+    format!("{:.0}%", 100.0 * value)
+}
+
+fn ratio(value: f64, total: f64) -> f64 {
+    if total > 0.0 {
+        value / total
+    } else {
+        0.0
+    }
+}
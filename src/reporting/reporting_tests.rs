@@ -0,0 +1,53 @@
+use crate::querying::querying_tests::create_querying;
+use crate::reporting::report::{create_report_data, write_report, ReportConfig, REPORT_DATA_FILE, REPORT_FILE};
+
+#[tokio::test]
+async fn test_create_report_data() {
+    let repository_querying = create_querying().await;
+
+    let report_data = create_report_data(&repository_querying, &create_report_config()).await.unwrap();
+    assert_eq!(286, report_data.summary.num_revisions);
+    assert_eq!(10, report_data.hotspots.len());
+    assert!(report_data.change_couplings.iter().all(|coupling| coupling.coupled_revisions >= 5 && coupling.coupling_ratio >= 0.3));
+    assert!(report_data.main_developers.len() <= 10);
+    assert_eq!(10, report_data.commit_spread.len());
+    assert!(report_data.commit_spread.windows(2).all(|entries| entries[0].num_revisions >= entries[1].num_revisions));
+
+    assert_eq!(6, report_data.code_age.len());
+    assert_eq!(50, report_data.code_age.iter().map(|entry| entry.num_files).sum::<u64>());
+    assert_eq!(10, report_data.oldest_files.len());
+    assert!(report_data.oldest_files.windows(2).all(|entries| entries[0].last_changed_date <= entries[1].last_changed_date));
+}
+
+#[tokio::test]
+async fn test_write_report() {
+    let repository_querying = create_querying().await;
+    let output_directory = tempfile::Builder::new().prefix("gitrends-report-test-").tempdir().unwrap();
+    let output_directory = output_directory.path();
+
+    let report_data = create_report_data(&repository_querying, &create_report_config()).await.unwrap();
+    write_report(&report_data, output_directory).unwrap();
+
+    let report = std::fs::read_to_string(output_directory.join(REPORT_FILE)).unwrap();
+    assert!(report.contains("<h2>Hotspots</h2>"));
+    assert!(report.contains("<h2>Code age</h2>"));
+    assert!(report.contains(&report_data.hotspots[0].name));
+    assert!(!report.contains("https://"));
+
+    let embedded_data = report
+        .split("<script type=\"application/json\" id=\"report-data\">").nth(1).unwrap()
+        .split("</script>").next().unwrap();
+    let embedded_data = serde_json::from_str::<serde_json::Value>(embedded_data).unwrap();
+    assert_eq!(286, embedded_data["summary"]["num_revisions"]);
+
+    let data = serde_json::from_str::<serde_json::Value>(&std::fs::read_to_string(output_directory.join(REPORT_DATA_FILE)).unwrap()).unwrap();
+    assert_eq!(embedded_data, data);
+}
+
+fn create_report_config() -> ReportConfig {
+    ReportConfig {
+        count: 10,
+        change_coupling_min_commits: 5,
+        change_coupling_min_ratio: 0.3
+    }
+}
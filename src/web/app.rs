@@ -2,18 +2,19 @@ use std::collections::HashMap;
 use std::net::{SocketAddr};
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Duration;
 
 use log::info;
 
 use arc_swap::ArcSwap;
-use chrono::Local;
+use chrono::{Local, NaiveDate};
 
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 
 use axum::response::{Html, IntoResponse, Response};
 use axum::{Json, Router};
-use axum::routing::{get, post, put};
+use axum::routing::{delete, get, post, put};
 use axum::extract::{Path, Query, State};
 
 use askama::Template;
@@ -24,9 +25,14 @@ use tokio::task::spawn_blocking;
 use tower_http::services::ServeDir;
 
 use crate::indexing::indexer;
-use crate::querying::engine::{RepositoryQuerying, RepositoryQueryingConfig};
-use crate::querying::model::{ChangeCouplingTree, HotspotTree, MainDeveloperTree};
+use crate::querying::data_transformers::{AuthorNormalizer, IgnoreFile, ModuleDefinitions};
+use crate::querying::saved_queries::{self, SavedQuery};
+use crate::querying::user_views::{self, ViewFile};
+use crate::querying::querying_helpers::QueryParameters;
+use crate::querying::engine::{module_definition_file_name, module_definition_path, module_views, CustomAnalysisLimits, RepositoryQuerying, RepositoryQueryingConfig, DEFAULT_MODULE_VIEW, MIN_EXCLUDED_REVISION_LENGTH};
+use crate::querying::model::{ChangeCouplingTree, HotspotTree, MainDeveloperTree, ModuleTree};
 use crate::web::{WebAppError, WebAppResult};
+use crate::web::export::{analysis_response, export_format, export_response, graph_response};
 
 #[derive(Clone, Deserialize)]
 pub struct WebAppConfig {
@@ -37,7 +43,34 @@ pub struct WebAppConfig {
     #[serde(default="default_change_coupling_min_ratio")]
     pub change_coupling_min_ratio: f64,
     #[serde(default="default_change_coupling_min_commits")]
-    pub change_coupling_min_commits: u64
+    pub change_coupling_min_commits: u64,
+    #[serde(default="default_co_change_cluster_max_size")]
+    pub co_change_cluster_max_size: usize,
+
+    #[serde(default="default_author_collaboration_min_shared_files")]
+    pub author_collaboration_min_shared_files: u64,
+    #[serde(default="default_author_collaboration_min_ratio")]
+    pub author_collaboration_min_ratio: f64,
+
+    #[serde(default="default_custom_analysis_timeout")]
+    pub custom_analysis_timeout: f64,
+    #[serde(default="default_custom_analysis_memory_limit")]
+    pub custom_analysis_memory_limit: usize,
+    #[serde(default="default_custom_analysis_max_rows")]
+    pub custom_analysis_max_rows: usize
+}
+
+impl WebAppConfig {
+    // A limit of zero means unlimited
+    pub fn custom_analysis_limits(&self) -> CustomAnalysisLimits {
+        CustomAnalysisLimits {
+            timeout: Some(self.custom_analysis_timeout)
+                .filter(|timeout| *timeout > 0.0)
+                .map(Duration::from_secs_f64),
+            memory_limit: Some(self.custom_analysis_memory_limit * 1024 * 1024).filter(|limit| *limit > 0),
+            max_rows: Some(self.custom_analysis_max_rows).filter(|max_rows| *max_rows > 0)
+        }
+    }
 }
 
 fn default_change_coupling_min_ratio() -> f64 {
@@ -48,23 +81,37 @@ fn default_change_coupling_min_commits() -> u64 {
     15
 }
 
+fn default_co_change_cluster_max_size() -> usize {
+    4
+}
+
+fn default_author_collaboration_min_shared_files() -> u64 {
+    5
+}
+
+fn default_author_collaboration_min_ratio() -> f64 {
+    0.1
+}
+
+fn default_custom_analysis_timeout() -> f64 {
+    30.0
+}
+
+// In megabytes
+fn default_custom_analysis_memory_limit() -> usize {
+    1024
+}
+
+fn default_custom_analysis_max_rows() -> usize {
+    100000
+}
+
 pub async fn main(config: WebAppConfig) {
     indexer::try_index_repository(&config.source_dir, &config.data_dir).unwrap();
     let persistent_state = PersistentWebAppState::load_from_file(&config.data_dir.join("state.json"))
         .unwrap_or_default();
 
-    let repository_querying = RepositoryQuerying::new(
-        &config.data_dir,
-        persistent_state.querying_config.clone()
-    ).await.unwrap();
-
-    let state = Arc::new(
-        WebAppState {
-            config: config.clone(),
-            persistent_state: Mutex::new(persistent_state),
-            repository_querying: ArcSwap::from(Arc::new(repository_querying))
-        }
-    );
+    let state = Arc::new(WebAppState::new(config.clone(), persistent_state).await.unwrap());
 
     let mut content_dir = std::path::Path::new("frontend/static");
     if !content_dir.exists() {
@@ -82,8 +129,32 @@ pub async fn main(config: WebAppConfig) {
         .route("/api/state/valid-date", get(get_valid_date))
         .route("/api/state/valid-date", put(set_valid_date))
 
+        .route("/api/state/coupling-filter", get(get_coupling_filter))
+        .route("/api/state/coupling-filter", put(set_coupling_filter))
+
+        .route("/api/state/excluded-authors", get(get_excluded_authors))
+        .route("/api/state/excluded-authors", put(set_excluded_authors))
+
+        .route("/api/state/timezone", get(get_timezone))
+        .route("/api/state/timezone", put(set_timezone))
+
         .route("/api/state/module-definition", get(get_module_definition))
         .route("/api/state/module-definition", put(set_module_definition))
+        .route("/api/state/module-definition/validation", get(get_module_definition_validation))
+        .route("/api/state/module-definition/validation", post(validate_module_definition))
+        .route("/api/state/module-views", get(get_module_views))
+
+        .route("/api/state/ignore", get(get_ignore_file))
+        .route("/api/state/ignore", put(set_ignore_file))
+
+        .route("/api/state/views", get(get_view_files))
+        .route("/api/state/views/{*name}", get(get_view_file))
+        .route("/api/state/views/{*name}", put(set_view_file))
+
+        .route("/api/state/authors", get(get_authors))
+        .route("/api/state/authors", put(set_authors))
+        .route("/api/state/authors/alias-suggestions", get(get_author_alias_suggestions))
+        .route("/api/state/authors/aliases", post(add_author_aliases))
 
         .route("/api/summary", get(get_summary))
 
@@ -94,21 +165,40 @@ pub async fn main(config: WebAppConfig) {
         .route("/api/file/hotspots-structure", get(get_file_hotspots_structure))
         .route("/api/file/change-coupling", get(get_file_change_coupling))
         .route("/api/file/change-coupling-structure", get(get_file_change_coupling_structure))
+        .route("/api/file/change-coupling-graph", get(get_file_change_coupling_graph))
+        .route("/api/file/co-change-clusters", get(get_file_co_change_clusters))
         .route("/api/file/sum-of-couplings", get(get_file_sum_of_couplings))
         .route("/api/file/history/{*file_name}", get(get_file_history))
         .route("/api/file/main-developer", get(get_files_main_developer))
+        .route("/api/file/code-age", get(get_file_code_age))
         .route("/api/file/main-developer-structure", get(get_files_main_developer_structure))
 
         .route("/api/module", get(get_modules))
+        .route("/api/module/tree", get(get_module_tree))
         .route("/api/module/files/{:module_name}", get(get_module_files))
         .route("/api/module/hotspots", get(get_module_hotspots))
         .route("/api/module/change-coupling", get(get_module_change_coupling))
         .route("/api/module/change-coupling-structure", get(get_module_change_coupling_structure))
+        .route("/api/module/change-coupling-graph", get(get_module_change_coupling_graph))
+        .route("/api/module/co-change-clusters", get(get_module_co_change_clusters))
+        .route("/api/module/coupling-violations", get(get_module_coupling_violations))
         .route("/api/module/sum-of-couplings", get(get_module_sum_of_couplings))
         .route("/api/module/main-developer", get(get_modules_main_developer))
         .route("/api/module/commit-spread", get(get_modules_commit_spread))
+        .route("/api/module/truck-factor", get(get_modules_truck_factor))
+        .route("/api/module/suggested-definition", get(get_suggested_module_definition))
+        .route("/api/module/suggested-definition/comparison", get(get_suggested_module_definition_comparison))
+
+        .route("/api/author/collaboration-graph", get(get_author_collaboration_graph))
 
         .route("/api/custom-analysis", post(post_custom_analysis))
+        .route("/api/custom-analysis/explain", post(post_custom_analysis_explain))
+        .route("/api/custom-analysis/schema", get(get_custom_analysis_schema))
+        .route("/api/custom-analysis/saved", get(get_saved_queries))
+        .route("/api/custom-analysis/saved/{name}", get(get_saved_query))
+        .route("/api/custom-analysis/saved/{name}", put(set_saved_query))
+        .route("/api/custom-analysis/saved/{name}", delete(delete_saved_query))
+        .route("/api/custom-analysis/saved/{name}/run", post(run_saved_query))
 
         .with_state(state.clone())
         ;
@@ -123,43 +213,104 @@ pub async fn main(config: WebAppConfig) {
     }
 }
 
-struct WebAppState {
+pub struct WebAppState {
     config: WebAppConfig,
     persistent_state: Mutex<PersistentWebAppState>,
     repository_querying: ArcSwap<RepositoryQuerying>
 }
 
 impl WebAppState {
+    pub async fn new(config: WebAppConfig, persistent_state: PersistentWebAppState) -> WebAppResult<WebAppState> {
+        let repository_querying = RepositoryQuerying::new(
+            &config.data_dir,
+            persistent_state.querying_config.clone()
+        ).await?;
+
+        Ok(
+            WebAppState {
+                config,
+                persistent_state: Mutex::new(persistent_state),
+                repository_querying: ArcSwap::from(Arc::new(repository_querying))
+            }
+        )
+    }
+
     pub async fn recreate_repository_querying(&self, persistent_state: &PersistentWebAppState) -> WebAppResult<()> {
-        self.repository_querying.store(Arc::new(
-            RepositoryQuerying::new(
-                &self.config.data_dir,
-                persistent_state.querying_config.clone()
-            ).await?
-        ));
+        let repository_querying = RepositoryQuerying::new(
+            &self.config.data_dir,
+            persistent_state.querying_config.clone()
+        ).await?;
+
+        self.repository_querying.store(Arc::new(repository_querying));
 
         Ok(())
     }
+
+    // The updated config is only persisted if a querying can be created from it (such as when patterns are valid)
+    async fn update_querying_config(&self, update: impl FnOnce(&mut RepositoryQueryingConfig)) -> WebAppResult<()> {
+        let mut persistent_state = self.persistent_state.lock().await;
+
+        let mut new_persistent_state = persistent_state.clone();
+        update(&mut new_persistent_state.querying_config);
+        self.recreate_repository_querying(&new_persistent_state).await?;
+
+        *persistent_state = new_persistent_state;
+        persistent_state.save_to_file(&self.config.data_dir.join("state.json"))
+            .map_err(WebAppError::PersistState)
+    }
+
+    // The querying of the module view given by the 'view' parameter (or the default view if not given), where the
+    // 'from' and 'to' parameters override the persisted date range for just this request
+    pub async fn querying(&self, query: &HashMap<String, String>) -> WebAppResult<Arc<RepositoryQuerying>> {
+        let view = query.get("view").filter(|view| view.as_str() != DEFAULT_MODULE_VIEW).cloned();
+        let min_date = parse_date_parameter(query, "from", false)?;
+        let max_date = parse_date_parameter(query, "to", true)?;
+
+        // Other scopes than the persisted one are derived from the current querying
+        let repository_querying = self.repository_querying.load_full();
+        if view.is_none() && min_date.is_none() && max_date.is_none() {
+            return Ok(repository_querying);
+        }
+
+        Ok(Arc::new(repository_querying.with_scope(view, min_date, max_date).await?))
+    }
 }
 
-#[derive(Clone, Deserialize, Serialize)]
+// Dates are either UNIX timestamps or on the form YYYY-MM-DD (in UTC), where the end date is inclusive
+pub fn parse_date_parameter(query: &HashMap<String, String>, name: &str, is_end: bool) -> WebAppResult<Option<i64>> {
+    let Some(value) = query.get(name).filter(|value| !value.is_empty()) else {
+        return Ok(None);
+    };
+
+    if let Ok(timestamp) = i64::from_str(value) {
+        return Ok(Some(timestamp));
+    }
+
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| WebAppError::InvalidParameter(name.to_owned(), value.clone()))?;
+
+    let date_time = if is_end {
+        date.and_hms_opt(23, 59, 59)
+    } else {
+        date.and_hms_opt(0, 0, 0)
+    };
+
+    Ok(date_time.map(|date_time| date_time.and_utc().timestamp()))
+}
+
+#[derive(Clone, Default, Deserialize, Serialize)]
 pub struct PersistentWebAppState {
     querying_config: RepositoryQueryingConfig
 }
 
-impl Default for PersistentWebAppState {
-    fn default() -> Self {
-        PersistentWebAppState {
-            querying_config: RepositoryQueryingConfig::default()
-        }
+impl PersistentWebAppState {
+    pub fn querying_config(&self) -> &RepositoryQueryingConfig {
+        &self.querying_config
     }
-}
 
-impl PersistentWebAppState {
     pub fn load_from_file(path: &std::path::Path) -> Option<PersistentWebAppState> {
         std::fs::read_to_string(path).ok()
-            .map(|state| serde_json::from_str::<PersistentWebAppState>(&state).ok())
-            .flatten()
+            .and_then(|state| serde_json::from_str::<PersistentWebAppState>(&state).ok())
     }
 
     pub fn save_to_file(&self, path: &std::path::Path) -> std::io::Result<()> {
@@ -236,22 +387,150 @@ async fn set_valid_date(
     persistent_state.querying_config.min_date = input.min_date;
     persistent_state.querying_config.max_date = input.max_date;
     persistent_state.save_to_file(&state.config.data_dir.join("state.json"))
-        .map_err(|err| WebAppError::PersistState(err))?;
+        .map_err(WebAppError::PersistState)?;
 
     state.recreate_repository_querying(&persistent_state).await?;
 
     Ok(Json(json!({ "success": true })))
 }
 
+#[derive(Serialize, Deserialize)]
+struct CouplingFilter {
+    max_files_per_commit: Option<u64>,
+    #[serde(default)]
+    excluded_revisions: Vec<String>,
+    #[serde(default)]
+    excluded_commit_message_patterns: Vec<String>
+}
+
+async fn get_coupling_filter(
+    State(state): State<Arc<WebAppState>>
+)  -> WebAppResult<impl IntoResponse> {
+    let persistent_state = state.persistent_state.lock().await;
+
+    Ok(
+        Json(
+            CouplingFilter {
+                max_files_per_commit: persistent_state.querying_config.coupling_max_files_per_commit,
+                excluded_revisions: persistent_state.querying_config.coupling_excluded_revisions.clone(),
+                excluded_commit_message_patterns: persistent_state.querying_config.coupling_excluded_commit_message_patterns.clone()
+            }
+        )
+    )
+}
+
+async fn set_coupling_filter(
+    State(state): State<Arc<WebAppState>>,
+    Json(input): Json<CouplingFilter>
+)  -> WebAppResult<impl IntoResponse> {
+    if let Some(revision) = input.excluded_revisions.iter().find(|revision| revision.trim().len() < MIN_EXCLUDED_REVISION_LENGTH) {
+        return Err(WebAppError::InvalidParameter("excluded_revisions".to_owned(), revision.clone()));
+    }
+
+    state.update_querying_config(|config| {
+        config.coupling_max_files_per_commit = input.max_files_per_commit;
+        config.coupling_excluded_revisions = input.excluded_revisions;
+        config.coupling_excluded_commit_message_patterns = input.excluded_commit_message_patterns;
+    }).await?;
+
+    Ok(Json(json!({ "success": true })))
+}
+
+#[derive(Serialize, Deserialize)]
+struct ExcludedAuthors {
+    excluded_authors: Vec<String>
+}
+
+async fn get_excluded_authors(
+    State(state): State<Arc<WebAppState>>
+)  -> WebAppResult<impl IntoResponse> {
+    let persistent_state = state.persistent_state.lock().await;
+
+    Ok(
+        Json(
+            ExcludedAuthors {
+                excluded_authors: persistent_state.querying_config.excluded_authors.clone()
+            }
+        )
+    )
+}
+
+async fn set_excluded_authors(
+    State(state): State<Arc<WebAppState>>,
+    Json(input): Json<ExcludedAuthors>
+)  -> WebAppResult<impl IntoResponse> {
+    state.update_querying_config(|config| config.excluded_authors = input.excluded_authors).await?;
+
+    Ok(Json(json!({ "success": true })))
+}
+
+#[derive(Serialize, Deserialize)]
+struct Timezone {
+    timezone: Option<String>
+}
+
+async fn get_timezone(
+    State(state): State<Arc<WebAppState>>
+)  -> WebAppResult<impl IntoResponse> {
+    let persistent_state = state.persistent_state.lock().await;
+
+    Ok(
+        Json(
+            Timezone {
+                timezone: persistent_state.querying_config.timezone.clone()
+            }
+        )
+    )
+}
+
+async fn set_timezone(
+    State(state): State<Arc<WebAppState>>,
+    Json(input): Json<Timezone>
+)  -> WebAppResult<impl IntoResponse> {
+    state.update_querying_config(|config| config.timezone = input.timezone).await?;
+
+    Ok(Json(json!({ "success": true })))
+}
+
 #[derive(Serialize, Deserialize)]
 struct ModuleDefinitionContent {
     content: String
 }
 
-async fn get_module_definition(
+async fn get_module_definition_validation(
+    State(state): State<Arc<WebAppState>>,
+    Query(query): Query<HashMap<String, String>>
+)  -> WebAppResult<impl IntoResponse> {
+    let view = query.get("view").map(|view| view.as_str()).unwrap_or(DEFAULT_MODULE_VIEW);
+    let module_definitions_path = module_definition_path(&state.config.data_dir, view)?;
+    let definition = std::fs::read_to_string(module_definitions_path).unwrap_or(String::new());
+
+    let repository_querying = state.repository_querying.load();
+    Ok(Json(repository_querying.validate_module_definition(view, &definition).await?))
+}
+
+async fn validate_module_definition(
+    State(state): State<Arc<WebAppState>>,
+    Query(query): Query<HashMap<String, String>>,
+    Json(input): Json<ModuleDefinitionContent>
+)  -> WebAppResult<impl IntoResponse> {
+    let view = query.get("view").map(|view| view.as_str()).unwrap_or(DEFAULT_MODULE_VIEW);
+    let repository_querying = state.repository_querying.load();
+    Ok(Json(repository_querying.validate_module_definition(view, &input.content).await?))
+}
+
+async fn get_module_views(
     State(state): State<Arc<WebAppState>>
 )  -> WebAppResult<impl IntoResponse> {
-    let module_definitions_path = state.config.data_dir.join("modules.txt");
+    Ok(Json(module_views(&state.config.data_dir)))
+}
+
+async fn get_module_definition(
+    State(state): State<Arc<WebAppState>>,
+    Query(query): Query<HashMap<String, String>>
+)  -> WebAppResult<impl IntoResponse> {
+    let view = query.get("view").map(|view| view.as_str()).unwrap_or(DEFAULT_MODULE_VIEW);
+    let module_definitions_path = module_definition_path(&state.config.data_dir, view)?;
 
     Ok(
         Json(
@@ -264,11 +543,14 @@ async fn get_module_definition(
 
 async fn set_module_definition(
     State(state): State<Arc<WebAppState>>,
+    Query(query): Query<HashMap<String, String>>,
     Json(input): Json<ModuleDefinitionContent>
 )  -> WebAppResult<impl IntoResponse> {
-    let module_definitions_path = state.config.data_dir.join("modules.txt");
+    let view = query.get("view").map(|view| view.as_str()).unwrap_or(DEFAULT_MODULE_VIEW);
+    let module_definitions_path = module_definition_path(&state.config.data_dir, view)?;
 
-    std::fs::write(module_definitions_path, input.content).map_err(|err| WebAppError::IO(err))?;
+    ModuleDefinitions::new(&module_definition_file_name(view)?, &input.content).map_err(|err| WebAppError::Querying(err.into()))?;
+    std::fs::write(module_definitions_path, input.content).map_err(WebAppError::IO)?;
 
     let persistent_state = state.persistent_state.lock().await;
     state.recreate_repository_querying(&persistent_state).await?;
@@ -276,44 +558,222 @@ async fn set_module_definition(
     Ok(Json(json!({ "success": true })))
 }
 
-async fn get_summary(
+#[derive(Serialize, Deserialize)]
+struct IgnoreFileContent {
+    content: String
+}
+
+async fn get_ignore_file(
+    State(state): State<Arc<WebAppState>>
+)  -> WebAppResult<impl IntoResponse> {
+    let ignore_file_path = state.config.data_dir.join("ignore.txt");
+
+    Ok(
+        Json(
+            IgnoreFileContent {
+                content: std::fs::read_to_string(ignore_file_path).unwrap_or(String::new())
+            }
+        )
+    )
+}
+
+async fn set_ignore_file(
+    State(state): State<Arc<WebAppState>>,
+    Json(input): Json<IgnoreFileContent>
+)  -> WebAppResult<impl IntoResponse> {
+    let ignore_file_path = state.config.data_dir.join("ignore.txt");
+
+    IgnoreFile::new(&input.content).map_err(|err| WebAppError::Querying(err.into()))?;
+    std::fs::write(ignore_file_path, input.content).map_err(WebAppError::IO)?;
+
+    let persistent_state = state.persistent_state.lock().await;
+    state.recreate_repository_querying(&persistent_state).await?;
+
+    Ok(Json(json!({ "success": true })))
+}
+
+async fn get_view_files(
+    State(state): State<Arc<WebAppState>>
+)  -> WebAppResult<impl IntoResponse> {
+    Ok(Json(user_views::load_view_files(&state.config.data_dir)?))
+}
+
+#[derive(Serialize, Deserialize)]
+struct ViewFileContent {
+    content: String
+}
+
+async fn get_view_file(
+    State(state): State<Arc<WebAppState>>,
+    Path(name): Path<String>
+)  -> WebAppResult<impl IntoResponse> {
+    let view_file_path = user_views::view_file_path(&state.config.data_dir, &name)?;
+
+    Ok(
+        Json(
+            ViewFileContent {
+                content: std::fs::read_to_string(view_file_path).unwrap_or(String::new())
+            }
+        )
+    )
+}
+
+// The views are validated by creating them together with the other view files before saving
+async fn set_view_file(
+    State(state): State<Arc<WebAppState>>,
+    Path(name): Path<String>,
+    Json(input): Json<ViewFileContent>
+)  -> WebAppResult<impl IntoResponse> {
+    let view_file_path = user_views::view_file_path(&state.config.data_dir, &name)?;
+
+    let mut view_files = user_views::load_view_files(&state.config.data_dir)?;
+    match view_files.iter_mut().find(|view_file| view_file.name == name) {
+        Some(view_file) => view_file.content = input.content.clone(),
+        None => {
+            view_files.push(ViewFile { name: name.clone(), content: input.content.clone() });
+            view_files.sort_by_key(|view_file| (view_file.name != user_views::VIEWS_FILE, view_file.name.clone()));
+        }
+    }
+
+    let persistent_state = state.persistent_state.lock().await;
+    RepositoryQuerying::with_view_files(
+        &state.config.data_dir,
+        persistent_state.querying_config.clone(),
+        &view_files
+    ).await?;
+
+    if let Some(parent) = view_file_path.parent() {
+        std::fs::create_dir_all(parent).map_err(WebAppError::IO)?;
+    }
+    std::fs::write(view_file_path, input.content).map_err(WebAppError::IO)?;
+
+    state.recreate_repository_querying(&persistent_state).await?;
+
+    Ok(Json(json!({ "success": true })))
+}
+
+#[derive(Serialize, Deserialize)]
+struct AuthorsContent {
+    content: String
+}
+
+async fn get_authors(
     State(state): State<Arc<WebAppState>>
+)  -> WebAppResult<impl IntoResponse> {
+    let authors_path = state.config.data_dir.join("authors.txt");
+
+    Ok(
+        Json(
+            AuthorsContent {
+                content: std::fs::read_to_string(authors_path).unwrap_or(String::new())
+            }
+        )
+    )
+}
+
+async fn set_authors(
+    State(state): State<Arc<WebAppState>>,
+    Json(input): Json<AuthorsContent>
+)  -> WebAppResult<impl IntoResponse> {
+    let authors_path = state.config.data_dir.join("authors.txt");
+
+    AuthorNormalizer::new(&input.content).map_err(|err| WebAppError::Querying(err.into()))?;
+
+    let persistent_state = state.persistent_state.lock().await;
+    std::fs::write(authors_path, input.content).map_err(WebAppError::IO)?;
+    state.recreate_repository_querying(&persistent_state).await?;
+
+    Ok(Json(json!({ "success": true })))
+}
+
+async fn get_author_alias_suggestions(
+    State(state): State<Arc<WebAppState>>,
+    Query(query): Query<HashMap<String, String>>
 ) -> WebAppResult<impl IntoResponse> {
-    let repository_querying = state.repository_querying.load();
+    let repository_querying = state.querying(&query).await?;
+
+    Ok(Json(repository_querying.suggest_author_aliases().await?))
+}
+
+#[derive(Deserialize)]
+struct AcceptedAuthorAlias {
+    alias: String,
+    name: String
+}
+
+#[derive(Deserialize)]
+struct AcceptedAuthorAliases {
+    aliases: Vec<AcceptedAuthorAlias>
+}
+
+// Appends the accepted aliases to the author definition
+async fn add_author_aliases(
+    State(state): State<Arc<WebAppState>>,
+    Json(input): Json<AcceptedAuthorAliases>
+)  -> WebAppResult<impl IntoResponse> {
+    let authors_path = state.config.data_dir.join("authors.txt");
+
+    // Held from the read to the write so that concurrent updates are not lost
+    let persistent_state = state.persistent_state.lock().await;
+    let mut content = std::fs::read_to_string(&authors_path).unwrap_or(String::new());
+    if !content.is_empty() && !content.ends_with('\n') {
+        content += "\n";
+    }
+
+    for alias in &input.aliases {
+        content += &AuthorNormalizer::create_alias_definition(&alias.alias, &alias.name);
+        content += "\n";
+    }
+
+    AuthorNormalizer::new(&content).map_err(|err| WebAppError::Querying(err.into()))?;
+    std::fs::write(authors_path, content).map_err(WebAppError::IO)?;
+    state.recreate_repository_querying(&persistent_state).await?;
+
+    Ok(Json(json!({ "success": true })))
+}
+
+async fn get_summary(
+    State(state): State<Arc<WebAppState>>,
+    Query(query): Query<HashMap<String, String>>
+) -> WebAppResult<impl IntoResponse> {
+    let repository_querying = state.querying(&query).await?;
 
     Ok(Json(repository_querying.summary().await?))
 }
 
 async fn get_git_log(
-    State(state): State<Arc<WebAppState>>
+    State(state): State<Arc<WebAppState>>,
+    Query(query): Query<HashMap<String, String>>
 ) -> WebAppResult<impl IntoResponse> {
-    let repository_querying = state.repository_querying.load();
+    let repository_querying = state.querying(&query).await?;
 
-    Ok(Json(repository_querying.log().await?))
+    analysis_response(&query, "git-log", repository_querying.log().await?)
 }
 
 async fn get_files(
-    State(state): State<Arc<WebAppState>>
+    State(state): State<Arc<WebAppState>>,
+    Query(query): Query<HashMap<String, String>>
 ) -> WebAppResult<impl IntoResponse> {
-    let repository_querying = state.repository_querying.load();
+    let repository_querying = state.querying(&query).await?;
 
-    Ok(Json(repository_querying.files().await?))
+    analysis_response(&query, "files", repository_querying.files().await?)
 }
 
 async fn get_file_hotspots(
     State(state): State<Arc<WebAppState>>,
     Query(query): Query<HashMap<String, String>>
 ) -> WebAppResult<impl IntoResponse> {
-    let repository_querying = state.repository_querying.load();
+    let repository_querying = state.querying(&query).await?;
 
-    let count = query.get("count").map(|x| usize::from_str(x).ok()).flatten();
-    Ok(Json(repository_querying.file_hotspots(count.or(Some(100))).await?))
+    let count = query.get("count").and_then(|x| usize::from_str(x).ok());
+    analysis_response(&query, "file-hotspots", repository_querying.file_hotspots(count.or(Some(100))).await?)
 }
 
 async fn get_file_hotspots_structure(
-    State(state): State<Arc<WebAppState>>
+    State(state): State<Arc<WebAppState>>,
+    Query(query): Query<HashMap<String, String>>
 ) -> WebAppResult<impl IntoResponse> {
-    let repository_querying = state.repository_querying.load();
+    let repository_querying = state.querying(&query).await?;
 
     let hotspots = repository_querying.file_hotspots(None).await?;
     let hotspot_tree = HotspotTree::from_vec(&hotspots);
@@ -324,26 +784,27 @@ async fn get_file_change_coupling(
     State(state): State<Arc<WebAppState>>,
     Query(query): Query<HashMap<String, String>>
 ) -> WebAppResult<impl IntoResponse> {
-    let repository_querying = state.repository_querying.load();
+    let repository_querying = state.querying(&query).await?;
 
     let file_name = query.get("name");
-    let count = query.get("count").map(|x| usize::from_str(x).ok()).flatten();
+    let count = query.get("count").and_then(|x| usize::from_str(x).ok());
 
     match file_name {
         Some(file_name) => {
-            Ok(Json(repository_querying.change_couplings_for_file(file_name, count).await?))
+            analysis_response(&query, "file-change-coupling", repository_querying.change_couplings_for_file(file_name, count).await?)
         }
         None => {
-            Ok(Json(repository_querying.file_change_couplings(count.or(Some(100))).await?))
+            analysis_response(&query, "file-change-coupling", repository_querying.file_change_couplings(count.or(Some(100))).await?)
         }
     }
 }
 
 async fn get_file_change_coupling_structure(
-    State(state): State<Arc<WebAppState>>
+    State(state): State<Arc<WebAppState>>,
+    Query(query): Query<HashMap<String, String>>
 ) -> WebAppResult<impl IntoResponse> {
     let config = &state.config;
-    let repository_querying = state.repository_querying.load();
+    let repository_querying = state.querying(&query).await?;
 
     let change_couplings = repository_querying.file_change_couplings(None).await?;
     let change_coupling_tree = ChangeCouplingTree::from_vec(
@@ -356,38 +817,87 @@ async fn get_file_change_coupling_structure(
     Ok(Json(change_coupling_tree))
 }
 
+async fn get_file_change_coupling_graph(
+    State(state): State<Arc<WebAppState>>,
+    Query(query): Query<HashMap<String, String>>
+) -> WebAppResult<impl IntoResponse> {
+    let config = &state.config;
+    let repository_querying = state.querying(&query).await?;
+
+    let graph = repository_querying.file_coupling_graph(
+        config.change_coupling_min_commits,
+        config.change_coupling_min_ratio
+    ).await?;
+
+    graph_response(&query, graph)
+}
+
+async fn get_file_co_change_clusters(
+    State(state): State<Arc<WebAppState>>,
+    Query(query): Query<HashMap<String, String>>
+) -> WebAppResult<impl IntoResponse> {
+    let config = &state.config;
+    let repository_querying = state.querying(&query).await?;
+
+    let min_support = query.get("min_support").and_then(|x| u64::from_str(x).ok());
+    let max_size = query.get("max_size").and_then(|x| usize::from_str(x).ok());
+    let count = query.get("count").and_then(|x| usize::from_str(x).ok());
+
+    let co_change_clusters = repository_querying.file_co_change_clusters(
+        min_support.unwrap_or(config.change_coupling_min_commits),
+        max_size.unwrap_or(config.co_change_cluster_max_size),
+        count.or(Some(100))
+    ).await?;
+
+    analysis_response(&query, "file-co-change-clusters", co_change_clusters)
+}
+
 async fn get_file_sum_of_couplings(
     State(state): State<Arc<WebAppState>>,
     Query(query): Query<HashMap<String, String>>
 ) -> WebAppResult<impl IntoResponse> {
-    let repository_querying = state.repository_querying.load();
+    let repository_querying = state.querying(&query).await?;
 
-    let count = query.get("count").map(|x| usize::from_str(x).ok()).flatten();
+    let count = query.get("count").and_then(|x| usize::from_str(x).ok());
 
-    Ok(Json(repository_querying.file_sum_of_couplings(count.or(Some(100))).await?))
+    analysis_response(&query, "file-sum-of-couplings", repository_querying.file_sum_of_couplings(count.or(Some(100))).await?)
 }
 
 async fn get_file_history(
     State(state): State<Arc<WebAppState>>,
+    Query(query): Query<HashMap<String, String>>,
     Path(file_name): Path<String>
 ) -> WebAppResult<impl IntoResponse> {
-    let repository_querying = state.repository_querying.load();
+    let repository_querying = state.querying(&query).await?;
 
-    Ok(Json(repository_querying.file_history(&file_name).await?))
+    analysis_response(&query, "file-history", repository_querying.file_history(&file_name).await?)
 }
 
 async fn get_files_main_developer(
-    State(state): State<Arc<WebAppState>>
+    State(state): State<Arc<WebAppState>>,
+    Query(query): Query<HashMap<String, String>>
 ) -> WebAppResult<impl IntoResponse> {
-    let repository_querying = state.repository_querying.load();
+    let repository_querying = state.querying(&query).await?;
 
-    Ok(Json(repository_querying.files_main_developer().await?))
+    analysis_response(&query, "file-main-developer", repository_querying.files_main_developer().await?)
+}
+
+async fn get_file_code_age(
+    State(state): State<Arc<WebAppState>>,
+    Query(query): Query<HashMap<String, String>>
+) -> WebAppResult<impl IntoResponse> {
+    let repository_querying = state.querying(&query).await?;
+
+    let count = query.get("count").and_then(|x| usize::from_str(x).ok());
+
+    analysis_response(&query, "file-code-age", repository_querying.file_code_age(count.or(Some(100))).await?)
 }
 
 async fn get_files_main_developer_structure(
-    State(state): State<Arc<WebAppState>>
+    State(state): State<Arc<WebAppState>>,
+    Query(query): Query<HashMap<String, String>>
 ) -> WebAppResult<impl IntoResponse> {
-    let repository_querying = state.repository_querying.load();
+    let repository_querying = state.querying(&query).await?;
 
     let main_developer_entries = repository_querying.files_main_developer().await?;
     let main_developer_tree = MainDeveloperTree::from_vec(&main_developer_entries);
@@ -395,58 +905,74 @@ async fn get_files_main_developer_structure(
 }
 
 async fn get_modules(
-    State(state): State<Arc<WebAppState>>
+    State(state): State<Arc<WebAppState>>,
+    Query(query): Query<HashMap<String, String>>
 ) -> WebAppResult<impl IntoResponse> {
-    let repository_querying = state.repository_querying.load();
+    let repository_querying = state.querying(&query).await?;
+
+    analysis_response(&query, "modules", repository_querying.modules().await?)
+}
+
+async fn get_module_tree(
+    State(state): State<Arc<WebAppState>>,
+    Query(query): Query<HashMap<String, String>>
+) -> WebAppResult<impl IntoResponse> {
+    let repository_querying = state.querying(&query).await?;
 
-    Ok(Json(repository_querying.modules().await?))
+    let modules = repository_querying.modules().await?;
+    Ok(Json(ModuleTree::from_modules(&modules)))
 }
 
 async fn get_module_files(
     State(state): State<Arc<WebAppState>>,
-    Path(module_name): Path<String>
+    Path(module_name): Path<String>,
+    Query(query): Query<HashMap<String, String>>
 ) -> WebAppResult<impl IntoResponse> {
-    let repository_querying = state.repository_querying.load();
+    let repository_querying = state.querying(&query).await?;
 
-    Ok(Json(repository_querying.module_files(&module_name).await?))
+    analysis_response(&query, "module-files", repository_querying.module_files(&module_name).await?)
 }
 
 async fn get_module_hotspots(
     State(state): State<Arc<WebAppState>>,
     Query(query): Query<HashMap<String, String>>
 ) -> WebAppResult<impl IntoResponse> {
-    let repository_querying = state.repository_querying.load();
+    let repository_querying = state.querying(&query).await?;
 
-    let count = query.get("count").map(|x| usize::from_str(x).ok()).flatten();
-    Ok(Json(repository_querying.module_hotspots(count.or(Some(100))).await?))
+    let count = query.get("count").and_then(|x| usize::from_str(x).ok());
+    let depth = query.get("depth").and_then(|x| usize::from_str(x).ok());
+    analysis_response(&query, "module-hotspots", repository_querying.module_hotspots(count.or(Some(100)), depth).await?)
 }
 
 async fn get_module_change_coupling(
     State(state): State<Arc<WebAppState>>,
     Query(query): Query<HashMap<String, String>>
 ) -> WebAppResult<impl IntoResponse> {
-    let repository_querying = state.repository_querying.load();
+    let repository_querying = state.querying(&query).await?;
 
     let module_name = query.get("name");
-    let count = query.get("count").map(|x| usize::from_str(x).ok()).flatten();
+    let count = query.get("count").and_then(|x| usize::from_str(x).ok());
+    let depth = query.get("depth").and_then(|x| usize::from_str(x).ok());
 
     match module_name {
         Some(module_name) => {
-            Ok(Json(repository_querying.change_couplings_for_module(module_name, count).await?))
+            analysis_response(&query, "module-change-coupling", repository_querying.change_couplings_for_module(module_name, count, depth).await?)
         }
         None => {
-            Ok(Json(repository_querying.module_change_couplings(count.or(Some(100))).await?))
+            analysis_response(&query, "module-change-coupling", repository_querying.module_change_couplings(count.or(Some(100)), depth).await?)
         }
     }
 }
 
 async fn get_module_change_coupling_structure(
-    State(state): State<Arc<WebAppState>>
+    State(state): State<Arc<WebAppState>>,
+    Query(query): Query<HashMap<String, String>>
 ) -> WebAppResult<impl IntoResponse> {
     let config = &state.config;
-    let repository_querying = state.repository_querying.load();
+    let repository_querying = state.querying(&query).await?;
 
-    let change_couplings = repository_querying.module_change_couplings(None).await?;
+    let depth = query.get("depth").and_then(|x| usize::from_str(x).ok());
+    let change_couplings = repository_querying.module_change_couplings(None, depth).await?;
     let change_coupling_tree = ChangeCouplingTree::from_vec(
         &change_couplings,
         false,
@@ -457,44 +983,278 @@ async fn get_module_change_coupling_structure(
     Ok(Json(change_coupling_tree))
 }
 
+async fn get_module_change_coupling_graph(
+    State(state): State<Arc<WebAppState>>,
+    Query(query): Query<HashMap<String, String>>
+) -> WebAppResult<impl IntoResponse> {
+    let config = &state.config;
+    let repository_querying = state.querying(&query).await?;
+
+    let depth = query.get("depth").and_then(|x| usize::from_str(x).ok());
+    let graph = repository_querying.module_coupling_graph(
+        depth,
+        config.change_coupling_min_commits,
+        config.change_coupling_min_ratio
+    ).await?;
+
+    graph_response(&query, graph)
+}
+
+async fn get_module_co_change_clusters(
+    State(state): State<Arc<WebAppState>>,
+    Query(query): Query<HashMap<String, String>>
+) -> WebAppResult<impl IntoResponse> {
+    let config = &state.config;
+    let repository_querying = state.querying(&query).await?;
+
+    let min_support = query.get("min_support").and_then(|x| u64::from_str(x).ok());
+    let max_size = query.get("max_size").and_then(|x| usize::from_str(x).ok());
+    let count = query.get("count").and_then(|x| usize::from_str(x).ok());
+
+    let co_change_clusters = repository_querying.module_co_change_clusters(
+        min_support.unwrap_or(config.change_coupling_min_commits),
+        max_size.unwrap_or(config.co_change_cluster_max_size),
+        count.or(Some(100))
+    ).await?;
+
+    analysis_response(&query, "module-co-change-clusters", co_change_clusters)
+}
+
+async fn get_module_coupling_violations(
+    State(state): State<Arc<WebAppState>>,
+    Query(query): Query<HashMap<String, String>>
+) -> WebAppResult<impl IntoResponse> {
+    let repository_querying = state.querying(&query).await?;
+
+    analysis_response(&query, "module-coupling-violations", repository_querying.coupling_violations().await?)
+}
+
 async fn get_module_sum_of_couplings(
     State(state): State<Arc<WebAppState>>,
     Query(query): Query<HashMap<String, String>>
 ) -> WebAppResult<impl IntoResponse> {
-    let repository_querying = state.repository_querying.load();
+    let repository_querying = state.querying(&query).await?;
 
-    let count = query.get("count").map(|x| usize::from_str(x).ok()).flatten();
+    let count = query.get("count").and_then(|x| usize::from_str(x).ok());
 
-    Ok(Json(repository_querying.module_sum_of_couplings(count.or(Some(100))).await?))
+    analysis_response(&query, "module-sum-of-couplings", repository_querying.module_sum_of_couplings(count.or(Some(100))).await?)
 }
 
 async fn get_modules_main_developer(
-    State(state): State<Arc<WebAppState>>
+    State(state): State<Arc<WebAppState>>,
+    Query(query): Query<HashMap<String, String>>
 ) -> WebAppResult<impl IntoResponse> {
-    let repository_querying = state.repository_querying.load();
+    let repository_querying = state.querying(&query).await?;
 
-    Ok(Json(repository_querying.modules_main_developer().await?))
+    let depth = query.get("depth").and_then(|x| usize::from_str(x).ok());
+    analysis_response(&query, "module-main-developer", repository_querying.modules_main_developer(depth).await?)
 }
 
 async fn get_modules_commit_spread(
-    State(state): State<Arc<WebAppState>>
+    State(state): State<Arc<WebAppState>>,
+    Query(query): Query<HashMap<String, String>>
 ) -> WebAppResult<impl IntoResponse> {
-    let repository_querying = state.repository_querying.load();
+    let repository_querying = state.querying(&query).await?;
+
+    let depth = query.get("depth").and_then(|x| usize::from_str(x).ok());
+    analysis_response(&query, "module-commit-spread", repository_querying.commit_spread(depth).await?)
+}
+
+async fn get_modules_truck_factor(
+    State(state): State<Arc<WebAppState>>,
+    Query(query): Query<HashMap<String, String>>
+) -> WebAppResult<impl IntoResponse> {
+    let repository_querying = state.querying(&query).await?;
+
+    let depth = query.get("depth").and_then(|x| usize::from_str(x).ok());
+    analysis_response(&query, "module-truck-factor", repository_querying.modules_truck_factor(depth).await?)
+}
+
+async fn get_suggested_module_definition(
+    State(state): State<Arc<WebAppState>>,
+    Query(query): Query<HashMap<String, String>>
+) -> WebAppResult<impl IntoResponse> {
+    let config = &state.config;
+    let repository_querying = state.querying(&query).await?;
+
+    let min_coupled_revisions = query.get("min_coupled_revisions").and_then(|x| u64::from_str(x).ok());
+    let min_coupling_ratio = query.get("min_coupling_ratio").and_then(|x| f64::from_str(x).ok());
+
+    Ok(
+        Json(
+            repository_querying.suggest_module_definition(
+                min_coupled_revisions.unwrap_or(config.change_coupling_min_commits),
+                min_coupling_ratio.unwrap_or(config.change_coupling_min_ratio)
+            ).await?
+        )
+    )
+}
+
+async fn get_suggested_module_definition_comparison(
+    State(state): State<Arc<WebAppState>>,
+    Query(query): Query<HashMap<String, String>>
+) -> WebAppResult<impl IntoResponse> {
+    let config = &state.config;
+    let repository_querying = state.querying(&query).await?;
+
+    let min_coupled_revisions = query.get("min_coupled_revisions").and_then(|x| u64::from_str(x).ok());
+    let min_coupling_ratio = query.get("min_coupling_ratio").and_then(|x| f64::from_str(x).ok());
+
+    let suggestion = repository_querying.suggest_module_definition(
+        min_coupled_revisions.unwrap_or(config.change_coupling_min_commits),
+        min_coupling_ratio.unwrap_or(config.change_coupling_min_ratio)
+    ).await?;
+
+    Ok(Json(repository_querying.compare_module_definition(&suggestion.definition).await?))
+}
+
+async fn get_author_collaboration_graph(
+    State(state): State<Arc<WebAppState>>,
+    Query(query): Query<HashMap<String, String>>
+) -> WebAppResult<impl IntoResponse> {
+    let config = &state.config;
+    let repository_querying = state.querying(&query).await?;
+
+    let graph = repository_querying.author_collaboration_graph(
+        config.author_collaboration_min_shared_files,
+        config.author_collaboration_min_ratio
+    ).await?;
 
-    Ok(Json(repository_querying.commit_spread().await?))
+    graph_response(&query, graph)
 }
 
 #[derive(Deserialize)]
 struct CustomAnalysisQuery {
-    query: String
+    query: String,
+    #[serde(default)]
+    parameters: serde_json::Value
 }
 
 async fn post_custom_analysis(
     State(state): State<Arc<WebAppState>>,
+    Query(parameters): Query<HashMap<String, String>>,
     Json(query): Json<CustomAnalysisQuery>,
+) -> WebAppResult<impl IntoResponse> {
+    let repository_querying = state.querying(&parameters).await?;
+
+    let query_parameters = QueryParameters::from_json(&query.parameters)?;
+    custom_analysis_response(&state, &repository_querying, &parameters, "custom-analysis", &query.query, &query_parameters).await
+}
+
+// Responds with the result as JSON, or streams it as a download if an export format is given by the 'format' parameter
+async fn custom_analysis_response(
+    state: &WebAppState,
+    repository_querying: &RepositoryQuerying,
+    query: &HashMap<String, String>,
+    name: &str,
+    sql: &str,
+    parameters: &QueryParameters
+) -> WebAppResult<Response> {
+    let limits = state.config.custom_analysis_limits();
+
+    match export_format(query)? {
+        Some(format) => {
+            let batches = repository_querying.custom_analysis_stream(sql, parameters, &limits).await?;
+            export_response(name, format, batches.schema(), batches, limits.timeout)
+        }
+        None => {
+            Ok(Json(repository_querying.custom_analysis(sql, parameters, &limits).await?).into_response())
+        }
+    }
+}
+
+async fn post_custom_analysis_explain(
+    State(state): State<Arc<WebAppState>>,
+    Query(parameters): Query<HashMap<String, String>>,
+    Json(query): Json<CustomAnalysisQuery>,
+) -> WebAppResult<impl IntoResponse> {
+    let repository_querying = state.querying(&parameters).await?;
+    let analyze = parameters.get("analyze").map(|analyze| analyze == "true").unwrap_or(false);
+
+    let parameters = QueryParameters::from_json(&query.parameters)?;
+    let query_plan = repository_querying.explain_custom_analysis(
+        &query.query,
+        &parameters,
+        analyze,
+        &state.config.custom_analysis_limits()
+    ).await?;
+    Ok(Json(query_plan))
+}
+
+async fn get_custom_analysis_schema(
+    State(state): State<Arc<WebAppState>>
+) -> WebAppResult<impl IntoResponse> {
+    let repository_querying = state.repository_querying.load();
+
+    Ok(Json(repository_querying.schema().await?))
+}
+
+async fn get_saved_queries(
+    State(state): State<Arc<WebAppState>>
+) -> WebAppResult<impl IntoResponse> {
+    Ok(Json(saved_queries::list_saved_queries(&state.config.data_dir)?))
+}
+
+async fn get_saved_query(
+    State(state): State<Arc<WebAppState>>,
+    Path(name): Path<String>
+) -> WebAppResult<impl IntoResponse> {
+    Ok(Json(saved_queries::load_saved_query(&state.config.data_dir, &name)?))
+}
+
+#[derive(Deserialize)]
+struct SavedQueryContent {
+    #[serde(default)]
+    description: String,
+    sql: String,
+    chart: Option<String>
+}
+
+async fn set_saved_query(
+    State(state): State<Arc<WebAppState>>,
+    Path(name): Path<String>,
+    Json(input): Json<SavedQueryContent>
 ) -> WebAppResult<impl IntoResponse> {
     let repository_querying = state.repository_querying.load();
+    repository_querying.validate_query(&input.sql).await?;
+
+    saved_queries::save_query(
+        &state.config.data_dir,
+        &SavedQuery {
+            name,
+            description: input.description,
+            sql: input.sql,
+            chart: input.chart
+        }
+    )?;
+
+    Ok(Json(json!({ "success": true })))
+}
+
+async fn delete_saved_query(
+    State(state): State<Arc<WebAppState>>,
+    Path(name): Path<String>
+) -> WebAppResult<impl IntoResponse> {
+    saved_queries::delete_saved_query(&state.config.data_dir, &name)?;
+    Ok(Json(json!({ "success": true })))
+}
+
+#[derive(Default, Deserialize)]
+struct SavedQueryParameters {
+    #[serde(default)]
+    parameters: serde_json::Value
+}
+
+async fn run_saved_query(
+    State(state): State<Arc<WebAppState>>,
+    Path(name): Path<String>,
+    Query(query): Query<HashMap<String, String>>,
+    input: Option<Json<SavedQueryParameters>>
+) -> WebAppResult<impl IntoResponse> {
+    let saved_query = saved_queries::load_saved_query(&state.config.data_dir, &name)?;
+    let repository_querying = state.querying(&query).await?;
 
-    let custom_analysis = repository_querying.custom_analysis(&query.query).await?;
-    Ok(Json(custom_analysis))
+    let Json(input) = input.unwrap_or_default();
+    let parameters = QueryParameters::from_json(&input.parameters)?;
+    custom_analysis_response(&state, &repository_querying, &query, &name, &saved_query.sql, &parameters).await
 }
\ No newline at end of file
@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use futures::{Stream, StreamExt};
+
+use axum::body::{Body, Bytes};
+use axum::http::header;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use tokio::sync::mpsc;
+
+use datafusion::arrow::array::RecordBatch;
+use datafusion::arrow::datatypes::SchemaRef;
+
+use crate::querying::export::{rows_to_record_batches, ExportFormat, ExportRow, RecordBatchWriter};
+use crate::querying::graph_export::GraphFormat;
+use crate::querying::model::Graph;
+use crate::querying::QueryingError;
+use crate::web::{WebAppError, WebAppResult};
+
+// The format given by the 'format' parameter, where JSON (the default) isn't an export format
+pub fn export_format(query: &HashMap<String, String>) -> WebAppResult<Option<ExportFormat>> {
+    match query.get("format").map(|format| format.as_str()) {
+        None | Some("json") => Ok(None),
+        Some(format) => {
+            ExportFormat::from_name(format)
+                .map(Some)
+                .ok_or_else(|| WebAppError::InvalidParameter("format".to_owned(), format.to_owned()))
+        }
+    }
+}
+
+// Responds with the rows as JSON, or as a download if an export format is given by the 'format' parameter
+pub fn analysis_response<T: ExportRow + Send + 'static>(
+    query: &HashMap<String, String>,
+    name: &str,
+    rows: Vec<T>
+) -> WebAppResult<Response> {
+    match export_format(query)? {
+        Some(format) => {
+            let (schema, batches) = rows_to_record_batches(rows, format)?;
+            export_response(name, format, schema, futures::stream::iter(batches), None)
+        }
+        None => Ok(Json(rows).into_response())
+    }
+}
+
+// Responds with the graph as JSON, or as a GraphML or DOT download if given by the 'format' parameter
+pub fn graph_response(query: &HashMap<String, String>, graph: Graph) -> WebAppResult<Response> {
+    let format = match query.get("format").map(|format| format.as_str()) {
+        None | Some("json") => return Ok(Json(graph).into_response()),
+        Some(format) => {
+            GraphFormat::from_name(format).ok_or_else(|| WebAppError::InvalidParameter("format".to_owned(), format.to_owned()))?
+        }
+    };
+
+    Ok(
+        (
+            [
+                (header::CONTENT_TYPE, format.content_type().to_owned()),
+                (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}.{}\"", graph.name, format.extension()))
+            ],
+            format.write(&graph)
+        ).into_response()
+    )
+}
+
+// Streams the batches as a download, where each batch is sent as soon as it has been written
+pub fn export_response<E: Into<QueryingError> + Send + 'static>(
+    name: &str,
+    format: ExportFormat,
+    schema: SchemaRef,
+    batches: impl Stream<Item=Result<RecordBatch, E>> + Send + 'static,
+    timeout: Option<Duration>
+) -> WebAppResult<Response> {
+    let output = SharedBuffer::default();
+    let writer = RecordBatchWriter::new(format, schema, output.clone())?;
+
+    let (sender, receiver) = mpsc::channel::<Result<Bytes, std::io::Error>>(16);
+    tokio::spawn(async move {
+        let write = write_batches(writer, output, batches, &sender);
+        let result = match timeout {
+            Some(timeout) => {
+                tokio::time::timeout(timeout, write).await
+                    .unwrap_or_else(|_| Err(QueryingError::QueryTimeout(timeout.as_secs_f64())))
+            }
+            None => write.await
+        };
+
+        // Aborts the download as the response has already started
+        if let Err(err) = result {
+            let _ = sender.send(Err(std::io::Error::other(err.to_string()))).await;
+        }
+    });
+
+    let body = Body::from_stream(
+        futures::stream::unfold(receiver, |mut receiver| async move {
+            receiver.recv().await.map(|bytes| (bytes, receiver))
+        })
+    );
+
+    Ok(
+        (
+            [
+                (header::CONTENT_TYPE, format.content_type().to_owned()),
+                (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}.{}\"", name, format.extension()))
+            ],
+            body
+        ).into_response()
+    )
+}
+
+async fn write_batches<E: Into<QueryingError>>(
+    mut writer: RecordBatchWriter<SharedBuffer>,
+    output: SharedBuffer,
+    batches: impl Stream<Item=Result<RecordBatch, E>>,
+    sender: &mpsc::Sender<Result<Bytes, std::io::Error>>
+) -> Result<(), QueryingError> {
+    let mut batches = std::pin::pin!(batches);
+    while let Some(batch) = batches.next().await {
+        writer.write(&batch.map_err(Into::into)?)?;
+
+        let bytes = output.take();
+        if !bytes.is_empty() && sender.send(Ok(bytes.into())).await.is_err() {
+            // The client is gone
+            return Ok(());
+        }
+    }
+
+    writer.finish()?;
+
+    let bytes = output.take();
+    if !bytes.is_empty() {
+        let _ = sender.send(Ok(bytes.into())).await;
+    }
+
+    Ok(())
+}
+
+// Buffer that the writer writes to and the response takes the written bytes from
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl SharedBuffer {
+    fn take(&self) -> Vec<u8> {
+        std::mem::take(&mut *self.0.lock().unwrap())
+    }
+}
+
+impl std::io::Write for SharedBuffer {
+    fn write(&mut self, buffer: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buffer);
+        Ok(buffer.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
@@ -7,13 +7,18 @@ use axum::response::{IntoResponse, Response};
 
 use crate::indexing::indexer::IndexError;
 use crate::querying::QueryingError;
+use crate::querying::saved_queries::SavedQueryError;
 
 pub mod app;
+mod export;
+
+#[cfg(test)]
+pub mod web_tests;
 
 type WebAppResult<T> = Result<T, WebAppError>;
 
 #[derive(Debug, Error)]
-enum WebAppError {
+pub enum WebAppError {
     #[error("I/O: {0}")]
     IO(std::io::Error),
     #[error("Failed to persist state due to: {0}")]
@@ -21,7 +26,11 @@ enum WebAppError {
     #[error("Indexing: {0}")]
     Indexing(IndexError),
     #[error("Querying: {0}")]
-    Querying(QueryingError)
+    Querying(QueryingError),
+    #[error("Invalid value '{1}' for parameter '{0}'")]
+    InvalidParameter(String, String),
+    #[error("Saved query: {0}")]
+    SavedQuery(SavedQueryError)
 }
 
 impl IntoResponse for WebAppError {
@@ -61,6 +70,32 @@ impl IntoResponse for WebAppError {
                 )
             }
             WebAppError::Querying(err) => {
+                let code = match &err {
+                    QueryingError::QueryNotReadOnly(_) => StatusCode::FORBIDDEN,
+                    QueryingError::QueryTimeout(_) => StatusCode::REQUEST_TIMEOUT,
+                    QueryingError::QueryMemoryLimitExceeded(_) | QueryingError::QueryMaxRowsExceeded(_) => {
+                        StatusCode::PAYLOAD_TOO_LARGE
+                    }
+                    _ => StatusCode::BAD_REQUEST
+                };
+
+                with_response_code(
+                    Json(
+                        json!({
+                            "success": false,
+                            "message": err.to_string()
+                        })
+                    ).into_response(),
+                    code
+                )
+            }
+            WebAppError::SavedQuery(err) => {
+                let code = match &err {
+                    SavedQueryError::NotFound(_) => StatusCode::NOT_FOUND,
+                    SavedQueryError::InvalidName(_) => StatusCode::BAD_REQUEST,
+                    _ => StatusCode::INTERNAL_SERVER_ERROR
+                };
+
                 with_response_code(
                     Json(
                         json!({
@@ -68,6 +103,17 @@ impl IntoResponse for WebAppError {
                             "message": err.to_string()
                         })
                     ).into_response(),
+                    code
+                )
+            }
+            WebAppError::InvalidParameter(..) => {
+                with_response_code(
+                    Json(
+                        json!({
+                            "success": false,
+                            "message": self.to_string()
+                        })
+                    ).into_response(),
                     StatusCode::BAD_REQUEST
                 )
             }
@@ -81,6 +127,12 @@ impl From<IndexError> for WebAppError {
     }
 }
 
+impl From<SavedQueryError> for WebAppError {
+    fn from(err: SavedQueryError) -> Self {
+        WebAppError::SavedQuery(err)
+    }
+}
+
 impl From<QueryingError> for WebAppError {
     fn from(err: QueryingError) -> Self {
         WebAppError::Querying(err)
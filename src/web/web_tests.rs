@@ -0,0 +1,75 @@
+use std::collections::HashMap;
+use std::path::Path;
+
+use chrono::NaiveDate;
+
+use crate::querying::engine::RepositoryQueryingConfig;
+use crate::querying::model::HotspotEntry;
+use crate::web::app::{parse_date_parameter, PersistentWebAppState, WebAppConfig, WebAppState};
+use crate::web::WebAppError;
+
+#[test]
+fn test_parse_date_parameter() {
+    let query = HashMap::from_iter([
+        ("timestamp".to_owned(), "1700000000".to_owned()),
+        ("date".to_owned(), "2024-02-29".to_owned()),
+        ("empty".to_owned(), "".to_owned()),
+        ("invalid".to_owned(), "2024-02-30".to_owned())
+    ]);
+
+    assert_eq!(Some(1700000000), parse_date_parameter(&query, "timestamp", false).unwrap());
+    assert_eq!(Some(1700000000), parse_date_parameter(&query, "timestamp", true).unwrap());
+
+    let start_of_day = NaiveDate::from_ymd_opt(2024, 2, 29).unwrap().and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp();
+    assert_eq!(Some(start_of_day), parse_date_parameter(&query, "date", false).unwrap());
+    assert_eq!(Some(start_of_day + 24 * 60 * 60 - 1), parse_date_parameter(&query, "date", true).unwrap());
+
+    assert_eq!(None, parse_date_parameter(&query, "empty", false).unwrap());
+    assert_eq!(None, parse_date_parameter(&query, "missing", false).unwrap());
+
+    let result = parse_date_parameter(&query, "invalid", false);
+    assert!(matches!(result, Err(WebAppError::InvalidParameter(name, value)) if name == "invalid" && value == "2024-02-30"));
+}
+
+#[tokio::test]
+async fn test_querying_date_range() {
+    let state = create_state(RepositoryQueryingConfig::default()).await;
+
+    let repository_querying = state.querying(&HashMap::new()).await.unwrap();
+    let mut dates = repository_querying.log().await.unwrap().iter().map(|entry| entry.date).collect::<Vec<_>>();
+    dates.sort();
+    let min_date = dates[dates.len() / 2];
+
+    let num_revisions = |hotspots: &[HotspotEntry]| hotspots.iter().map(|entry| entry.num_revisions).sum::<u64>();
+    let all_num_revisions = num_revisions(&repository_querying.file_hotspots(None).await.unwrap());
+
+    let query = HashMap::from_iter([("from".to_owned(), min_date.to_string())]);
+    let date_range_num_revisions = num_revisions(&state.querying(&query).await.unwrap().file_hotspots(None).await.unwrap());
+    assert!(0 < date_range_num_revisions && date_range_num_revisions < all_num_revisions);
+
+    let query = HashMap::from_iter([("from".to_owned(), min_date.to_string()), ("to".to_owned(), (min_date - 1).to_string())]);
+    assert_eq!(0, state.querying(&query).await.unwrap().file_hotspots(None).await.unwrap().len());
+
+    // Uses the current configuration rather than the one at the time of the first request
+    let query = HashMap::from_iter([("from".to_owned(), min_date.to_string())]);
+    let persistent_state = create_persistent_state(
+        RepositoryQueryingConfig { excluded_authors: vec!["Anton Jansson".to_owned()], ..Default::default() }
+    );
+    state.recreate_repository_querying(&persistent_state).await.unwrap();
+    assert_eq!(0, state.querying(&query).await.unwrap().file_hotspots(None).await.unwrap().len());
+
+    let query = HashMap::from_iter([("to".to_owned(), "yesterday".to_owned())]);
+    assert!(matches!(state.querying(&query).await, Err(WebAppError::InvalidParameter(_, _))));
+}
+
+async fn create_state(querying_config: RepositoryQueryingConfig) -> WebAppState {
+    let config = serde_json::from_value::<WebAppConfig>(
+        serde_json::json!({ "source_dir": ".", "data_dir": Path::new("test_data/sqlgrep") })
+    ).unwrap();
+
+    WebAppState::new(config, create_persistent_state(querying_config)).await.unwrap()
+}
+
+fn create_persistent_state(querying_config: RepositoryQueryingConfig) -> PersistentWebAppState {
+    serde_json::from_value(serde_json::json!({ "querying_config": querying_config })).unwrap()
+}